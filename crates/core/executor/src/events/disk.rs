@@ -0,0 +1,122 @@
+use std::{
+    fs::File,
+    io::{BufReader, BufWriter, Seek, SeekFrom, Write},
+    marker::PhantomData,
+};
+
+use serde::{de::DeserializeOwned, Serialize};
+
+/// An append-only buffer of events that lives in a file instead of memory.
+///
+/// Events are written with bincode as they are pushed and read back in the same order, so
+/// executions that produce millions of precompile events do not need to hold them all in RAM.
+pub struct DiskEventBuffer<T> {
+    writer: BufWriter<File>,
+    len: usize,
+    _marker: PhantomData<T>,
+}
+
+impl<T: Serialize + DeserializeOwned> DiskEventBuffer<T> {
+    /// Creates an empty buffer backed by `file`, which is truncated.
+    pub fn new(file: File) -> std::io::Result<Self> {
+        file.set_len(0)?;
+        Ok(Self { writer: BufWriter::new(file), len: 0, _marker: PhantomData })
+    }
+
+    /// Appends an event to the end of the buffer.
+    pub fn push(&mut self, event: &T) -> bincode::Result<()> {
+        bincode::serialize_into(&mut self.writer, event)?;
+        self.len += 1;
+        Ok(())
+    }
+
+    /// The number of events in the buffer.
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Whether the buffer has no events.
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Consumes the buffer and returns an iterator that reads the events back in push order, one
+    /// at a time.
+    ///
+    /// Each item fails if its event can no longer be read from the file.
+    pub fn read_back(self) -> bincode::Result<impl Iterator<Item = bincode::Result<T>>> {
+        let mut file = self.writer.into_inner().map_err(std::io::IntoInnerError::into_error)?;
+        file.flush()?;
+        file.seek(SeekFrom::Start(0))?;
+        let mut reader = BufReader::new(file);
+        Ok((0..self.len).map(move |_| bincode::deserialize_from(&mut reader)))
+    }
+
+    /// Consumes the buffer and reads all the events back into memory.
+    pub fn into_events(self) -> bincode::Result<Vec<T>> {
+        self.read_back()?.collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use sp1_curves::{weierstrass::secp256k1::Secp256k1, EllipticCurve};
+
+    use super::*;
+    use crate::{
+        events::create_ec_add_event, programs::tests::executor_with_words,
+        syscalls::SyscallContext, ExecutionRecord,
+    };
+
+    #[test]
+    fn test_disk_events_match_in_memory() {
+        let (p_ptr, q_ptr) = (0x1000, 0x1100);
+        let generator = Secp256k1::ec_generator();
+        let doubled = Secp256k1::ec_double(&generator);
+        let mut rt = executor_with_words(&[
+            (p_ptr, &generator.to_words_le()),
+            (q_ptr, &doubled.to_words_le()),
+        ]);
+        let mut ctx = SyscallContext::new(&mut rt);
+        let events = (0..16)
+            .map(|_| {
+                ctx.clk += 4;
+                create_ec_add_event::<Secp256k1>(&mut ctx, p_ptr, q_ptr).unwrap()
+            })
+            .collect::<Vec<_>>();
+
+        // Spill the first half of the events to disk while collecting the second half in memory.
+        let mut record = ExecutionRecord::default();
+        let mut buffer = DiskEventBuffer::new(tempfile::tempfile().unwrap()).unwrap();
+        record.secp256k1_add_events.extend_from_slice(&events[..8]);
+        record.spill_events(|record| &mut record.secp256k1_add_events, &mut buffer).unwrap();
+        assert!(record.secp256k1_add_events.is_empty());
+        assert_eq!(buffer.len(), 8);
+        record.secp256k1_add_events.extend_from_slice(&events[8..]);
+
+        record.restore_events(|record| &mut record.secp256k1_add_events, buffer).unwrap();
+        assert_eq!(record.secp256k1_add_events.len(), events.len());
+        for (event, read) in events.iter().zip(record.secp256k1_add_events.iter()) {
+            assert_eq!(event.content_hash(), read.content_hash());
+        }
+    }
+
+    #[test]
+    fn test_truncated_buffer_returns_error() {
+        let file = tempfile::tempfile().unwrap();
+        let mut buffer = DiskEventBuffer::<u64>::new(file.try_clone().unwrap()).unwrap();
+        for value in 0..4 {
+            buffer.push(&value).unwrap();
+        }
+        buffer.writer.flush().unwrap();
+
+        // Drop the last event from the file behind the buffer's back.
+        file.set_len(3 * 8).unwrap();
+        let read = buffer.read_back().unwrap().collect::<Vec<_>>();
+        assert_eq!(read.len(), 4);
+        assert!(read[..3].iter().all(Result::is_ok));
+        assert!(read[3].is_err());
+    }
+}
@@ -3,6 +3,8 @@
 mod alu;
 mod byte;
 mod cpu;
+#[cfg(feature = "disk-events")]
+mod disk;
 mod memory;
 mod precompiles;
 mod utils;
@@ -10,6 +12,8 @@ mod utils;
 pub use alu::*;
 pub use byte::*;
 pub use cpu::*;
+#[cfg(feature = "disk-events")]
+pub use disk::*;
 pub use memory::*;
 pub use precompiles::*;
 pub use utils::*;
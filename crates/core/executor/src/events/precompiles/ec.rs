@@ -1,19 +1,29 @@
+use std::ops::Range;
+
+use num::{BigUint, Zero};
 use serde::{Deserialize, Serialize};
 
 use sp1_curves::{
-    params::{NumLimbs, NumWords},
-    weierstrass::{bls12_381::bls12381_decompress, secp256k1::secp256k1_decompress},
-    AffinePoint, CurveType, EllipticCurve,
+    edwards::ed25519::Ed25519,
+    params::{FieldParameters, NumLimbs, NumWords},
+    weierstrass::{
+        bls12_381::{bls12381_decompress, bls12381_g1_in_subgroup, Bls12381, Bls12381G2},
+        bn254::Bn254,
+        secp256k1::{secp256k1_decompress, Secp256k1},
+        secp256r1::{secp256r1_decompress, Secp256r1},
+    },
+    AffinePoint, CurveType, EllipticCurve, EllipticCurveParameters,
 };
-use sp1_primitives::consts::{bytes_to_words_le_vec, words_to_bytes_le_vec};
 use typenum::Unsigned;
 
 use crate::{
     events::{
+        event_content_hash,
         memory::{MemoryReadRecord, MemoryWriteRecord},
         LookupId,
     },
-    syscalls::SyscallContext,
+    syscalls::{SyscallContext, SyscallError},
+    MemoryRegion,
 };
 
 /// Elliptic Curve Add Event.
@@ -30,16 +40,137 @@ pub struct EllipticCurveAddEvent {
     pub clk: u32,
     /// The pointer to the first point.
     pub p_ptr: u32,
-    /// The first point as a list of words.
+    /// The first point as a list of little-endian words, whatever its word order in memory.
     pub p: Vec<u32>,
     /// The pointer to the second point.
     pub q_ptr: u32,
-    /// The second point as a list of words.
+    /// The second point as a list of little-endian words, as for [`Self::p`].
     pub q: Vec<u32>,
     /// The memory records for the first point.
     pub p_memory_records: Vec<MemoryWriteRecord>,
     /// The memory records for the second point.
     pub q_memory_records: Vec<MemoryReadRecord>,
+    /// The memory region holding the first point, or `None` if it straddles a region boundary.
+    pub p_region: Option<MemoryRegion>,
+    /// The memory region holding the second point, as for [`Self::p_region`].
+    pub q_region: Option<MemoryRegion>,
+    /// Whether both points were read from the same pointer, in which case the sum is a doubling.
+    pub operands_alias: bool,
+    /// The curve the points are on.
+    pub curve: CurveType,
+}
+
+impl EllipticCurveAddEvent {
+    /// The lookup identifier of the syscall that created the event.
+    #[must_use]
+    pub fn lookup_id(&self) -> LookupId {
+        self.lookup_id
+    }
+
+    /// Create an add event as [`create_ec_add_event`] does, but with a fixed `lookup_id` instead
+    /// of the one of the current syscall, for instance to replay a captured trace.
+    pub fn new_with_lookup_id<E: EllipticCurve>(
+        rt: &mut SyscallContext,
+        p_ptr: u32,
+        q_ptr: u32,
+        lookup_id: LookupId,
+    ) -> Result<Self, SyscallError> {
+        Ok(create_ec_add_event::<E>(rt, p_ptr, q_ptr)?.with_lookup_id(lookup_id))
+    }
+
+    /// Replaces the lookup identifier of the event.
+    ///
+    /// The identifier ties the event to the syscall that created it, so this is only meant for
+    /// re-materializing events captured from another run.
+    #[must_use]
+    pub fn with_lookup_id(mut self, lookup_id: LookupId) -> Self {
+        self.lookup_id = lookup_id;
+        self
+    }
+
+    /// The first point, as read before the addition.
+    ///
+    /// ```
+    /// use sp1_core_executor::{
+    ///     events::{create_ec_add_event, MemoryRecord},
+    ///     syscalls::SyscallContext,
+    ///     Executor, Program,
+    /// };
+    /// use sp1_curves::{weierstrass::secp256k1::Secp256k1, EllipticCurve};
+    /// use sp1_stark::SP1CoreOpts;
+    ///
+    /// let generator = Secp256k1::ec_generator();
+    /// let mut rt = Executor::new(Program::new(vec![], 0, 0), SP1CoreOpts::default());
+    /// let doubled = generator.sw_double();
+    /// let (p_ptr, q_ptr) = (0x1000, 0x1100);
+    /// for (ptr, point) in [(p_ptr, &generator), (q_ptr, &doubled)] {
+    ///     for (i, word) in point.to_words_le().into_iter().enumerate() {
+    ///         let record = MemoryRecord { value: word, shard: 0, timestamp: 0 };
+    ///         rt.state.memory.insert(ptr + 4 * i as u32, record);
+    ///     }
+    /// }
+    ///
+    /// let mut ctx = SyscallContext::new(&mut rt);
+    /// let event = create_ec_add_event::<Secp256k1>(&mut ctx, p_ptr, q_ptr).unwrap();
+    /// assert_eq!(event.p_point::<Secp256k1>(), generator);
+    /// assert_eq!(event.q_point::<Secp256k1>(), doubled);
+    /// assert_eq!(event.result_point::<Secp256k1>(), doubled.sw_add(&generator));
+    /// ```
+    #[must_use]
+    pub fn p_point<E: EllipticCurve>(&self) -> AffinePoint<E> {
+        AffinePoint::from_words_le(&self.p)
+    }
+
+    /// The second point, decoded as in [`Self::p_point`].
+    #[must_use]
+    pub fn q_point<E: EllipticCurve>(&self) -> AffinePoint<E> {
+        AffinePoint::from_words_le(&self.q)
+    }
+
+    /// The sum written back over the first point.
+    ///
+    /// The memory records hold the words as laid out in memory, so this is only the sum for events
+    /// created with [`EcWordOrder::LittleEndian`].
+    #[must_use]
+    pub fn result_point<E: EllipticCurve>(&self) -> AffinePoint<E> {
+        let words = self.p_memory_records.iter().map(|record| record.value).collect::<Vec<_>>();
+        AffinePoint::from_words_le(&words)
+    }
+
+    /// The number of memory accesses of the event, for packing events into shards without walking
+    /// them.
+    #[must_use]
+    pub fn mem_record_count(&self) -> usize {
+        self.p_memory_records.len() + self.q_memory_records.len()
+    }
+
+    /// Whether both points and the written sum have the number of words of a point of
+    /// [`Self::curve`].
+    #[must_use]
+    pub fn has_curve_word_counts(&self) -> bool {
+        let num_words = ec_point_num_words(self.curve) as usize;
+        self.p.len() == num_words
+            && self.q.len() == num_words
+            && self.p_memory_records.len() == num_words
+    }
+
+    /// The SHA-256 digest of the event's contents, used as its leaf in
+    /// [`crate::events::events_merkle_root`].
+    #[must_use]
+    pub fn content_hash(&self) -> [u8; 32] {
+        event_content_hash(self)
+    }
+
+    /// Returns a [`SyscallError::MisattributedAccess`] error if a memory access of the event is
+    /// attributed to another shard or channel than the event.
+    ///
+    /// Memory records do not store a channel: every access is made on the channel of `rt` at the
+    /// time of the access, so the accesses are on the event's channel if `rt` is still on it.
+    pub fn check_memory_attribution(&self, rt: &SyscallContext) -> Result<(), SyscallError> {
+        let shards = self.p_memory_records.iter().map(|record| record.shard);
+        let shards = shards.chain(self.q_memory_records.iter().map(|record| record.shard));
+        check_attribution(rt, self.shard, self.channel, shards)
+    }
 }
 
 /// Elliptic Curve Double Event.
@@ -57,10 +188,223 @@ pub struct EllipticCurveDoubleEvent {
     pub clk: u32,
     /// The pointer to the point.
     pub p_ptr: u32,
-    /// The point as a list of words.
+    /// The point as a list of little-endian words, whatever its word order in memory.
     pub p: Vec<u32>,
     /// The memory records for the point.
     pub p_memory_records: Vec<MemoryWriteRecord>,
+    /// The memory region holding the point, or `None` if it straddles a region boundary.
+    pub p_region: Option<MemoryRegion>,
+    /// The curve the point is on.
+    pub curve: CurveType,
+}
+
+impl EllipticCurveDoubleEvent {
+    /// The number of memory accesses of the event, as in
+    /// [`EllipticCurveAddEvent::mem_record_count`].
+    #[must_use]
+    pub fn mem_record_count(&self) -> usize {
+        self.p_memory_records.len()
+    }
+
+    /// Whether the point and the written double have the number of words of a point of
+    /// [`Self::curve`].
+    #[must_use]
+    pub fn has_curve_word_counts(&self) -> bool {
+        let num_words = ec_point_num_words(self.curve) as usize;
+        self.p.len() == num_words && self.p_memory_records.len() == num_words
+    }
+
+    /// The SHA-256 digest of the event's contents.
+    #[must_use]
+    pub fn content_hash(&self) -> [u8; 32] {
+        event_content_hash(self)
+    }
+
+    /// Returns a [`SyscallError::MisattributedAccess`] error if a memory access of the event is
+    /// attributed to another shard or channel than the event, as in
+    /// [`EllipticCurveAddEvent::check_memory_attribution`].
+    pub fn check_memory_attribution(&self, rt: &SyscallContext) -> Result<(), SyscallError> {
+        let shards = self.p_memory_records.iter().map(|record| record.shard);
+        check_attribution(rt, self.shard, self.channel, shards)
+    }
+}
+
+/// The interface shared by the elliptic curve precompile events, so that code routing events to
+/// their chips can handle any of them.
+pub trait EcPrecompileEvent {
+    /// The shard number.
+    fn shard(&self) -> u32;
+    /// The channel number.
+    fn channel(&self) -> u8;
+    /// The clock cycle.
+    fn clk(&self) -> u32;
+    /// The lookup identifier of the syscall that created the event.
+    fn lookup_id(&self) -> LookupId;
+    /// The number of memory accesses of the event.
+    fn mem_record_count(&self) -> usize;
+}
+
+impl EcPrecompileEvent for EllipticCurveAddEvent {
+    fn shard(&self) -> u32 {
+        self.shard
+    }
+
+    fn channel(&self) -> u8 {
+        self.channel
+    }
+
+    fn clk(&self) -> u32 {
+        self.clk
+    }
+
+    fn lookup_id(&self) -> LookupId {
+        self.lookup_id
+    }
+
+    fn mem_record_count(&self) -> usize {
+        self.mem_record_count()
+    }
+}
+
+impl EcPrecompileEvent for EllipticCurveDoubleEvent {
+    fn shard(&self) -> u32 {
+        self.shard
+    }
+
+    fn channel(&self) -> u8 {
+        self.channel
+    }
+
+    fn clk(&self) -> u32 {
+        self.clk
+    }
+
+    fn lookup_id(&self) -> LookupId {
+        self.lookup_id
+    }
+
+    fn mem_record_count(&self) -> usize {
+        self.mem_record_count()
+    }
+}
+
+impl EcPrecompileEvent for EllipticCurveDecompressEvent {
+    fn shard(&self) -> u32 {
+        self.shard
+    }
+
+    fn channel(&self) -> u8 {
+        self.channel
+    }
+
+    fn clk(&self) -> u32 {
+        self.clk
+    }
+
+    fn lookup_id(&self) -> LookupId {
+        self.lookup_id
+    }
+
+    fn mem_record_count(&self) -> usize {
+        self.mem_record_count()
+    }
+}
+
+/// Returns a [`SyscallError::MisattributedAccess`] error if any of the `record_shards` differs
+/// from the `shard` of their event, or if `rt` is no longer on the event's `channel`.
+fn check_attribution(
+    rt: &SyscallContext,
+    shard: u32,
+    channel: u8,
+    record_shards: impl IntoIterator<Item = u32>,
+) -> Result<(), SyscallError> {
+    let mut record_shards = record_shards.into_iter();
+    if rt.current_channel() == channel && record_shards.all(|record_shard| record_shard == shard) {
+        Ok(())
+    } else {
+        Err(SyscallError::MisattributedAccess { shard, channel, syscall: rt.syscall_code })
+    }
+}
+
+/// Panics in debug builds if the `result_words` written back by a precompile are not as wide as
+/// the `num_words` it read, in which case the write would not cover exactly the point it replaces.
+fn debug_assert_result_width(result_words: &[u32], num_words: usize) {
+    debug_assert_eq!(
+        result_words.len(),
+        num_words,
+        "EC result has a different number of words than the point it overwrites"
+    );
+}
+
+/// The size of the limbs in which the coordinates of a point are encoded.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum EcLimbSize {
+    /// 16-bit limbs, for AIRs that range check half words.
+    Bits16,
+    /// 32-bit limbs, matching the words in memory.
+    #[default]
+    Bits32,
+}
+
+impl EcLimbSize {
+    /// The number of bytes in a limb.
+    #[must_use]
+    pub const fn num_bytes(self) -> usize {
+        match self {
+            Self::Bits16 => 2,
+            Self::Bits32 => 4,
+        }
+    }
+}
+
+/// The order in which the words of each coordinate of a point are laid out in memory.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum EcWordOrder {
+    /// The least-significant word first, as read by `AffinePoint::from_words_le`.
+    #[default]
+    LittleEndian,
+    /// The most-significant word first.
+    BigEndian,
+}
+
+/// Converts limbs of the given size to a byte vector in little endian.
+#[must_use]
+pub fn limbs_to_bytes_le_vec(limbs: &[u32], limb_size: EcLimbSize) -> Vec<u8> {
+    limbs.iter().flat_map(|limb| limb.to_le_bytes()[..limb_size.num_bytes()].to_vec()).collect()
+}
+
+/// Converts a byte array in little endian to a vector of limbs of the given size.
+#[must_use]
+pub fn bytes_to_limbs_le_vec(bytes: &[u8], limb_size: EcLimbSize) -> Vec<u32> {
+    assert_eq!(bytes.len() % limb_size.num_bytes(), 0, "bytes must fill a whole number of limbs");
+    bytes
+        .chunks_exact(limb_size.num_bytes())
+        .map(|chunk| {
+            let mut limb = [0u8; 4];
+            limb[..chunk.len()].copy_from_slice(chunk);
+            u32::from_le_bytes(limb)
+        })
+        .collect()
+}
+
+/// Decodes a point from the little-endian limbs of its coordinates.
+#[must_use]
+pub fn ec_point_from_limbs_le<E: EllipticCurve>(
+    limbs: &[u32],
+    limb_size: EcLimbSize,
+) -> AffinePoint<E> {
+    let bytes = limbs_to_bytes_le_vec(limbs, limb_size);
+    AffinePoint::from_words_le(&bytes_to_limbs_le_vec(&bytes, EcLimbSize::Bits32))
+}
+
+/// Encodes a point as the little-endian limbs of its coordinates.
+#[must_use]
+pub fn ec_point_to_limbs_le<E: EllipticCurve>(
+    point: &AffinePoint<E>,
+    limb_size: EcLimbSize,
+) -> Vec<u32> {
+    let bytes = limbs_to_bytes_le_vec(&point.to_words_le(), EcLimbSize::Bits32);
+    bytes_to_limbs_le_vec(&bytes, limb_size)
 }
 
 /// Elliptic Curve Point Decompress Event.
@@ -90,44 +434,276 @@ pub struct EllipticCurveDecompressEvent {
     pub y_memory_records: Vec<MemoryWriteRecord>,
 }
 
+impl EllipticCurveDecompressEvent {
+    /// The SHA-256 digest of the event's contents.
+    #[must_use]
+    pub fn content_hash(&self) -> [u8; 32] {
+        event_content_hash(self)
+    }
+
+    /// The sign bit that encodes the decompressed `y` in the canonical compressed form of `E`.
+    ///
+    /// For `secp256k1` and `secp256r1` (`SEC1`) this is the parity of `y`. For BLS12-381 (`ZCash`
+    /// encoding) this is whether `y` is lexicographically larger than `-y`.
+    #[must_use]
+    pub fn canonical_sign_bit<E: EllipticCurve>(&self) -> bool {
+        let y = BigUint::from_bytes_le(&self.decompressed_y_bytes);
+        match E::CURVE_TYPE {
+            CurveType::Secp256k1 | CurveType::Secp256r1 => y.bit(0),
+            CurveType::Bls12381 => {
+                let modulus = E::BaseField::modulus();
+                (&modulus - &y) % &modulus < y
+            }
+            _ => panic!("Unsupported curve"),
+        }
+    }
+
+    /// Whether the stored `sign_bit` agrees with the canonical encoding of the decompressed point.
+    #[must_use]
+    pub fn has_canonical_sign_bit<E: EllipticCurve>(&self) -> bool {
+        self.sign_bit == self.canonical_sign_bit::<E>()
+    }
+
+    /// The number of memory accesses of the event, as in
+    /// [`EllipticCurveAddEvent::mem_record_count`].
+    #[must_use]
+    pub fn mem_record_count(&self) -> usize {
+        self.x_memory_records.len() + self.y_memory_records.len()
+    }
+
+    /// Encodes the event in a fixed binary layout, without going through `serde`.
+    ///
+    /// A fixed-width header holds the lookup id, shard, channel, clock, pointer and sign bit. It is
+    /// followed by the x and y bytes, the x and y memory records and the curve witness, each
+    /// prefixed by its length or presence. Integers are little-endian. The encoding delimits
+    /// itself, so encoded events can be concatenated and read back with [`Self::read_bytes`].
+    #[must_use]
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut out = Vec::new();
+        self.write_bytes(&mut out);
+        out
+    }
+
+    /// Appends the encoding of [`Self::to_bytes`] to `out`.
+    pub fn write_bytes(&self, out: &mut Vec<u8>) {
+        let LookupId { a, b, c, d } = self.lookup_id;
+        for word in [a, b, c, d, self.shard] {
+            write_u32(out, word);
+        }
+        out.push(self.channel);
+        write_u32(out, self.clk);
+        write_u32(out, self.ptr);
+        out.push(u8::from(self.sign_bit));
+        write_byte_vec(out, &self.x_bytes);
+        write_byte_vec(out, &self.decompressed_y_bytes);
+        write_u32(out, self.x_memory_records.len() as u32);
+        for record in &self.x_memory_records {
+            let MemoryReadRecord { value, shard, timestamp, prev_shard, prev_timestamp } = *record;
+            for word in [value, shard, timestamp, prev_shard, prev_timestamp] {
+                write_u32(out, word);
+            }
+        }
+        write_u32(out, self.y_memory_records.len() as u32);
+        for record in &self.y_memory_records {
+            let MemoryWriteRecord {
+                value,
+                shard,
+                timestamp,
+                prev_value,
+                prev_shard,
+                prev_timestamp,
+            } = *record;
+            for word in [value, shard, timestamp, prev_value, prev_shard, prev_timestamp] {
+                write_u32(out, word);
+            }
+        }
+    }
+
+    /// Decodes an event encoded by [`Self::to_bytes`], or returns `None` if `bytes` is not exactly
+    /// one encoded event.
+    #[must_use]
+    pub fn from_bytes(mut bytes: &[u8]) -> Option<Self> {
+        let event = Self::read_bytes(&mut bytes)?;
+        bytes.is_empty().then_some(event)
+    }
+
+    /// Decodes the event encoded at the front of `bytes` and advances `bytes` past it, or returns
+    /// `None` if the encoding is truncated or malformed.
+    pub fn read_bytes(bytes: &mut &[u8]) -> Option<Self> {
+        let lookup_id = LookupId {
+            a: read_u32(bytes)?,
+            b: read_u32(bytes)?,
+            c: read_u32(bytes)?,
+            d: read_u32(bytes)?,
+        };
+        let shard = read_u32(bytes)?;
+        let channel = read_u8(bytes)?;
+        let clk = read_u32(bytes)?;
+        let ptr = read_u32(bytes)?;
+        let sign_bit = match read_u8(bytes)? {
+            0 => false,
+            1 => true,
+            _ => return None,
+        };
+        let x_bytes = read_byte_vec(bytes)?;
+        let decompressed_y_bytes = read_byte_vec(bytes)?;
+        let x_memory_records = (0..read_u32(bytes)?)
+            .map(|_| {
+                Some(MemoryReadRecord {
+                    value: read_u32(bytes)?,
+                    shard: read_u32(bytes)?,
+                    timestamp: read_u32(bytes)?,
+                    prev_shard: read_u32(bytes)?,
+                    prev_timestamp: read_u32(bytes)?,
+                })
+            })
+            .collect::<Option<Vec<_>>>()?;
+        let y_memory_records = (0..read_u32(bytes)?)
+            .map(|_| {
+                Some(MemoryWriteRecord {
+                    value: read_u32(bytes)?,
+                    shard: read_u32(bytes)?,
+                    timestamp: read_u32(bytes)?,
+                    prev_value: read_u32(bytes)?,
+                    prev_shard: read_u32(bytes)?,
+                    prev_timestamp: read_u32(bytes)?,
+                })
+            })
+            .collect::<Option<Vec<_>>>()?;
+        Some(Self {
+            lookup_id,
+            shard,
+            channel,
+            clk,
+            ptr,
+            sign_bit,
+            x_bytes,
+            decompressed_y_bytes,
+            x_memory_records,
+            y_memory_records,
+        })
+    }
+}
+
+fn write_u32(out: &mut Vec<u8>, value: u32) {
+    out.extend_from_slice(&value.to_le_bytes());
+}
+
+fn write_byte_vec(out: &mut Vec<u8>, bytes: &[u8]) {
+    write_u32(out, bytes.len() as u32);
+    out.extend_from_slice(bytes);
+}
+
+fn read_u8(bytes: &mut &[u8]) -> Option<u8> {
+    let (first, rest) = (*bytes).split_first()?;
+    *bytes = rest;
+    Some(*first)
+}
+
+fn read_u32(bytes: &mut &[u8]) -> Option<u32> {
+    let (head, rest) = (*bytes).split_first_chunk::<4>()?;
+    *bytes = rest;
+    Some(u32::from_le_bytes(*head))
+}
+
+fn read_byte_vec(bytes: &mut &[u8]) -> Option<Vec<u8>> {
+    let len = read_u32(bytes)? as usize;
+    if bytes.len() < len {
+        return None;
+    }
+    let (head, rest) = (*bytes).split_at(len);
+    *bytes = rest;
+    Some(head.to_vec())
+}
+
+/// Converts a point between the word order `word_order` and little-endian words.
+///
+/// Only the words within each coordinate are reordered, so the conversion is its own inverse.
+fn ec_point_reorder_words<E: EllipticCurve>(words: &[u32], word_order: EcWordOrder) -> Vec<u32> {
+    match word_order {
+        EcWordOrder::LittleEndian => words.to_vec(),
+        EcWordOrder::BigEndian => {
+            let num_words = <E::BaseField as NumWords>::WordsFieldElement::USIZE;
+            words
+                .chunks(num_words)
+                .flat_map(|coordinate| coordinate.iter().rev().copied())
+                .collect()
+        }
+    }
+}
+
 /// Create an elliptic curve add event. It takes two pointers to memory locations, reads the points
 /// from memory, adds them together, and writes the result back to the first memory location.
 /// The generic parameter `N` is the number of u32 words in the point representation. For example,
 /// for the secp256k1 curve, `N` would be 16 (64 bytes) because the x and y coordinates are 32 bytes
 /// each.
+///
+/// Equal points are doubled, since the addition formula is undefined for them.
+///
+/// Returns a [`SyscallError`], without touching memory, if either pointer is not word aligned or
+/// either point is the identity. The affine encoding cannot represent the identity, so guests pass
+/// it in the [`crate::Program::ec_identity_encoding`] of the program, by default all-zero words,
+/// which is not a point of any supported curve. An encoding that could be mistaken for a point of
+/// `E` is rejected with [`SyscallError::AmbiguousIdentityEncoding`].
 pub fn create_ec_add_event<E: EllipticCurve>(
     rt: &mut SyscallContext,
     arg1: u32,
     arg2: u32,
-) -> EllipticCurveAddEvent {
+) -> Result<EllipticCurveAddEvent, SyscallError> {
+    create_ec_add_event_with_word_order::<E>(rt, arg1, arg2, EcWordOrder::LittleEndian)
+}
+
+/// Create an elliptic curve add event for points whose coordinates are laid out in memory in
+/// `word_order`.
+///
+/// The points are converted to little-endian words when they are read, and the event records them
+/// in that order. The result is written back in `word_order`. Otherwise, this behaves as
+/// [`create_ec_add_event`].
+pub fn create_ec_add_event_with_word_order<E: EllipticCurve>(
+    rt: &mut SyscallContext,
+    arg1: u32,
+    arg2: u32,
+    word_order: EcWordOrder,
+) -> Result<EllipticCurveAddEvent, SyscallError> {
     let start_clk = rt.clk;
     let p_ptr = arg1;
-    if p_ptr % 4 != 0 {
-        panic!();
-    }
+    rt.check_aligned(p_ptr)?;
     let q_ptr = arg2;
-    if q_ptr % 4 != 0 {
-        panic!();
-    }
+    rt.check_aligned(q_ptr)?;
 
     let num_words = <E::BaseField as NumWords>::WordsCurvePoint::USIZE;
+    rt.check_bounds(p_ptr, num_words)?;
+    rt.check_bounds(q_ptr, num_words)?;
+    let p_region = rt.memory_region(p_ptr, num_words);
+    let q_region = rt.memory_region(q_ptr, num_words);
 
+    let ec_identity_encoding = rt.rt.program.ec_identity_encoding;
+    if !ec_identity_encoding.is_unambiguous(E::BaseField::modulus().bits()) {
+        return Err(SyscallError::AmbiguousIdentityEncoding { syscall: rt.syscall_code });
+    }
+    for ptr in [p_ptr, q_ptr] {
+        if ec_identity_encoding.is_identity(&rt.slice_unsafe(ptr, num_words)) {
+            return Err(SyscallError::PointAtInfinity { ptr, syscall: rt.syscall_code });
+        }
+    }
     let p = rt.slice_unsafe(p_ptr, num_words);
 
     let (q_memory_records, q) = rt.mr_slice(q_ptr, num_words);
 
-    // When we write to p, we want the clk to be incremented because p and q could be the same.
-    rt.clk += 1;
+    // p and q could be the same, so p is written after the reads.
+    rt.advance_clk_for_write();
 
+    let p = ec_point_reorder_words::<E>(&p, word_order);
+    let q = ec_point_reorder_words::<E>(&q, word_order);
     let p_affine = AffinePoint::<E>::from_words_le(&p);
     let q_affine = AffinePoint::<E>::from_words_le(&q);
-    let result_affine = p_affine + q_affine;
-
-    let result_words = result_affine.to_words_le();
+    let result_le = ec_add_or_double(&p_affine, &q_affine).to_words_le();
+    let result_words = ec_point_reorder_words::<E>(&result_le, word_order);
+    debug_assert_result_width(&result_words, num_words);
 
     let p_memory_records = rt.mw_slice(p_ptr, &result_words);
 
-    EllipticCurveAddEvent {
+    let event = EllipticCurveAddEvent {
         lookup_id: rt.syscall_lookup_id,
         shard: rt.current_shard(),
         channel: rt.current_channel(),
@@ -138,6 +714,22 @@ pub fn create_ec_add_event<E: EllipticCurve>(
         q,
         p_memory_records,
         q_memory_records,
+        p_region,
+        q_region,
+        operands_alias: p_ptr == q_ptr,
+        curve: E::CURVE_TYPE,
+    };
+    event.check_memory_attribution(rt)?;
+    Ok(event)
+}
+
+/// Adds two points, doubling instead when they are equal since the short Weierstrass addition
+/// formula is undefined for `p == q`. The add chips take the same doubling branch.
+fn ec_add_or_double<E: EllipticCurve>(p: &AffinePoint<E>, q: &AffinePoint<E>) -> AffinePoint<E> {
+    if p.x == q.x && p.y == q.y {
+        E::ec_double(p)
+    } else {
+        E::ec_add(p, q)
     }
 }
 
@@ -145,30 +737,48 @@ pub fn create_ec_add_event<E: EllipticCurve>(
 ///
 /// It takes a pointer to a memory location, reads the point from memory, doubles it, and writes the
 /// result back to the memory location.
+///
+/// Returns a [`SyscallError::UnalignedPointer`] error, without touching memory, if the pointer is
+/// not word aligned.
 pub fn create_ec_double_event<E: EllipticCurve>(
     rt: &mut SyscallContext,
     arg1: u32,
     _: u32,
-) -> EllipticCurveDoubleEvent {
+) -> Result<EllipticCurveDoubleEvent, SyscallError> {
+    create_ec_double_event_with_word_order::<E>(rt, arg1, EcWordOrder::LittleEndian)
+}
+
+/// Create an elliptic curve double event for a point whose coordinates are laid out in memory in
+/// `word_order`.
+///
+/// The point is converted to little-endian words when it is read, and the event records it in that
+/// order. The result is written back in `word_order`. Otherwise, this behaves as
+/// [`create_ec_double_event`].
+pub fn create_ec_double_event_with_word_order<E: EllipticCurve>(
+    rt: &mut SyscallContext,
+    arg1: u32,
+    word_order: EcWordOrder,
+) -> Result<EllipticCurveDoubleEvent, SyscallError> {
     let start_clk = rt.clk;
     let p_ptr = arg1;
-    if p_ptr % 4 != 0 {
-        panic!();
-    }
+    rt.check_aligned(p_ptr)?;
 
     let num_words = <E::BaseField as NumWords>::WordsCurvePoint::USIZE;
+    rt.check_bounds(p_ptr, num_words)?;
+    let p_region = rt.memory_region(p_ptr, num_words);
 
-    let p = rt.slice_unsafe(p_ptr, num_words);
+    let p = ec_point_reorder_words::<E>(&rt.slice_unsafe(p_ptr, num_words), word_order);
 
     let p_affine = AffinePoint::<E>::from_words_le(&p);
 
     let result_affine = E::ec_double(&p_affine);
 
-    let result_words = result_affine.to_words_le();
+    let result_words = ec_point_reorder_words::<E>(&result_affine.to_words_le(), word_order);
+    debug_assert_result_width(&result_words, num_words);
 
     let p_memory_records = rt.mw_slice(p_ptr, &result_words);
 
-    EllipticCurveDoubleEvent {
+    let event = EllipticCurveDoubleEvent {
         lookup_id: rt.syscall_lookup_id,
         shard: rt.current_shard(),
         channel: rt.current_channel(),
@@ -176,47 +786,350 @@ pub fn create_ec_double_event<E: EllipticCurve>(
         p_ptr,
         p,
         p_memory_records,
+        p_region,
+        curve: E::CURVE_TYPE,
+    };
+    event.check_memory_attribution(rt)?;
+    Ok(event)
+}
+
+/// The number of words of a point of `curve`.
+#[must_use]
+pub fn ec_point_num_words(curve: CurveType) -> u32 {
+    fn num_words<E: EllipticCurveParameters>() -> u32 {
+        <E::BaseField as NumWords>::WordsCurvePoint::U32
+    }
+    match curve {
+        CurveType::Secp256k1 => num_words::<Secp256k1>(),
+        CurveType::Secp256r1 => num_words::<Secp256r1>(),
+        CurveType::Bn254 => num_words::<Bn254>(),
+        CurveType::Ed25519 => num_words::<Ed25519>(),
+        CurveType::Bls12381 => num_words::<Bls12381>(),
+        CurveType::Bls12381G2 => num_words::<Bls12381G2>(),
     }
 }
 
+/// The address ranges [`create_ec_add_event`] accesses when adding points of `curve` at `p_ptr`
+/// and `q_ptr`.
+///
+/// The first range holds `p`, which is read and overwritten with the sum. The second range holds
+/// `q`, which is only read.
+#[must_use]
+pub fn ec_add_access_range(p_ptr: u32, q_ptr: u32, curve: CurveType) -> Vec<Range<u32>> {
+    let num_bytes = ec_point_num_words(curve) * 4;
+    vec![p_ptr..p_ptr + num_bytes, q_ptr..q_ptr + num_bytes]
+}
+
+/// An address range accessed by a precompile event that falls outside the guest's memory.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PointerError {
+    /// The accessed range is not contained in the memory bounds.
+    OutOfBounds {
+        /// The accessed range.
+        range: Range<u32>,
+        /// The memory bounds.
+        bounds: Range<u32>,
+    },
+}
+
+/// Checks that the `num_words` words starting at `ptr` fall within `bounds`.
+fn check_pointer_range(
+    ptr: u32,
+    num_words: usize,
+    bounds: &Range<u32>,
+) -> Result<(), PointerError> {
+    if num_words == 0 {
+        return Ok(());
+    }
+    let end = u64::from(ptr) + num_words as u64 * 4;
+    if ptr < bounds.start || end > u64::from(bounds.end) {
+        let range = ptr..u32::try_from(end).unwrap_or(u32::MAX);
+        return Err(PointerError::OutOfBounds { range, bounds: bounds.clone() });
+    }
+    Ok(())
+}
+
+/// Checks that every point accessed by the add events falls within `mem_bounds`.
+pub fn validate_ec_event_pointers(
+    events: &[EllipticCurveAddEvent],
+    mem_bounds: Range<u32>,
+) -> Result<(), PointerError> {
+    events.iter().try_for_each(|event| {
+        check_pointer_range(event.p_ptr, event.p.len(), &mem_bounds)?;
+        check_pointer_range(event.q_ptr, event.q.len(), &mem_bounds)
+    })
+}
+
+/// Checks that every point accessed by the double events falls within `mem_bounds`.
+pub fn validate_ec_double_event_pointers(
+    events: &[EllipticCurveDoubleEvent],
+    mem_bounds: Range<u32>,
+) -> Result<(), PointerError> {
+    events.iter().try_for_each(|event| check_pointer_range(event.p_ptr, event.p.len(), &mem_bounds))
+}
+
+/// Checks that the `y` and `x` coordinates accessed by the decompress events fall within
+/// `mem_bounds`.
+pub fn validate_ec_decompress_event_pointers(
+    events: &[EllipticCurveDecompressEvent],
+    mem_bounds: Range<u32>,
+) -> Result<(), PointerError> {
+    events.iter().try_for_each(|event| {
+        let num_words = event.y_memory_records.len() + event.x_memory_records.len();
+        check_pointer_range(event.ptr, num_words, &mem_bounds)
+    })
+}
+
+/// A step of a scalar multiplication carried out by add and double events on an accumulator.
+#[derive(Debug, Clone, Copy)]
+pub enum EcScalarMulStep<'a> {
+    /// The accumulator is doubled.
+    Double(&'a EllipticCurveDoubleEvent),
+    /// A multiple of the base point is added to the accumulator.
+    Add(&'a EllipticCurveAddEvent),
+}
+
+/// A width-`w` NAF recoding of a scalar that is invalid, or that a sequence of
+/// [`EcScalarMulStep`]s does not follow.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum WnafError {
+    /// A non-zero digit is even or its magnitude is not below `2^(w-1)`.
+    DigitOutOfRange {
+        /// The position of the digit.
+        index: usize,
+        /// The digit.
+        digit: i32,
+    },
+    /// A non-zero digit is fewer than `w` positions above the previous non-zero digit.
+    DigitsTooClose {
+        /// The position of the digit.
+        index: usize,
+    },
+    /// The digits do not reconstruct the scalar, or the scalar is zero.
+    WrongScalar,
+    /// The step is not the operation the recoding prescribes at that point, or follows the last
+    /// one.
+    UnexpectedStep {
+        /// The position of the step.
+        index: usize,
+    },
+    /// The step does not operate on the accumulator, or adds the wrong multiple of the base point.
+    WrongOperand {
+        /// The position of the step.
+        index: usize,
+    },
+    /// The sequence ends before the recoding does.
+    MissingSteps,
+}
+
+/// The width-`window` non-adjacent form of `scalar`, least-significant digit first.
+///
+/// Every non-zero digit is odd with a magnitude below `2^(window-1)`, and any `window` consecutive
+/// digits contain at most one non-zero digit.
+#[must_use]
+pub fn ec_wnaf_digits(scalar: &BigUint, window: usize) -> Vec<i32> {
+    assert!((2..=16).contains(&window), "wNAF window must be between 2 and 16");
+    let modulus = 1i64 << window;
+    let mut k = scalar.clone();
+    let mut digits = Vec::new();
+    while !k.is_zero() {
+        let mut digit = 0;
+        if k.bit(0) {
+            digit = (k.to_u64_digits()[0] & (modulus as u64 - 1)) as i64;
+            if digit >= modulus / 2 {
+                digit -= modulus;
+            }
+            if digit > 0 {
+                k -= digit as u64;
+            } else {
+                k += digit.unsigned_abs();
+            }
+        }
+        digits.push(digit as i32);
+        k >>= 1;
+    }
+    digits
+}
+
+/// Checks that `steps` compute `[scalar]base` by following the width-`window` NAF recoding
+/// `digits`, given least-significant digit first.
+///
+/// The recoding must be valid: its non-zero digits are odd, below `2^(window-1)` in magnitude, at
+/// least `window` positions apart, and they reconstruct `scalar`. The accumulator starts at
+/// `[d]base` for the most significant digit `d`. For each lower digit, from the top down, it is
+/// doubled and, if the digit is non-zero, the digit's multiple of `base` is added to it. Each step
+/// must take the accumulator written by the previous one. The events themselves are trusted to
+/// compute their results correctly.
+pub fn validate_ec_wnaf_scalar_mul<E: EllipticCurve>(
+    base: &AffinePoint<E>,
+    scalar: &BigUint,
+    window: usize,
+    digits: &[i32],
+    steps: &[EcScalarMulStep],
+) -> Result<(), WnafError> {
+    assert!((2..=16).contains(&window), "wNAF window must be between 2 and 16");
+    let bound = 1u32 << (window - 1);
+    let mut top = None;
+    let (mut positive, mut negative) = (BigUint::zero(), BigUint::zero());
+    for (index, &digit) in digits.iter().enumerate() {
+        if digit == 0 {
+            continue;
+        }
+        if digit % 2 == 0 || digit.unsigned_abs() >= bound {
+            return Err(WnafError::DigitOutOfRange { index, digit });
+        }
+        if top.is_some_and(|previous| index - previous < window) {
+            return Err(WnafError::DigitsTooClose { index });
+        }
+        top = Some(index);
+        let term = BigUint::from(digit.unsigned_abs()) << index;
+        if digit > 0 {
+            positive += term;
+        } else {
+            negative += term;
+        }
+    }
+    if scalar.is_zero() || positive != scalar + &negative {
+        return Err(WnafError::WrongScalar);
+    }
+    let top = top.expect("a non-zero scalar has a non-zero digit");
+
+    // The odd multiples `[2i + 1]base` that the digits select.
+    let double = E::ec_double(base);
+    let odd_multiples = std::iter::successors(Some(base.clone()), |m| Some(E::ec_add(m, &double)))
+        .take(bound as usize / 2)
+        .collect::<Vec<_>>();
+    let multiple = |digit: i32| {
+        let point = &odd_multiples[digit.unsigned_abs() as usize / 2];
+        let point = if digit > 0 { point.clone() } else { E::ec_neg(point) };
+        point.to_words_le()
+    };
+    let written = |records: &[MemoryWriteRecord]| {
+        records.iter().map(|record| record.value).collect::<Vec<_>>()
+    };
+
+    let mut acc = multiple(digits[top]);
+    let mut steps = steps.iter().enumerate();
+    for &digit in digits[..top].iter().rev() {
+        match steps.next().ok_or(WnafError::MissingSteps)? {
+            (index, EcScalarMulStep::Double(event)) => {
+                if event.p != acc {
+                    return Err(WnafError::WrongOperand { index });
+                }
+                acc = written(&event.p_memory_records);
+            }
+            (index, EcScalarMulStep::Add(_)) => return Err(WnafError::UnexpectedStep { index }),
+        }
+        if digit == 0 {
+            continue;
+        }
+        match steps.next().ok_or(WnafError::MissingSteps)? {
+            (index, EcScalarMulStep::Add(event)) => {
+                if event.p != acc || event.q != multiple(digit) {
+                    return Err(WnafError::WrongOperand { index });
+                }
+                acc = written(&event.p_memory_records);
+            }
+            (index, EcScalarMulStep::Double(_)) => return Err(WnafError::UnexpectedStep { index }),
+        }
+    }
+    match steps.next() {
+        Some((index, _)) => Err(WnafError::UnexpectedStep { index }),
+        None => Ok(()),
+    }
+}
+
+/// Encodes a decompressed `y` coordinate as little-endian bytes, padded to the limbs of `E`.
+///
+/// Returns a [`SyscallError::NotOnCurve`] error for the point at `ptr` if `y` is not reduced
+/// modulo the base field prime, so that the written output is canonical.
+fn ec_decompressed_y_bytes<E: EllipticCurve>(
+    rt: &SyscallContext,
+    ptr: u32,
+    y: &BigUint,
+) -> Result<Vec<u8>, SyscallError> {
+    if *y >= E::BaseField::modulus() {
+        return Err(SyscallError::NotOnCurve { ptr, syscall: rt.syscall_code });
+    }
+    let mut y_bytes = y.to_bytes_le();
+    y_bytes.resize(<E::BaseField as NumLimbs>::Limbs::USIZE, 0u8);
+    Ok(y_bytes)
+}
+
 /// Create an elliptic curve decompress event.
 ///
 /// It takes a pointer to a memory location, reads the point from memory, decompresses it, and
 /// writes the result back to the memory location.
+///
+/// Returns a [`SyscallError`], without touching memory, if the pointer is not word aligned, the
+/// slice of both coordinates runs past the end of memory, the sign bit is neither zero nor one, or
+/// `x` is not the coordinate of a point on the curve.
 pub fn create_ec_decompress_event<E: EllipticCurve>(
     rt: &mut SyscallContext,
     slice_ptr: u32,
     sign_bit: u32,
-) -> EllipticCurveDecompressEvent {
+) -> Result<EllipticCurveDecompressEvent, SyscallError> {
+    create_ec_decompress_event_with_subgroup_check::<E>(rt, slice_ptr, sign_bit, false)
+}
+
+/// Create an elliptic curve decompress event, optionally checking that the point is in the
+/// prime-order subgroup of the curve.
+///
+/// If `check_subgroup` is set and the decompressed point is not in the subgroup, this returns a
+/// [`SyscallError::NotInSubgroup`] error without writing the point. Only bls12-381 has a cofactor
+/// among the curves with decompression, so the check is a no-op on the others. On bls12-381 it
+/// costs a scalar multiplication by the subgroup order, see
+/// [`sp1_curves::weierstrass::bls12_381::bls12381_g1_in_subgroup`]. Otherwise, this behaves as
+/// [`create_ec_decompress_event`].
+pub fn create_ec_decompress_event_with_subgroup_check<E: EllipticCurve>(
+    rt: &mut SyscallContext,
+    slice_ptr: u32,
+    sign_bit: u32,
+    check_subgroup: bool,
+) -> Result<EllipticCurveDecompressEvent, SyscallError> {
     let start_clk = rt.clk;
-    assert!(slice_ptr % 4 == 0, "slice_ptr must be 4-byte aligned");
-    assert!(sign_bit <= 1, "is_odd must be 0 or 1");
+    rt.check_aligned(slice_ptr)?;
+    if sign_bit > 1 {
+        return Err(SyscallError::InvalidSignBit { sign_bit, syscall: rt.syscall_code });
+    }
 
     let num_limbs = <E::BaseField as NumLimbs>::Limbs::USIZE;
     let num_words_field_element = num_limbs / 4;
+    // The y coordinate is written at `slice_ptr`, followed by the x coordinate that is read.
+    rt.check_bounds(slice_ptr, 2 * num_words_field_element)?;
 
     let (x_memory_records, x_vec) =
         rt.mr_slice(slice_ptr + (num_limbs as u32), num_words_field_element);
 
-    let x_bytes = words_to_bytes_le_vec(&x_vec);
+    let x_bytes = limbs_to_bytes_le_vec(&x_vec, EcLimbSize::Bits32);
     let mut x_bytes_be = x_bytes.clone();
     x_bytes_be.reverse();
 
     let decompress_fn = match E::CURVE_TYPE {
         CurveType::Secp256k1 => secp256k1_decompress::<E>,
+        CurveType::Secp256r1 => secp256r1_decompress::<E>,
         CurveType::Bls12381 => bls12381_decompress::<E>,
         _ => panic!("Unsupported curve"),
     };
 
-    let computed_point: AffinePoint<E> = decompress_fn(&x_bytes_be, sign_bit);
+    let computed_point: AffinePoint<E> = decompress_fn(&x_bytes_be, sign_bit)
+        .ok_or(SyscallError::NotOnCurve { ptr: slice_ptr, syscall: rt.syscall_code })?;
+    if check_subgroup
+        && E::CURVE_TYPE == CurveType::Bls12381
+        && !bls12381_g1_in_subgroup(&AffinePoint::new(
+            computed_point.x.clone(),
+            computed_point.y.clone(),
+        ))
+    {
+        return Err(SyscallError::NotInSubgroup { ptr: slice_ptr, syscall: rt.syscall_code });
+    }
 
-    let mut decompressed_y_bytes = computed_point.y.to_bytes_le();
-    decompressed_y_bytes.resize(num_limbs, 0u8);
-    let y_words = bytes_to_words_le_vec(&decompressed_y_bytes);
+    let decompressed_y_bytes = ec_decompressed_y_bytes::<E>(rt, slice_ptr, &computed_point.y)?;
+    let y_words = bytes_to_limbs_le_vec(&decompressed_y_bytes, EcLimbSize::Bits32);
 
     let y_memory_records = rt.mw_slice(slice_ptr, &y_words);
 
-    EllipticCurveDecompressEvent {
+    Ok(EllipticCurveDecompressEvent {
         lookup_id: rt.syscall_lookup_id,
         shard: rt.current_shard(),
         channel: rt.current_channel(),
@@ -227,5 +1140,951 @@ pub fn create_ec_decompress_event<E: EllipticCurve>(
         decompressed_y_bytes,
         x_memory_records,
         y_memory_records,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use sp1_primitives::consts::STACK_TOP;
+    use sp1_stark::SP1CoreOpts;
+
+    use super::*;
+    use crate::{
+        events::{create_alu_lookup_id, events_merkle_root, CpuEvent},
+        programs::tests::{executor_with_words, write_words},
+        syscalls::{SyscallCode, PRECOMPILE_WRITE_CLK_OFFSET},
+        EcIdentityEncoding, ExecutionError, ExecutionRecord, Executor, Instruction, MemoryRegion,
+        Opcode, Program,
+    };
+
+    /// Decompresses the generator of `E` through the precompile and returns the event.
+    fn decompress_generator<E: EllipticCurve>(sign_bit: u32) -> EllipticCurveDecompressEvent {
+        decompress_x::<E>(&E::ec_generator().x, sign_bit)
+    }
+
+    /// Decompresses the point of `E` with coordinate `x` through the precompile and returns the
+    /// event.
+    fn decompress_x<E: EllipticCurve>(x: &BigUint, sign_bit: u32) -> EllipticCurveDecompressEvent {
+        let num_limbs = <E::BaseField as NumLimbs>::Limbs::USIZE;
+        let mut x_bytes = x.to_bytes_le();
+        x_bytes.resize(num_limbs, 0u8);
+
+        let ptr = 0x1000;
+        let x_words = bytes_to_limbs_le_vec(&x_bytes, EcLimbSize::Bits32);
+        let mut rt = executor_with_words(&[(ptr + num_limbs as u32, &x_words)]);
+        let mut ctx = SyscallContext::new(&mut rt);
+        create_ec_decompress_event::<E>(&mut ctx, ptr, sign_bit).unwrap()
+    }
+
+    #[test]
+    fn test_add_equal_points_doubles() {
+        let (p_ptr, q_ptr) = (0x1000, 0x1100);
+        let generator = Secp256k1::ec_generator().to_words_le();
+
+        let mut rt = executor_with_words(&[(p_ptr, &generator)]);
+        let mut ctx = SyscallContext::new(&mut rt);
+        create_ec_double_event::<Secp256k1>(&mut ctx, p_ptr, 0).unwrap();
+        let doubled = ctx.slice_unsafe(p_ptr, generator.len());
+        assert_eq!(doubled, Secp256k1::ec_double(&Secp256k1::ec_generator()).to_words_le());
+
+        // Equal points behind distinct pointers, and the same pointer for both points.
+        for q_ptr in [q_ptr, p_ptr] {
+            let mut rt = executor_with_words(&[(p_ptr, &generator), (q_ptr, &generator)]);
+            let mut ctx = SyscallContext::new(&mut rt);
+            let add = create_ec_add_event::<Secp256k1>(&mut ctx, p_ptr, q_ptr).unwrap();
+            assert_eq!(ctx.slice_unsafe(p_ptr, generator.len()), doubled);
+            assert_eq!(add.result_point::<Secp256k1>(), AffinePoint::from_words_le(&doubled));
+        }
+    }
+
+    #[test]
+    fn test_add_aliased_operands() {
+        let (p_ptr, q_ptr) = (0x1000, 0x1100);
+        let generator = Secp256k1::ec_generator();
+        let mut rt = executor_with_words(&[
+            (p_ptr, &generator.to_words_le()),
+            (q_ptr, &generator.to_words_le()),
+        ]);
+        let mut ctx = SyscallContext::new(&mut rt);
+
+        let aliased = create_ec_add_event::<Secp256k1>(&mut ctx, p_ptr, p_ptr).unwrap();
+        assert!(aliased.operands_alias);
+        assert_eq!(aliased.result_point::<Secp256k1>(), Secp256k1::ec_double(&generator));
+
+        ctx.clk += 1;
+        let distinct = create_ec_add_event::<Secp256k1>(&mut ctx, p_ptr, q_ptr).unwrap();
+        assert!(!distinct.operands_alias);
+        assert_eq!(
+            distinct.result_point::<Secp256k1>(),
+            Secp256k1::ec_double(&generator).sw_add(&generator)
+        );
+    }
+
+    #[test]
+    fn test_events_record_curve() {
+        fn add_and_double<E: EllipticCurve>() {
+            let (p_ptr, q_ptr) = (0x1000, 0x1100);
+            let generator = E::ec_generator();
+            let mut rt = executor_with_words(&[
+                (p_ptr, &generator.to_words_le()),
+                (q_ptr, &E::ec_double(&generator).to_words_le()),
+            ]);
+            let mut ctx = SyscallContext::new(&mut rt);
+
+            let add = create_ec_add_event::<E>(&mut ctx, p_ptr, q_ptr).unwrap();
+            assert_eq!(add.curve, E::CURVE_TYPE);
+            assert!(add.has_curve_word_counts());
+
+            ctx.clk += 1;
+            let double = create_ec_double_event::<E>(&mut ctx, p_ptr, 0).unwrap();
+            assert_eq!(double.curve, E::CURVE_TYPE);
+            assert!(double.has_curve_word_counts());
+
+            // The curve survives serialization, so a mixed log can be checked event by event.
+            let add: EllipticCurveAddEvent =
+                bincode::deserialize(&bincode::serialize(&add).unwrap()).unwrap();
+            assert_eq!(add.curve, E::CURVE_TYPE);
+        }
+        add_and_double::<Secp256k1>();
+        add_and_double::<Bls12381>();
+
+        let mut rt = executor_with_words(&[(0x1000, &Secp256k1::ec_generator().to_words_le())]);
+        let mut ctx = SyscallContext::new(&mut rt);
+        let mut event = create_ec_double_event::<Secp256k1>(&mut ctx, 0x1000, 0).unwrap();
+        event.curve = CurveType::Bls12381;
+        assert!(!event.has_curve_word_counts());
+    }
+
+    #[test]
+    fn test_bls12381_g2_add_and_double() {
+        let (p_ptr, q_ptr) = (0x1000, 0x2000);
+        let generator = Bls12381G2::ec_generator();
+        let two_g = Bls12381G2::ec_double(&generator);
+        let num_words = generator.to_words_le().len();
+        assert_eq!(num_words, 2 * ec_point_num_words(CurveType::Bls12381) as usize);
+        assert_eq!(ec_point_num_words(CurveType::Bls12381G2) as usize, num_words);
+
+        let mut rt = executor_with_words(&[(p_ptr, &generator.to_words_le())]);
+        let mut ctx = SyscallContext::new(&mut rt);
+        let event = create_ec_double_event::<Bls12381G2>(&mut ctx, p_ptr, 0).unwrap();
+        assert_eq!(event.p_memory_records.len(), num_words);
+        assert_eq!(ctx.slice_unsafe(p_ptr, num_words), two_g.to_words_le());
+
+        write_words(ctx.rt, q_ptr, &generator.to_words_le());
+        let event = create_ec_add_event::<Bls12381G2>(&mut ctx, p_ptr, q_ptr).unwrap();
+        assert_eq!(event.p_memory_records.len(), num_words);
+        assert_eq!(event.q_memory_records.len(), num_words);
+        let three_g = AffinePoint::<Bls12381G2>::from_words_le(&ctx.slice_unsafe(p_ptr, num_words));
+        assert!(three_g.g2_is_on_curve());
+        assert_eq!(three_g, Bls12381G2::ec_add(&generator, &two_g));
+    }
+
+    /// Multiplies `point` by a non-zero `scalar` with left-to-right double-and-add.
+    ///
+    /// [`AffinePoint::scalar_mul`] adds its running point to itself, which short Weierstrass
+    /// addition does not support.
+    fn double_and_add<E: EllipticCurve>(
+        point: &AffinePoint<E>,
+        scalar: &BigUint,
+    ) -> AffinePoint<E> {
+        let mut result = point.clone();
+        for i in (0..scalar.bits() - 1).rev() {
+            result = E::ec_double(&result);
+            if scalar.bit(i) {
+                result = E::ec_add(&result, point);
+            }
+        }
+        result
+    }
+
+    /// Multiplies the generator of `E` by `scalar` with add and double events following the
+    /// width-`window` NAF recoding of `scalar`, and returns the digits and the events.
+    fn wnaf_scalar_mul<E: EllipticCurve>(
+        scalar: &BigUint,
+        window: usize,
+    ) -> (Vec<i32>, Vec<EllipticCurveDoubleEvent>, Vec<EllipticCurveAddEvent>, AffinePoint<E>) {
+        let (p_ptr, q_ptr) = (0x1000, 0x2000);
+        let generator = E::ec_generator();
+        let multiple = |digit: i32| {
+            let point = double_and_add(&generator, &BigUint::from(digit.unsigned_abs()));
+            let point = if digit > 0 { point } else { E::ec_neg(&point) };
+            point.to_words_le()
+        };
+        let digits = ec_wnaf_digits(scalar, window);
+        let num_words = generator.to_words_le().len();
+
+        let mut rt = executor_with_words(&[(p_ptr, &multiple(*digits.last().unwrap()))]);
+        let mut ctx = SyscallContext::new(&mut rt);
+        let (mut doubles, mut adds) = (Vec::new(), Vec::new());
+        for &digit in digits[..digits.len() - 1].iter().rev() {
+            doubles.push(create_ec_double_event::<E>(&mut ctx, p_ptr, 0).unwrap());
+            ctx.clk += 1;
+            if digit != 0 {
+                write_words(ctx.rt, q_ptr, &multiple(digit));
+                adds.push(create_ec_add_event::<E>(&mut ctx, p_ptr, q_ptr).unwrap());
+                ctx.clk += 1;
+            }
+        }
+        let result = AffinePoint::from_words_le(&ctx.slice_unsafe(p_ptr, num_words));
+        (digits, doubles, adds, result)
+    }
+
+    /// Interleaves the double and add events in the order [`wnaf_scalar_mul`] created them.
+    fn wnaf_steps<'a>(
+        digits: &[i32],
+        doubles: &'a [EllipticCurveDoubleEvent],
+        adds: &'a [EllipticCurveAddEvent],
+    ) -> Vec<EcScalarMulStep<'a>> {
+        let (mut doubles, mut adds) = (doubles.iter(), adds.iter());
+        let mut steps = Vec::new();
+        for &digit in digits[..digits.len() - 1].iter().rev() {
+            steps.push(EcScalarMulStep::Double(doubles.next().unwrap()));
+            if digit != 0 {
+                steps.push(EcScalarMulStep::Add(adds.next().unwrap()));
+            }
+        }
+        steps
+    }
+
+    #[test]
+    fn test_wnaf_scalar_mul() {
+        let scalar = BigUint::from(0xC0FFEEu32);
+        let (digits, doubles, adds, result) = wnaf_scalar_mul::<Secp256k1>(&scalar, 4);
+        assert_eq!(digits, [0, 7, 0, 0, 0, -1, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 1, 0, 0, 0, 0, 0, 3]);
+        assert_eq!(result, double_and_add(&Secp256k1::ec_generator(), &scalar));
+
+        let generator = Secp256k1::ec_generator();
+        let steps = wnaf_steps(&digits, &doubles, &adds);
+        let validate = |digits: &[i32], steps: &[EcScalarMulStep]| {
+            validate_ec_wnaf_scalar_mul(&generator, &scalar, 4, digits, steps)
+        };
+        assert_eq!(validate(&digits, &steps), Ok(()));
+
+        // Invalid recodings.
+        let mut even = digits.clone();
+        even[0] = 2;
+        assert_eq!(validate(&even, &steps), Err(WnafError::DigitOutOfRange { index: 0, digit: 2 }));
+        let mut large = digits.clone();
+        large[1] = 9;
+        assert_eq!(
+            validate(&large, &steps),
+            Err(WnafError::DigitOutOfRange { index: 1, digit: 9 })
+        );
+        let mut close = digits.clone();
+        close[3] = 1;
+        assert_eq!(validate(&close, &steps), Err(WnafError::DigitsTooClose { index: 3 }));
+        let binary = (0..24).map(|i| i32::from(scalar.bit(i))).collect::<Vec<_>>();
+        assert_eq!(validate(&binary, &steps), Err(WnafError::DigitsTooClose { index: 2 }));
+        let mut other = digits.clone();
+        other[1] = -7;
+        assert_eq!(validate(&other, &steps), Err(WnafError::WrongScalar));
+
+        // Sequences that do not follow the recoding.
+        assert_eq!(validate(&digits, &steps[..steps.len() - 1]), Err(WnafError::MissingSteps));
+        let mut extra = steps.clone();
+        extra.push(steps[0]);
+        assert_eq!(
+            validate(&digits, &extra),
+            Err(WnafError::UnexpectedStep { index: steps.len() })
+        );
+        let mut swapped = steps.clone();
+        swapped.swap(5, 6);
+        assert_eq!(validate(&digits, &swapped), Err(WnafError::UnexpectedStep { index: 5 }));
+        let mut reordered = steps.clone();
+        reordered.swap(0, 1);
+        assert_eq!(validate(&digits, &reordered), Err(WnafError::WrongOperand { index: 0 }));
+    }
+
+    #[test]
+    fn test_add_event_with_lookup_id() {
+        let (p_ptr, q_ptr) = (0x1000, 0x1100);
+        let lookup_id = LookupId { a: 1, b: 2, c: 3, d: 4 };
+        let generator = Secp256k1::ec_generator();
+        let add = |with_lookup_id: bool| {
+            let mut rt = executor_with_words(&[
+                (p_ptr, &generator.to_words_le()),
+                (q_ptr, &Secp256k1::ec_double(&generator).to_words_le()),
+            ]);
+            let mut ctx = SyscallContext::new(&mut rt);
+            ctx.syscall_lookup_id = create_alu_lookup_id();
+            if with_lookup_id {
+                EllipticCurveAddEvent::new_with_lookup_id::<Secp256k1>(
+                    &mut ctx, p_ptr, q_ptr, lookup_id,
+                )
+                .unwrap()
+            } else {
+                create_ec_add_event::<Secp256k1>(&mut ctx, p_ptr, q_ptr).unwrap()
+            }
+        };
+
+        let replayed = add(true);
+        assert_eq!(replayed.lookup_id(), lookup_id);
+
+        // A captured event is re-materialized with the same contents as the replayed one.
+        let captured: EllipticCurveAddEvent =
+            bincode::deserialize(&bincode::serialize(&add(false)).unwrap()).unwrap();
+        assert_ne!(captured.lookup_id(), lookup_id);
+        let rematerialized = captured.with_lookup_id(lookup_id);
+        assert_eq!(rematerialized.content_hash(), replayed.content_hash());
+    }
+
+    #[test]
+    fn test_add_writes_after_reads() {
+        let (p_ptr, q_ptr) = (0x1000, 0x1100);
+        let generator = Secp256k1::ec_generator();
+        let mut rt = executor_with_words(&[
+            (p_ptr, &generator.to_words_le()),
+            (q_ptr, &Secp256k1::ec_double(&generator).to_words_le()),
+        ]);
+        let mut ctx = SyscallContext::new(&mut rt);
+        ctx.clk = 8;
+
+        let event = create_ec_add_event::<Secp256k1>(&mut ctx, p_ptr, q_ptr).unwrap();
+        assert_eq!(event.clk, 8);
+        assert!(event.q_memory_records.iter().all(|record| record.timestamp == event.clk));
+        assert!(event
+            .p_memory_records
+            .iter()
+            .all(|record| record.timestamp == event.clk + PRECOMPILE_WRITE_CLK_OFFSET));
+        assert_eq!(ctx.clk, 9);
+    }
+
+    #[test]
+    fn test_decompress_subgroup_check() {
+        let ptr = 0x1000;
+        for (x, in_subgroup) in [(Bls12381::ec_generator().x, true), (BigUint::from(4u32), false)] {
+            let mut x_bytes = x.to_bytes_le();
+            x_bytes.resize(48, 0);
+            for check_subgroup in [false, true] {
+                let mut rt = executor_with_words(&[(
+                    ptr + 48,
+                    &bytes_to_limbs_le_vec(&x_bytes, EcLimbSize::Bits32),
+                )]);
+                let mut ctx = SyscallContext::new(&mut rt);
+                ctx.syscall_code = SyscallCode::BLS12381_DECOMPRESS;
+                let result = create_ec_decompress_event_with_subgroup_check::<Bls12381>(
+                    &mut ctx,
+                    ptr,
+                    0,
+                    check_subgroup,
+                );
+                if in_subgroup || !check_subgroup {
+                    assert_eq!(result.unwrap().x_bytes, x_bytes);
+                } else {
+                    assert_eq!(
+                        result.unwrap_err(),
+                        SyscallError::NotInSubgroup {
+                            ptr,
+                            syscall: SyscallCode::BLS12381_DECOMPRESS
+                        }
+                    );
+                    // The point is not written.
+                    assert_eq!(ctx.slice_unsafe(ptr, 12), vec![0; 12]);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_decompress_event_bytes_round_trip() {
+        let event = decompress_generator::<Secp256k1>(1);
+        let without_witness = decompress_generator::<Bls12381>(0);
+
+        for event in [&event, &without_witness] {
+            let bytes = event.to_bytes();
+            let decoded = EllipticCurveDecompressEvent::from_bytes(&bytes).unwrap();
+            assert_eq!(decoded.content_hash(), event.content_hash());
+            assert_eq!(decoded.to_bytes(), bytes);
+        }
+
+        // Concatenated events are read back one at a time.
+        let mut stream = event.to_bytes();
+        without_witness.write_bytes(&mut stream);
+        let mut reader = stream.as_slice();
+        let first = EllipticCurveDecompressEvent::read_bytes(&mut reader).unwrap();
+        let second = EllipticCurveDecompressEvent::read_bytes(&mut reader).unwrap();
+        assert!(reader.is_empty());
+        assert_eq!(first.content_hash(), event.content_hash());
+        assert_eq!(second.content_hash(), without_witness.content_hash());
+        assert!(EllipticCurveDecompressEvent::from_bytes(&stream).is_none());
+
+        let bytes = event.to_bytes();
+        assert!(EllipticCurveDecompressEvent::from_bytes(&bytes[..bytes.len() - 1]).is_none());
+        let mut bad_sign_bit = bytes;
+        bad_sign_bit[29] = 2;
+        assert!(EllipticCurveDecompressEvent::from_bytes(&bad_sign_bit).is_none());
+    }
+
+    #[test]
+    fn test_big_endian_word_order() {
+        let (p_ptr, q_ptr) = (0x1000, 0x1100);
+        let generator = Secp256k1::ec_generator().to_words_le();
+        let doubled = Secp256k1::ec_double(&Secp256k1::ec_generator()).to_words_le();
+        let to_be =
+            |words: &[u32]| ec_point_reorder_words::<Secp256k1>(words, EcWordOrder::BigEndian);
+        assert_ne!(to_be(&generator), generator);
+        assert_eq!(to_be(&to_be(&generator)), generator);
+
+        let add = |p: &[u32], q: &[u32], word_order: EcWordOrder| {
+            let mut rt = executor_with_words(&[(p_ptr, p), (q_ptr, q)]);
+            let mut ctx = SyscallContext::new(&mut rt);
+            let event = create_ec_add_event_with_word_order::<Secp256k1>(
+                &mut ctx, p_ptr, q_ptr, word_order,
+            )
+            .unwrap();
+            assert_eq!((event.p, event.q), (generator.clone(), doubled.clone()));
+            ctx.slice_unsafe(p_ptr, p.len())
+        };
+        let le = add(&generator, &doubled, EcWordOrder::LittleEndian);
+        let be = add(&to_be(&generator), &to_be(&doubled), EcWordOrder::BigEndian);
+        assert_eq!(to_be(&be), le);
+
+        let double = |p: &[u32], word_order: EcWordOrder| {
+            let mut rt = executor_with_words(&[(p_ptr, p)]);
+            let mut ctx = SyscallContext::new(&mut rt);
+            let event =
+                create_ec_double_event_with_word_order::<Secp256k1>(&mut ctx, p_ptr, word_order)
+                    .unwrap();
+            assert_eq!(event.p, generator);
+            ctx.slice_unsafe(p_ptr, p.len())
+        };
+        let le = double(&generator, EcWordOrder::LittleEndian);
+        assert_eq!(le, doubled);
+        assert_eq!(to_be(&double(&to_be(&generator), EcWordOrder::BigEndian)), le);
+    }
+
+    #[test]
+    fn test_ec_add_access_range() {
+        fn accessed_addresses<E: EllipticCurve>(p_ptr: u32, q_ptr: u32) -> Vec<u32> {
+            let generator = E::ec_generator();
+            let doubled = E::ec_double(&generator);
+            let mut rt = executor_with_words(&[
+                (p_ptr, &generator.to_words_le()),
+                (q_ptr, &doubled.to_words_le()),
+            ]);
+            let mut ctx = SyscallContext::new(&mut rt);
+            ctx.clk = 4;
+            create_ec_add_event::<E>(&mut ctx, p_ptr, q_ptr).unwrap();
+            let mut addresses = rt
+                .state
+                .memory
+                .keys()
+                .filter(|addr| rt.state.memory.get(*addr).unwrap().timestamp > 0)
+                .collect::<Vec<_>>();
+            addresses.sort_unstable();
+            addresses
+        }
+
+        let (p_ptr, q_ptr) = (0x1000, 0x1100);
+        for (curve, addresses) in [
+            (CurveType::Secp256k1, accessed_addresses::<Secp256k1>(p_ptr, q_ptr)),
+            (CurveType::Bls12381, accessed_addresses::<Bls12381>(p_ptr, q_ptr)),
+        ] {
+            let expected = ec_add_access_range(p_ptr, q_ptr, curve)
+                .into_iter()
+                .flat_map(|range| range.step_by(4))
+                .collect::<Vec<_>>();
+            assert_eq!(addresses, expected);
+        }
+    }
+
+    /// Runs a secp256k1 add through the `ecall` instruction and returns the record, the CPU event
+    /// of the `ecall`, and the add event.
+    fn traced_add() -> (ExecutionRecord, CpuEvent, EllipticCurveAddEvent) {
+        let (p_ptr, q_ptr) = (0x1000, 0x1100);
+        let generator = Secp256k1::ec_generator();
+        let doubled = Secp256k1::ec_double(&generator);
+        let instructions = vec![
+            Instruction::new(Opcode::ADD, 5, 0, SyscallCode::SECP256K1_ADD as u32, false, true),
+            Instruction::new(Opcode::ADD, 10, 0, p_ptr, false, true),
+            Instruction::new(Opcode::ADD, 11, 0, q_ptr, false, true),
+            Instruction::new(Opcode::ECALL, 5, 10, 11, false, false),
+        ];
+        let mut program = Program::new(instructions, 0, 0);
+        for (ptr, point) in [(p_ptr, &generator), (q_ptr, &doubled)] {
+            for (i, word) in point.to_words_le().into_iter().enumerate() {
+                program.memory_image.insert(ptr + i as u32 * 4, word);
+            }
+        }
+
+        let mut rt = Executor::new(program, SP1CoreOpts::default());
+        rt.run().unwrap();
+        let record = rt.records.remove(0);
+        let cpu_event = *record
+            .cpu_events
+            .iter()
+            .find(|event| event.instruction.opcode == Opcode::ECALL)
+            .unwrap();
+        let add_event = record.secp256k1_add_events[0].clone();
+        (record, cpu_event, add_event)
+    }
+
+    #[test]
+    fn test_misaligned_pointer_traps() {
+        let (p_ptr, q_ptr) = (0x1002, 0x1100);
+        let instructions = vec![
+            Instruction::new(Opcode::ADD, 5, 0, SyscallCode::SECP256K1_ADD as u32, false, true),
+            Instruction::new(Opcode::ADD, 10, 0, p_ptr, false, true),
+            Instruction::new(Opcode::ADD, 11, 0, q_ptr, false, true),
+            Instruction::new(Opcode::ECALL, 5, 10, 11, false, false),
+        ];
+        let mut rt = Executor::new(Program::new(instructions, 0, 0), SP1CoreOpts::default());
+        let error = rt.run().unwrap_err();
+        assert!(matches!(
+            error,
+            ExecutionError::SyscallTrap(SyscallError::UnalignedPointer {
+                ptr: 0x1002,
+                syscall: SyscallCode::SECP256K1_ADD,
+            })
+        ));
+    }
+
+    #[test]
+    fn test_validate_ec_event_pointers() {
+        let (p_ptr, q_ptr) = (0x1000, 0x1100);
+        let generator = Secp256k1::ec_generator();
+        let mut rt = executor_with_words(&[
+            (p_ptr, &generator.to_words_le()),
+            (q_ptr, &Secp256k1::ec_double(&generator).to_words_le()),
+        ]);
+        let mut ctx = SyscallContext::new(&mut rt);
+        let add_event = create_ec_add_event::<Secp256k1>(&mut ctx, p_ptr, q_ptr).unwrap();
+        ctx.clk += 1;
+        let double_event = create_ec_double_event::<Secp256k1>(&mut ctx, q_ptr, 0).unwrap();
+        let decompress_event = decompress_generator::<Secp256k1>(0);
+
+        let bounds = 0x1000..0x1140;
+        assert!(validate_ec_event_pointers(&[add_event.clone()], bounds.clone()).is_ok());
+        assert!(validate_ec_double_event_pointers(&[double_event], bounds.clone()).is_ok());
+        assert!(validate_ec_decompress_event_pointers(&[decompress_event], bounds.clone()).is_ok());
+
+        // `q` ends one word past the bounds.
+        let bounds = 0x1000..0x113C;
+        assert_eq!(
+            validate_ec_event_pointers(&[add_event], bounds.clone()),
+            Err(PointerError::OutOfBounds { range: q_ptr..q_ptr + 64, bounds })
+        );
+    }
+
+    #[test]
+    fn test_add_rejects_point_at_infinity() {
+        let (p_ptr, q_ptr) = (0x1000, 0x1100);
+        let generator = Secp256k1::ec_generator().to_words_le();
+
+        let mut rt = executor_with_words(&[(p_ptr, &generator), (q_ptr, &[0; 16])]);
+        let mut ctx = SyscallContext::new(&mut rt);
+        ctx.syscall_code = SyscallCode::SECP256K1_ADD;
+        let error = create_ec_add_event::<Secp256k1>(&mut ctx, p_ptr, q_ptr).unwrap_err();
+        assert_eq!(
+            error,
+            SyscallError::PointAtInfinity { ptr: q_ptr, syscall: SyscallCode::SECP256K1_ADD }
+        );
+        assert_eq!(ctx.slice_unsafe(p_ptr, generator.len()), generator);
+    }
+
+    #[test]
+    fn test_identity_encodings() {
+        let (p_ptr, q_ptr) = (0x1000, 0x1100);
+        let generator = Bn254::ec_generator();
+        let doubled = Bn254::ec_double(&generator);
+        let mut flagged = doubled.to_words_le();
+        *flagged.last_mut().unwrap() |= 1 << 31;
+
+        for (ec_identity_encoding, identity) in [
+            (EcIdentityEncoding::Zero, vec![0; 16]),
+            (EcIdentityEncoding::TopBitFlag, flagged.clone()),
+            (EcIdentityEncoding::TopBitFlag, [vec![0; 15], vec![1 << 31]].concat()),
+        ] {
+            let program =
+                Program::new(vec![], 0, 0).with_ec_identity_encoding(ec_identity_encoding);
+            let mut rt = Executor::new(program, SP1CoreOpts::default());
+            write_words(&mut rt, p_ptr, &generator.to_words_le());
+            write_words(&mut rt, q_ptr, &identity);
+            let mut ctx = SyscallContext::new(&mut rt);
+
+            let error = create_ec_add_event::<Bn254>(&mut ctx, p_ptr, q_ptr).unwrap_err();
+            assert_eq!(
+                error,
+                SyscallError::PointAtInfinity { ptr: q_ptr, syscall: ctx.syscall_code }
+            );
+
+            // Points of the curve are not mistaken for the identity.
+            write_words(ctx.rt, q_ptr, &doubled.to_words_le());
+            let event = create_ec_add_event::<Bn254>(&mut ctx, p_ptr, q_ptr).unwrap();
+            assert_eq!(event.result_point::<Bn254>(), &generator + &doubled);
+        }
+
+        assert!(!EcIdentityEncoding::Zero.is_identity(&flagged));
+        assert!(!EcIdentityEncoding::TopBitFlag.is_identity(&[0; 16]));
+    }
+
+    #[test]
+    fn test_top_bit_flag_rejected_for_secp256k1() {
+        let (p_ptr, q_ptr) = (0x1000, 0x1100);
+        let generator = Secp256k1::ec_generator();
+        // The secp256k1 modulus fills 256 bits, so a point may have the top bit of `y` set.
+        let mut high_y = Secp256k1::ec_double(&generator);
+        while !high_y.y.bit(255) {
+            high_y = Secp256k1::ec_add(&high_y, &generator);
+        }
+        assert!(EcIdentityEncoding::TopBitFlag.is_identity(&high_y.to_words_le()));
+
+        for ec_identity_encoding in [EcIdentityEncoding::Zero, EcIdentityEncoding::TopBitFlag] {
+            let program =
+                Program::new(vec![], 0, 0).with_ec_identity_encoding(ec_identity_encoding);
+            let mut rt = Executor::new(program, SP1CoreOpts::default());
+            write_words(&mut rt, p_ptr, &generator.to_words_le());
+            write_words(&mut rt, q_ptr, &high_y.to_words_le());
+            let mut ctx = SyscallContext::new(&mut rt);
+            ctx.syscall_code = SyscallCode::SECP256K1_ADD;
+
+            let result = create_ec_add_event::<Secp256k1>(&mut ctx, p_ptr, q_ptr);
+            if ec_identity_encoding == EcIdentityEncoding::Zero {
+                let sum = result.unwrap().result_point::<Secp256k1>();
+                assert_eq!(sum, Secp256k1::ec_add(&generator, &high_y));
+            } else {
+                assert_eq!(
+                    result.unwrap_err(),
+                    SyscallError::AmbiguousIdentityEncoding { syscall: SyscallCode::SECP256K1_ADD }
+                );
+            }
+        }
+
+        assert!(EcIdentityEncoding::TopBitFlag.is_unambiguous(254));
+        assert!(EcIdentityEncoding::TopBitFlag.is_unambiguous(381));
+        assert!(!EcIdentityEncoding::TopBitFlag.is_unambiguous(256));
+    }
+
+    #[test]
+    fn test_invalid_arguments_return_errors() {
+        let mut rt = executor_with_words(&[]);
+        let mut ctx = SyscallContext::new(&mut rt);
+        ctx.syscall_code = SyscallCode::SECP256K1_DOUBLE;
+        let error = create_ec_double_event::<Secp256k1>(&mut ctx, 0x1001, 0).unwrap_err();
+        assert_eq!(
+            error,
+            SyscallError::UnalignedPointer { ptr: 0x1001, syscall: SyscallCode::SECP256K1_DOUBLE }
+        );
+
+        ctx.syscall_code = SyscallCode::SECP256K1_DECOMPRESS;
+        let error = create_ec_decompress_event::<Secp256k1>(&mut ctx, 0x1000, 2).unwrap_err();
+        assert_eq!(
+            error,
+            SyscallError::InvalidSignBit {
+                sign_bit: 2,
+                syscall: SyscallCode::SECP256K1_DECOMPRESS
+            }
+        );
+        assert!(ctx.rt.state.memory.get(0x1020).is_none());
+    }
+
+    #[test]
+    fn test_ec_events_across_memory_regions() {
+        // The heap starts right after the program image at `0x0020_0800`.
+        let (straddling_ptr, heap_ptr) = (STACK_TOP - 32, 0x0030_0000);
+        let generator = Secp256k1::ec_generator().to_words_le();
+        let doubled = Secp256k1::ec_double(&Secp256k1::ec_generator()).to_words_le();
+        let program = Program::new(vec![], 0x0020_0800, 0x0020_0800);
+        let mut rt = Executor::new(program, SP1CoreOpts::default());
+        write_words(&mut rt, straddling_ptr, &generator);
+        write_words(&mut rt, heap_ptr, &doubled);
+        let mut ctx = SyscallContext::new(&mut rt);
+
+        // A point that straddles the top of the stack is in no single region, but is still added.
+        assert_eq!(ctx.memory_region(heap_ptr, generator.len()), Some(MemoryRegion::Heap));
+        assert_eq!(ctx.memory_region(straddling_ptr, generator.len()), None);
+        let event = create_ec_add_event::<Secp256k1>(&mut ctx, straddling_ptr, heap_ptr).unwrap();
+        assert_eq!((event.p_region, event.q_region), (None, Some(MemoryRegion::Heap)));
+    }
+
+    #[test]
+    fn test_ec_events_tag_memory_regions() {
+        let (stack_ptr, heap_ptr) = (0x1000, 0x0030_0000);
+        let generator = Secp256k1::ec_generator().to_words_le();
+        let doubled = Secp256k1::ec_double(&Secp256k1::ec_generator()).to_words_le();
+        let program = Program::new(vec![], 0x0020_0800, 0x0020_0800);
+        let mut rt = Executor::new(program, SP1CoreOpts::default());
+        write_words(&mut rt, stack_ptr, &generator);
+        write_words(&mut rt, heap_ptr, &doubled);
+        let mut ctx = SyscallContext::new(&mut rt);
+
+        let event = create_ec_add_event::<Secp256k1>(&mut ctx, stack_ptr, heap_ptr).unwrap();
+        assert_eq!(event.p_region, Some(MemoryRegion::Stack));
+        assert_eq!(event.q_region, Some(MemoryRegion::Heap));
+
+        ctx.clk += 1;
+        let event = create_ec_double_event::<Secp256k1>(&mut ctx, heap_ptr, 0).unwrap();
+        assert_eq!(event.p_region, Some(MemoryRegion::Heap));
+    }
+
+    #[test]
+    fn test_decompress_near_end_of_memory() {
+        let mut rt = executor_with_words(&[]);
+        let mut ctx = SyscallContext::new(&mut rt);
+        ctx.syscall_code = SyscallCode::SECP256K1_DECOMPRESS;
+
+        // Both coordinates take 64 bytes, which would wrap around past `u32::MAX`.
+        let ptr = 0xFFFF_FFE0;
+        let error = create_ec_decompress_event::<Secp256k1>(&mut ctx, ptr, 0).unwrap_err();
+        assert_eq!(
+            error,
+            SyscallError::MemoryOutOfBounds { ptr, syscall: SyscallCode::SECP256K1_DECOMPRESS }
+        );
+        assert!(ctx.check_bounds(0xFFFF_FFC0, 16).is_ok());
+        assert!(ctx.check_bounds(0xFFFF_FFC4, 16).is_err());
+    }
+
+    #[test]
+    fn test_decompress_non_residue_x_returns_error() {
+        // 5^3 + 7 is not a square in the secp256k1 base field, so no point has x = 5.
+        let ptr = 0x1000;
+        let mut x_words = [0u32; 8];
+        x_words[0] = 5;
+        let mut rt = executor_with_words(&[(ptr + 32, &x_words)]);
+        let mut ctx = SyscallContext::new(&mut rt);
+        ctx.syscall_code = SyscallCode::SECP256K1_DECOMPRESS;
+
+        for sign_bit in [0, 1] {
+            let error =
+                create_ec_decompress_event::<Secp256k1>(&mut ctx, ptr, sign_bit).unwrap_err();
+            assert_eq!(
+                error,
+                SyscallError::NotOnCurve { ptr, syscall: SyscallCode::SECP256K1_DECOMPRESS }
+            );
+            ctx.clk += 1;
+        }
+        for i in 0..8 {
+            assert!(ctx.rt.state.memory.get(ptr + i * 4).is_none());
+        }
+    }
+
+    #[test]
+    fn test_syscall_lookup_ids() {
+        let (record, cpu_event, add_event) = traced_add();
+        record.assert_syscall_lookup_ids(&cpu_event, [add_event.lookup_id()]);
+    }
+
+    #[test]
+    #[should_panic(expected = "precompile event lookup id differs from its syscall's")]
+    fn test_inconsistent_syscall_lookup_ids() {
+        let (record, cpu_event, _) = traced_add();
+        record.assert_syscall_lookup_ids(&cpu_event, [create_alu_lookup_id()]);
+    }
+
+    #[test]
+    fn test_ec_memory_access_count() {
+        let (p_ptr, q_ptr) = (0x1000, 0x1100);
+        let generator = Secp256k1::ec_generator().to_words_le();
+        let mut rt = executor_with_words(&[(p_ptr, &generator), (q_ptr, &generator)]);
+        let mut ctx = SyscallContext::new(&mut rt);
+
+        let mut record = ExecutionRecord::default();
+        for _ in 0..3 {
+            ctx.clk += 4;
+            let event = create_ec_double_event::<Secp256k1>(&mut ctx, q_ptr, 0).unwrap();
+            record.secp256k1_double_events.push(event);
+            ctx.clk += 4;
+            let event = create_ec_add_event::<Secp256k1>(&mut ctx, p_ptr, q_ptr).unwrap();
+            record.secp256k1_add_events.push(event);
+        }
+        record.k256_decompress_events.push(decompress_generator::<Secp256k1>(0));
+        record.bls12381_decompress_events.push(decompress_generator::<Bls12381>(1));
+
+        // Each add touches two points of 16 words, each double one point, and each decompression
+        // reads `x` and writes `y`, one field element of 8 or 12 words each.
+        let expected = 3 * (2 * 16) + 3 * 16 + 2 * 8 + 2 * 12;
+        assert_eq!(record.ec_memory_access_count(), expected);
+    }
+
+    #[test]
+    fn test_memory_attribution() {
+        let (p_ptr, q_ptr) = (0x1000, 0x1100);
+        let generator = Secp256k1::ec_generator().to_words_le();
+        let mut rt = executor_with_words(&[(p_ptr, &generator), (q_ptr, &generator)]);
+        let mut ctx = SyscallContext::new(&mut rt);
+        ctx.syscall_code = SyscallCode::SECP256K1_ADD;
+        let double_event = create_ec_double_event::<Secp256k1>(&mut ctx, q_ptr, 0).unwrap();
+        ctx.clk += 4;
+        let add_event = create_ec_add_event::<Secp256k1>(&mut ctx, p_ptr, q_ptr).unwrap();
+        assert_eq!(double_event.check_memory_attribution(&ctx), Ok(()));
+        assert_eq!(add_event.check_memory_attribution(&ctx), Ok(()));
+
+        let error = SyscallError::MisattributedAccess {
+            shard: add_event.shard,
+            channel: add_event.channel,
+            syscall: SyscallCode::SECP256K1_ADD,
+        };
+        let mut other_shard = add_event.clone();
+        other_shard.q_memory_records[3].shard += 1;
+        assert_eq!(other_shard.check_memory_attribution(&ctx), Err(error));
+
+        // The accesses were made on another channel than the one the event records.
+        let mut other_channel = add_event.clone();
+        other_channel.channel += 1;
+        assert_eq!(
+            other_channel.check_memory_attribution(&ctx),
+            Err(SyscallError::MisattributedAccess {
+                shard: add_event.shard,
+                channel: add_event.channel + 1,
+                syscall: SyscallCode::SECP256K1_ADD,
+            })
+        );
+        ctx.rt.state.channel += 1;
+        assert_eq!(add_event.check_memory_attribution(&ctx), Err(error));
+        assert_eq!(other_channel.check_memory_attribution(&ctx), Ok(()));
+    }
+
+    #[test]
+    #[cfg(debug_assertions)]
+    #[should_panic(expected = "EC result has a different number of words than the point")]
+    fn test_result_width_mismatch() {
+        // `to_words_le` always sizes the point by the curve's `NumWords`, so no curve can produce a
+        // short result. Check the assert on a point with its last word dropped instead.
+        let num_words = ec_point_num_words(CurveType::Secp256k1) as usize;
+        let mut words = Secp256k1::ec_generator().to_words_le();
+        debug_assert_result_width(&words, num_words);
+        words.pop();
+        debug_assert_result_width(&words, num_words);
+    }
+
+    #[test]
+    fn test_limb_sizes_decode_to_same_point() {
+        let point = Secp256k1::ec_double(&Secp256k1::ec_generator());
+        let limbs_16 = ec_point_to_limbs_le(&point, EcLimbSize::Bits16);
+        let limbs_32 = ec_point_to_limbs_le(&point, EcLimbSize::Bits32);
+        assert_eq!(limbs_16.len(), 2 * limbs_32.len());
+        assert!(limbs_16.iter().all(|limb| u16::try_from(*limb).is_ok()));
+        assert_eq!(limbs_32, point.to_words_le());
+
+        let from_16 = ec_point_from_limbs_le::<Secp256k1>(&limbs_16, EcLimbSize::Bits16);
+        let from_32 = ec_point_from_limbs_le::<Secp256k1>(&limbs_32, EcLimbSize::Bits32);
+        assert_eq!(from_16, point);
+        assert_eq!(from_32, point);
+    }
+
+    #[test]
+    fn test_events_merkle_root() {
+        let (p_ptr, q_ptr) = (0x1000, 0x1100);
+        let mut point = Secp256k1::ec_generator();
+        let mut rt = executor_with_words(&[]);
+        let mut ctx = SyscallContext::new(&mut rt);
+        let mut events = (0..3)
+            .map(|_| {
+                point = Secp256k1::ec_double(&point);
+                write_words(ctx.rt, p_ptr, &point.to_words_le());
+                write_words(ctx.rt, q_ptr, &Secp256k1::ec_generator().to_words_le());
+                ctx.clk += 1;
+                create_ec_add_event::<Secp256k1>(&mut ctx, p_ptr, q_ptr).unwrap()
+            })
+            .collect::<Vec<_>>();
+
+        let root = events_merkle_root(&events);
+        assert_eq!(root, events_merkle_root(&events.clone()));
+        assert_ne!(root, events_merkle_root(&events[..2]));
+        assert_eq!(events_merkle_root::<EllipticCurveAddEvent>(&[]), [0u8; 32]);
+
+        let content_hash = events[1].content_hash();
+        events[1].clk += 1;
+        assert_ne!(events[1].content_hash(), content_hash);
+        assert_ne!(events_merkle_root(&events), root);
+    }
+
+    #[test]
+    fn test_decompress_sign_bit_is_canonical() {
+        for sign_bit in [0, 1] {
+            let event = decompress_generator::<Secp256k1>(sign_bit);
+            assert_eq!(event.canonical_sign_bit::<Secp256k1>(), sign_bit == 1);
+            assert!(event.has_canonical_sign_bit::<Secp256k1>());
+
+            let event = decompress_generator::<Bls12381>(sign_bit);
+            assert_eq!(event.canonical_sign_bit::<Bls12381>(), sign_bit == 1);
+            assert!(event.has_canonical_sign_bit::<Bls12381>());
+        }
+
+        // A sign bit that disagrees with the recovered `y` is detected.
+        let mut event = decompress_generator::<Bls12381>(1);
+        event.sign_bit = false;
+        assert!(!event.has_canonical_sign_bit::<Bls12381>());
+    }
+
+    #[test]
+    fn test_secp256r1_decompress() {
+        use num::Num;
+
+        assert_eq!(<Secp256r1 as EllipticCurveParameters>::BaseField::modulus().bits(), 256);
+        assert_eq!(
+            <<Secp256r1 as EllipticCurveParameters>::BaseField as NumWords>::WordsCurvePoint::USIZE,
+            16
+        );
+        assert_eq!(ec_point_num_words(CurveType::Secp256r1), 16);
+
+        // The generator and its double, from the SEC 2 parameters of P-256.
+        let vectors = [
+            (
+                "6b17d1f2e12c4247f8bce6e563a440f277037d812deb33a0f4a13945d898c296",
+                "4fe342e2fe1a7f9b8ee7eb4a7c0f9e162bce33576b315ececbb6406837bf51f5",
+            ),
+            (
+                "7cf27b188d034f7e8a52380304b51ac3c08969e277f21b35a60b48fc47669978",
+                "07775510db8ed040293d9ac69f7430dbba7dade63ce982299e04b79d227873d1",
+            ),
+        ];
+        let modulus = <Secp256r1 as EllipticCurveParameters>::BaseField::modulus();
+        for (x, y) in vectors {
+            let x = BigUint::from_str_radix(x, 16).unwrap();
+            let y = BigUint::from_str_radix(y, 16).unwrap();
+            for sign_bit in [0, 1] {
+                let expected_y =
+                    if y.bit(0) == (sign_bit == 1) { y.clone() } else { &modulus - &y };
+                let mut expected_bytes = expected_y.to_bytes_le();
+                expected_bytes.resize(32, 0u8);
+
+                let event = decompress_x::<Secp256r1>(&x, sign_bit);
+                assert_eq!(event.decompressed_y_bytes, expected_bytes);
+                let written =
+                    event.y_memory_records.iter().map(|record| record.value).collect::<Vec<_>>();
+                assert_eq!(written, bytes_to_limbs_le_vec(&expected_bytes, EcLimbSize::Bits32));
+                assert!(event.has_canonical_sign_bit::<Secp256r1>());
+            }
+        }
+    }
+
+    #[test]
+    fn test_decompress_unreduced_x_returns_error() {
+        fn decompress_modulus<E: EllipticCurve>(syscall: SyscallCode) {
+            let ptr = 0x1000;
+            let num_limbs = <E::BaseField as NumLimbs>::Limbs::USIZE;
+            let mut x_bytes = E::BaseField::modulus().to_bytes_le();
+            x_bytes.resize(num_limbs, 0);
+            let mut rt = executor_with_words(&[(
+                ptr + num_limbs as u32,
+                &bytes_to_limbs_le_vec(&x_bytes, EcLimbSize::Bits32),
+            )]);
+            let mut ctx = SyscallContext::new(&mut rt);
+            ctx.syscall_code = syscall;
+
+            let error = create_ec_decompress_event::<E>(&mut ctx, ptr, 0).unwrap_err();
+            assert_eq!(error, SyscallError::NotOnCurve { ptr, syscall });
+            assert!(ctx.rt.state.memory.get(ptr).is_none());
+        }
+        decompress_modulus::<Secp256k1>(SyscallCode::SECP256K1_DECOMPRESS);
+        decompress_modulus::<Bls12381>(SyscallCode::BLS12381_DECOMPRESS);
+    }
+
+    #[test]
+    fn test_decompressed_y_bytes_is_reduced() {
+        let mut rt = executor_with_words(&[]);
+        let mut ctx = SyscallContext::new(&mut rt);
+        ctx.syscall_code = SyscallCode::BLS12381_DECOMPRESS;
+        let modulus = <Bls12381 as EllipticCurveParameters>::BaseField::modulus();
+        let bytes = ec_decompressed_y_bytes::<Bls12381>(&ctx, 0x1000, &(&modulus - 1u32)).unwrap();
+        assert_eq!(BigUint::from_bytes_le(&bytes), &modulus - 1u32);
+        assert_eq!(
+            ec_decompressed_y_bytes::<Bls12381>(&ctx, 0x1000, &modulus),
+            Err(SyscallError::NotOnCurve {
+                ptr: 0x1000,
+                syscall: SyscallCode::BLS12381_DECOMPRESS
+            })
+        );
     }
 }
@@ -1,9 +1,18 @@
 use serde::{Deserialize, Serialize};
-use sp1_curves::{edwards::WORDS_FIELD_ELEMENT, COMPRESSED_POINT_BYTES, NUM_BYTES_FIELD_ELEMENT};
+use sp1_curves::{
+    curve25519_dalek::CompressedEdwardsY,
+    edwards::{ed25519::try_decompress, EdwardsParameters, WORDS_FIELD_ELEMENT},
+    CurveType, EllipticCurve, COMPRESSED_POINT_BYTES, NUM_BYTES_FIELD_ELEMENT,
+};
+use sp1_primitives::consts::{bytes_to_words_le, words_to_bytes_le};
 
-use crate::events::{
-    memory::{MemoryReadRecord, MemoryWriteRecord},
-    LookupId,
+use crate::{
+    events::{
+        create_ec_add_event,
+        memory::{MemoryReadRecord, MemoryWriteRecord},
+        EllipticCurveAddEvent, LookupId,
+    },
+    syscalls::{SyscallContext, SyscallError},
 };
 
 /// Edwards Decompress Event.
@@ -32,3 +41,177 @@ pub struct EdDecompressEvent {
     /// The memory records for the y coordinate.
     pub y_memory_records: [MemoryReadRecord; WORDS_FIELD_ELEMENT],
 }
+
+/// Create a twisted Edwards add event. It reads the points at `p_ptr` and `q_ptr`, adds them with
+/// the Edwards addition law, and writes the result back to `p_ptr`.
+///
+/// Points are laid out as for the Weierstrass curves: the little-endian `x` words followed by the
+/// little-endian `y` words.
+pub fn create_ed_add_event<E: EllipticCurve + EdwardsParameters>(
+    rt: &mut SyscallContext,
+    p_ptr: u32,
+    q_ptr: u32,
+) -> Result<EllipticCurveAddEvent, SyscallError> {
+    match E::CURVE_TYPE {
+        CurveType::Ed25519 => create_ec_add_event::<E>(rt, p_ptr, q_ptr),
+        _ => panic!("Unsupported curve"),
+    }
+}
+
+/// Create a twisted Edwards decompress event.
+///
+/// Unlike the Weierstrass decompress events, which recover `y` from `x`, this recovers `x` from
+/// `y`. The slice at `slice_ptr` holds the `x` words followed by the `y` words. The `y` words are
+/// read as the RFC 8032 encoding without its sign bit: the top bit of the last byte is ignored,
+/// and `sign` gives the parity of `x` instead. The recovered `x` is written to the first half of
+/// the slice.
+///
+/// Returns a [`SyscallError`], without writing `x`, if the pointer is not word aligned, `sign` is
+/// neither zero nor one, or no point of the curve has the given `y`.
+pub fn create_ed_decompress_event<E: EdwardsParameters>(
+    rt: &mut SyscallContext,
+    slice_ptr: u32,
+    sign: u32,
+) -> Result<EdDecompressEvent, SyscallError> {
+    let start_clk = rt.clk;
+    rt.check_aligned(slice_ptr)?;
+    if sign > 1 {
+        return Err(SyscallError::InvalidSignBit { sign_bit: sign, syscall: rt.syscall_code });
+    }
+
+    let (y_memory_records_vec, y_vec) =
+        rt.mr_slice(slice_ptr + (COMPRESSED_POINT_BYTES as u32), WORDS_FIELD_ELEMENT);
+    let y_memory_records: [MemoryReadRecord; 8] = y_memory_records_vec.try_into().unwrap();
+
+    let y_bytes: [u8; COMPRESSED_POINT_BYTES] = words_to_bytes_le(&y_vec);
+
+    // Re-insert the sign bit into the last bit of y for the CompressedEdwardsY format.
+    let mut compressed_edwards_y: [u8; COMPRESSED_POINT_BYTES] = y_bytes;
+    compressed_edwards_y[compressed_edwards_y.len() - 1] &= 0b0111_1111;
+    compressed_edwards_y[compressed_edwards_y.len() - 1] |= (sign as u8) << 7;
+
+    let decompress_fn = match E::CURVE_TYPE {
+        CurveType::Ed25519 => try_decompress,
+        _ => panic!("Unsupported curve"),
+    };
+    let decompressed = decompress_fn(&CompressedEdwardsY(compressed_edwards_y))
+        .ok_or(SyscallError::NotOnCurve { ptr: slice_ptr, syscall: rt.syscall_code })?;
+
+    let mut decompressed_x_bytes = decompressed.x.to_bytes_le();
+    decompressed_x_bytes.resize(32, 0u8);
+    let decompressed_x_words: [u32; WORDS_FIELD_ELEMENT] = bytes_to_words_le(&decompressed_x_bytes);
+
+    let x_memory_records_vec = rt.mw_slice(slice_ptr, &decompressed_x_words);
+    let x_memory_records: [MemoryWriteRecord; 8] = x_memory_records_vec.try_into().unwrap();
+
+    Ok(EdDecompressEvent {
+        lookup_id: rt.syscall_lookup_id,
+        shard: rt.current_shard(),
+        channel: rt.current_channel(),
+        clk: start_clk,
+        ptr: slice_ptr,
+        sign: sign != 0,
+        y_bytes,
+        decompressed_x_bytes: decompressed_x_bytes.try_into().unwrap(),
+        x_memory_records,
+        y_memory_records,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use num::BigUint;
+    use sp1_curves::{
+        edwards::ed25519::{Ed25519, Ed25519BaseField, Ed25519Parameters},
+        params::FieldParameters,
+        AffinePoint,
+    };
+
+    use super::*;
+    use crate::{programs::tests::executor_with_words, syscalls::SyscallCode};
+
+    /// The RFC 8032 encodings of the base point and of the public keys of test vectors 1 to 3.
+    const RFC_8032_POINTS: [&str; 4] = [
+        "5866666666666666666666666666666666666666666666666666666666666666",
+        "d75a980182b10ab7d54bfed3c964073a0ee172f3daa62325af021a68f707511a",
+        "3d4017c3e843895a92b70aa74d1b7ebc9c982ccf2ec4968cc0cd55f12af4660c",
+        "fc51cd8e6218a1a38da47ed00230f0580816ed13ba3303ac5deb911548908025",
+    ];
+
+    /// The RFC 8032 encodings of twice and three times the base point.
+    const BASE_POINT_2: &str = "c9a3f86aae465f0e56513864510f3997561fa2c9e85ea21dc2292309f3cd6022";
+    const BASE_POINT_3: &str = "d4b4f5784868c3020403246717ec169ff79e26608ea126a1ab69ee77d1b16712";
+
+    /// Encodes a point as in RFC 8032: `y` in little-endian, with the parity of `x` in the top
+    /// bit.
+    fn encode(point: &AffinePoint<Ed25519>) -> String {
+        let mut bytes = point.y.to_bytes_le();
+        bytes.resize(32, 0);
+        bytes[31] |= u8::from(point.x.bit(0)) << 7;
+        hex::encode(bytes)
+    }
+
+    /// Decompresses `encoding` through the precompile and returns the event and the point.
+    fn decompress(encoding: &str) -> (EdDecompressEvent, AffinePoint<Ed25519>) {
+        let bytes: [u8; 32] = hex::decode(encoding).unwrap().try_into().unwrap();
+        let sign = u32::from(bytes[31] >> 7);
+        let ptr = 0x1000;
+        let mut rt = executor_with_words(&[(ptr + 32, &bytes_to_words_le::<8>(&bytes))]);
+        let mut ctx = SyscallContext::new(&mut rt);
+        let event = create_ed_decompress_event::<Ed25519Parameters>(&mut ctx, ptr, sign).unwrap();
+
+        let x = BigUint::from_bytes_le(&event.decompressed_x_bytes);
+        let y = BigUint::from_bytes_le(&bytes) % (BigUint::from(1u32) << 255);
+        (event, AffinePoint::new(x, y))
+    }
+
+    #[test]
+    fn test_ed_decompress_rfc8032_vectors() {
+        for encoding in RFC_8032_POINTS {
+            let (event, point) = decompress(encoding);
+            assert_eq!(encode(&point), encoding);
+            assert_eq!(
+                event.x_memory_records.map(|record| record.value),
+                bytes_to_words_le::<8>(&event.decompressed_x_bytes)
+            );
+
+            // -x^2 + y^2 = 1 + d x^2 y^2.
+            let modulus = Ed25519BaseField::modulus();
+            let (xx, yy) = (&point.x * &point.x % &modulus, &point.y * &point.y % &modulus);
+            let lhs = (&yy + &modulus - &xx) % &modulus;
+            let rhs = (Ed25519Parameters::d_biguint() * &xx % &modulus * &yy + 1u32) % &modulus;
+            assert_eq!(lhs, rhs);
+        }
+
+        let (_, base_point) = decompress(RFC_8032_POINTS[0]);
+        assert_eq!(base_point, Ed25519::ec_generator());
+    }
+
+    #[test]
+    fn test_ed_decompress_invalid_y_returns_error() {
+        // 2 is not the y coordinate of any point of Ed25519.
+        let ptr = 0x1000;
+        let mut rt = executor_with_words(&[(ptr + 32, &[2, 0, 0, 0, 0, 0, 0, 0])]);
+        let mut ctx = SyscallContext::new(&mut rt);
+        ctx.syscall_code = SyscallCode::ED_DECOMPRESS;
+
+        let error = create_ed_decompress_event::<Ed25519Parameters>(&mut ctx, ptr, 0).unwrap_err();
+        assert_eq!(error, SyscallError::NotOnCurve { ptr, syscall: SyscallCode::ED_DECOMPRESS });
+        assert!(ctx.rt.state.memory.get(ptr).is_none());
+    }
+
+    #[test]
+    fn test_ed_add_rfc8032_vectors() {
+        let (p_ptr, q_ptr) = (0x1000, 0x1100);
+        let generator = Ed25519::ec_generator().to_words_le();
+        let mut rt = executor_with_words(&[(p_ptr, &generator), (q_ptr, &generator)]);
+        let mut ctx = SyscallContext::new(&mut rt);
+
+        let event = create_ed_add_event::<Ed25519>(&mut ctx, p_ptr, q_ptr).unwrap();
+        assert_eq!(encode(&event.result_point::<Ed25519>()), BASE_POINT_2);
+
+        let event = create_ed_add_event::<Ed25519>(&mut ctx, p_ptr, q_ptr).unwrap();
+        assert_eq!(event.p_point::<Ed25519>(), decompress(BASE_POINT_2).1);
+        assert_eq!(encode(&event.result_point::<Ed25519>()), BASE_POINT_3);
+    }
+}
@@ -0,0 +1,20 @@
+use serde::{Deserialize, Serialize};
+
+use crate::syscalls::SyscallCode;
+
+/// Failed Precompile Event.
+///
+/// This event is emitted when the guest invokes a precompile that is disabled for its program.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FailedPrecompileEvent {
+    /// The shard number.
+    pub shard: u32,
+    /// The clock cycle.
+    pub clk: u32,
+    /// The syscall code of the precompile.
+    pub syscall_code: SyscallCode,
+    /// The first argument of the syscall.
+    pub arg1: u32,
+    /// The second argument of the syscall.
+    pub arg2: u32,
+}
@@ -1,5 +1,6 @@
 mod ec;
 mod edwards;
+mod failed;
 mod fptower;
 mod keccak256_permute;
 mod sha256_compress;
@@ -8,6 +9,7 @@ mod uint256;
 
 pub use ec::*;
 pub use edwards::*;
+pub use failed::*;
 pub use fptower::*;
 pub use keccak256_permute::*;
 pub use sha256_compress::*;
@@ -6,6 +6,7 @@ use std::{
 };
 
 use rand::{thread_rng, Rng};
+use sha2::{Digest, Sha256};
 
 /// A unique identifier for lookups.
 ///
@@ -73,3 +74,48 @@ where
     let width = table_with_string_counts.peek().map(|(_, b)| b.len()).unwrap_or_default();
     table_with_string_counts.map(move |(label, count)| format!("{count:>width$} {label}"))
 }
+
+/// Returns the SHA-256 digest of the bincode encoding of an event.
+#[must_use]
+pub fn event_content_hash<T: Serialize>(event: &T) -> [u8; 32] {
+    let bytes = bincode::serialize(event).expect("failed to serialize event");
+    Sha256::digest(bytes).into()
+}
+
+/// Computes the root of a binary SHA-256 Merkle tree over the content hashes of `events`.
+///
+/// Leaves and inner nodes are domain separated by a `0x00` and `0x01` prefix respectively, and a
+/// node without a sibling is carried up to the next level unchanged. The root of no events is all
+/// zeros.
+#[must_use]
+pub fn events_merkle_root<T: Serialize>(events: &[T]) -> [u8; 32] {
+    let mut level = events
+        .iter()
+        .map(|event| {
+            let mut hasher = Sha256::new();
+            hasher.update([0u8]);
+            hasher.update(event_content_hash(event));
+            <[u8; 32]>::from(hasher.finalize())
+        })
+        .collect::<Vec<_>>();
+    if level.is_empty() {
+        return [0u8; 32];
+    }
+    while level.len() > 1 {
+        level = level
+            .chunks(2)
+            .map(|pair| match pair {
+                [left, right] => {
+                    let mut hasher = Sha256::new();
+                    hasher.update([1u8]);
+                    hasher.update(left);
+                    hasher.update(right);
+                    hasher.finalize().into()
+                }
+                [node] => *node,
+                _ => unreachable!(),
+            })
+            .collect();
+    }
+    level[0]
+}
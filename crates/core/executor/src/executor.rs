@@ -12,9 +12,9 @@ use thiserror::Error;
 use crate::{
     context::SP1Context,
     events::{
-        create_alu_lookup_id, create_alu_lookups, AluEvent, CpuEvent, LookupId,
-        MemoryAccessPosition, MemoryInitializeFinalizeEvent, MemoryReadRecord, MemoryRecord,
-        MemoryWriteRecord,
+        create_alu_lookup_id, create_alu_lookups, AluEvent, CpuEvent, FailedPrecompileEvent,
+        LookupId, MemoryAccessPosition, MemoryInitializeFinalizeEvent, MemoryReadRecord,
+        MemoryRecord, MemoryWriteRecord,
     },
     hook::{HookEnv, HookRegistry},
     memory::{Entry, PagedMemory},
@@ -22,8 +22,8 @@ use crate::{
     report::ExecutionReport,
     state::{ExecutionState, ForkState},
     subproof::{DefaultSubproofVerifier, SubproofVerifier},
-    syscalls::{default_syscall_map, Syscall, SyscallCode, SyscallContext},
-    Instruction, Opcode, Program, Register,
+    syscalls::{default_syscall_map, Syscall, SyscallCode, SyscallContext, SyscallError},
+    Instruction, MemoryRegionMap, Opcode, Program, Register,
 };
 
 /// An executor for the SP1 RISC-V zkVM.
@@ -34,6 +34,9 @@ pub struct Executor<'a> {
     /// The program.
     pub program: Arc<Program>,
 
+    /// The layout of the program's memory into regions.
+    pub memory_regions: MemoryRegionMap,
+
     /// The state of the execution.
     pub state: ExecutionState,
 
@@ -148,6 +151,10 @@ pub enum ExecutionError {
     /// The program ended in unconstrained mode.
     #[error("program ended in unconstrained mode")]
     EndInUnconstrained(),
+
+    /// The execution failed because a syscall trapped on its arguments.
+    #[error("syscall trapped: {0}")]
+    SyscallTrap(SyscallError),
 }
 
 macro_rules! assert_valid_memory_access {
@@ -198,6 +205,7 @@ impl<'a> Executor<'a> {
             record,
             records: vec![],
             state: ExecutionState::new(program.pc_start),
+            memory_regions: MemoryRegionMap::new(&program),
             program,
             memory_accesses: MemoryAccessRecord::default(),
             shard_size: (opts.shard_size as u32) * 4,
@@ -944,12 +952,29 @@ impl<'a> Executor<'a> {
                 let syscall_impl = self.get_syscall(syscall).cloned();
                 let mut precompile_rt = SyscallContext::new(self);
                 precompile_rt.syscall_lookup_id = syscall_lookup_id;
+                precompile_rt.syscall_code = syscall;
                 let (precompile_next_pc, precompile_cycles, returned_exit_code) =
                     if let Some(syscall_impl) = syscall_impl {
                         // Executing a syscall optionally returns a value to write to the t0
                         // register. If it returns None, we just keep the
                         // syscall_id in t0.
-                        let res = syscall_impl.execute(&mut precompile_rt, b, c);
+                        let res = if precompile_rt.rt.program.is_syscall_enabled(syscall) {
+                            syscall_impl.execute(&mut precompile_rt, b, c)
+                        } else {
+                            let event = FailedPrecompileEvent {
+                                shard: precompile_rt.current_shard(),
+                                clk,
+                                syscall_code: syscall,
+                                arg1: b,
+                                arg2: c,
+                            };
+                            precompile_rt.record_mut().failed_precompile_events.push(event);
+                            precompile_rt.trap(SyscallError::DisabledPrecompile { syscall });
+                            None
+                        };
+                        if let Some(error) = precompile_rt.error {
+                            return Err(ExecutionError::SyscallTrap(error));
+                        }
                         if let Some(val) = res {
                             a = val;
                         } else {
@@ -1433,9 +1458,12 @@ mod tests {
         ssz_withdrawals_program,
     };
 
-    use crate::Register;
+    use crate::{
+        syscalls::{SyscallCode, SyscallError},
+        Register,
+    };
 
-    use super::{Executor, Instruction, Opcode, Program};
+    use super::{ExecutionError, Executor, Instruction, Opcode, Program};
 
     fn _assert_send<T: Send>() {}
 
@@ -1466,6 +1494,34 @@ mod tests {
         runtime.run().unwrap();
     }
 
+    #[test]
+    fn test_disabled_precompile_traps() {
+        let instructions = vec![
+            Instruction::new(Opcode::ADD, 5, 0, SyscallCode::KECCAK_PERMUTE as u32, false, true),
+            Instruction::new(Opcode::ADD, 10, 0, 100, false, true),
+            Instruction::new(Opcode::ECALL, 5, 10, 11, false, false),
+        ];
+        let program = Program::new(instructions, 0, 0);
+
+        let enabled = program.clone().with_enabled_precompiles([SyscallCode::KECCAK_PERMUTE]);
+        let mut runtime = Executor::new(enabled, SP1CoreOpts::default());
+        runtime.run().unwrap();
+
+        let disabled = program.with_enabled_precompiles([SyscallCode::SHA_EXTEND]);
+        let mut runtime = Executor::new(disabled, SP1CoreOpts::default());
+        assert!(matches!(
+            runtime.run(),
+            Err(ExecutionError::SyscallTrap(SyscallError::DisabledPrecompile {
+                syscall: SyscallCode::KECCAK_PERMUTE
+            }))
+        ));
+        let [event] = runtime.record.failed_precompile_events[..] else {
+            panic!("expected one failed precompile event");
+        };
+        assert_eq!(event.syscall_code, SyscallCode::KECCAK_PERMUTE);
+        assert_eq!(event.arg1, 100);
+    }
+
     #[test]
     #[should_panic]
     fn test_panic() {
@@ -32,6 +32,7 @@ mod program;
 #[cfg(any(test, feature = "programs"))]
 pub mod programs;
 mod record;
+mod region;
 mod register;
 mod report;
 mod state;
@@ -45,6 +46,7 @@ pub use instruction::*;
 pub use opcode::*;
 pub use program::*;
 pub use record::*;
+pub use region::*;
 pub use register::*;
 pub use report::*;
 pub use state::*;
@@ -2,6 +2,8 @@
 
 use std::{collections::BTreeMap, fs::File, io::Read};
 
+use hashbrown::HashSet;
+
 use p3_field::Field;
 use serde::{Deserialize, Serialize};
 use sp1_stark::air::MachineProgram;
@@ -9,6 +11,7 @@ use sp1_stark::air::MachineProgram;
 use crate::{
     disassembler::{transpile, Elf},
     instruction::Instruction,
+    syscalls::SyscallCode,
 };
 
 /// A program that can be executed by the SP1 zkVM.
@@ -25,13 +28,28 @@ pub struct Program {
     pub pc_base: u32,
     /// The initial memory image, useful for global constants.
     pub memory_image: BTreeMap<u32, u32>,
+    /// The precompiles the program may invoke. If `None`, every precompile is enabled.
+    pub enabled_precompiles: Option<HashSet<SyscallCode>>,
+    /// How the program encodes the point at infinity to the elliptic curve precompiles.
+    pub ec_identity_encoding: EcIdentityEncoding,
+    /// Whether the decompress precompiles reject points outside the prime-order subgroup of the
+    /// curve. Off by default, since the check costs a scalar multiplication per point.
+    pub ec_subgroup_check: bool,
 }
 
 impl Program {
     /// Create a new [Program].
     #[must_use]
     pub const fn new(instructions: Vec<Instruction>, pc_start: u32, pc_base: u32) -> Self {
-        Self { instructions, pc_start, pc_base, memory_image: BTreeMap::new() }
+        Self {
+            instructions,
+            pc_start,
+            pc_base,
+            memory_image: BTreeMap::new(),
+            enabled_precompiles: None,
+            ec_identity_encoding: EcIdentityEncoding::Zero,
+            ec_subgroup_check: false,
+        }
     }
 
     /// Disassemble a RV32IM ELF to a program that be executed by the VM.
@@ -52,6 +70,9 @@ impl Program {
             pc_start: elf.pc_start,
             pc_base: elf.pc_base,
             memory_image: elf.memory_image,
+            enabled_precompiles: None,
+            ec_identity_encoding: EcIdentityEncoding::Zero,
+            ec_subgroup_check: false,
         })
     }
 
@@ -65,6 +86,82 @@ impl Program {
         File::open(path)?.read_to_end(&mut elf_code)?;
         Program::from(&elf_code)
     }
+
+    /// Restrict the precompiles the program may invoke to `precompiles`.
+    #[must_use]
+    pub fn with_enabled_precompiles(
+        mut self,
+        precompiles: impl IntoIterator<Item = SyscallCode>,
+    ) -> Self {
+        self.enabled_precompiles = Some(precompiles.into_iter().collect());
+        self
+    }
+
+    /// Set how the program encodes the point at infinity to the elliptic curve precompiles.
+    #[must_use]
+    pub const fn with_ec_identity_encoding(mut self, encoding: EcIdentityEncoding) -> Self {
+        self.ec_identity_encoding = encoding;
+        self
+    }
+
+    /// Set whether the decompress precompiles reject points outside the prime-order subgroup.
+    #[must_use]
+    pub const fn with_ec_subgroup_check(mut self, ec_subgroup_check: bool) -> Self {
+        self.ec_subgroup_check = ec_subgroup_check;
+        self
+    }
+
+    /// Whether the program may invoke the given syscall.
+    ///
+    /// System operations that are not precompiles are always enabled.
+    #[must_use]
+    pub fn is_syscall_enabled(&self, syscall: SyscallCode) -> bool {
+        match &self.enabled_precompiles {
+            Some(precompiles) if syscall.is_precompile() => precompiles.contains(&syscall),
+            _ => true,
+        }
+    }
+}
+
+/// An encoding of the point at infinity in the affine words passed to the elliptic curve
+/// precompiles.
+///
+/// The affine encoding has no representation of the identity, so libraries use a value that is not
+/// a point of the curve. Guests ported from a library keep its convention.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum EcIdentityEncoding {
+    /// Every word of the point is zero.
+    #[default]
+    Zero,
+    /// The most significant bit of the last word of the point is set, and the other bits are
+    /// ignored. This is the infinity flag of serializations that pack flags into the unused top
+    /// bits of `y`, so it is only unambiguous for curves whose base field leaves that bit unused.
+    /// The precompiles reject it for the other curves, such as secp256k1.
+    TopBitFlag,
+}
+
+impl EcIdentityEncoding {
+    /// Whether the `words` of a point, in memory order, encode the point at infinity.
+    #[must_use]
+    pub fn is_identity(self, words: &[u32]) -> bool {
+        match self {
+            Self::Zero => words.iter().all(|word| *word == 0),
+            Self::TopBitFlag => words.last().is_some_and(|word| word >> 31 == 1),
+        }
+    }
+
+    /// Whether no point of a curve whose base field modulus has `modulus_bits` bits is mistaken
+    /// for the identity.
+    ///
+    /// [`Self::TopBitFlag`] is ambiguous when the modulus fills its top word, since the `y`
+    /// coordinate of a point may then have its top bit set.
+    #[must_use]
+    pub const fn is_unambiguous(self, modulus_bits: u64) -> bool {
+        match self {
+            Self::Zero => true,
+            Self::TopBitFlag => modulus_bits % 32 != 0,
+        }
+    }
 }
 
 impl<F: Field> MachineProgram<F> for Program {
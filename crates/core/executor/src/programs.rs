@@ -3,7 +3,9 @@
 #[allow(dead_code)]
 #[allow(missing_docs)]
 pub mod tests {
-    use crate::{Instruction, Opcode, Program};
+    use sp1_stark::SP1CoreOpts;
+
+    use crate::{events::MemoryRecord, Executor, Instruction, Opcode, Program};
 
     pub const CHESS_ELF: &[u8] =
         include_bytes!("../../../../examples/chess/program/elf/riscv32im-succinct-zkvm-elf");
@@ -204,4 +206,22 @@ pub mod tests {
         ];
         Program::new(instructions, 0, 0)
     }
+
+    /// Initializes the memory starting at `ptr` with `words`.
+    pub fn write_words(rt: &mut Executor, ptr: u32, words: &[u32]) {
+        for (i, word) in words.iter().enumerate() {
+            let record = MemoryRecord { value: *word, shard: 0, timestamp: 0 };
+            rt.state.memory.insert(ptr + i as u32 * 4, record);
+        }
+    }
+
+    /// Get an executor for an empty program whose memory holds each `(ptr, words)` pair.
+    #[must_use]
+    pub fn executor_with_words(words: &[(u32, &[u32])]) -> Executor<'static> {
+        let mut rt = Executor::new(Program::new(vec![], 0, 0), SP1CoreOpts::default());
+        for (ptr, words) in words {
+            write_words(&mut rt, *ptr, words);
+        }
+        rt
+    }
 }
@@ -7,12 +7,14 @@ use std::sync::Arc;
 use serde::{Deserialize, Serialize};
 
 use super::{program::Program, Opcode};
+#[cfg(feature = "disk-events")]
+use crate::events::DiskEventBuffer;
 use crate::events::{
     add_sharded_byte_lookup_events, AluEvent, ByteLookupEvent, ByteRecord, CpuEvent,
     EdDecompressEvent, EllipticCurveAddEvent, EllipticCurveDecompressEvent,
-    EllipticCurveDoubleEvent, Fp2AddSubEvent, Fp2MulEvent, FpOpEvent, KeccakPermuteEvent, LookupId,
-    MemoryInitializeFinalizeEvent, MemoryRecordEnum, ShaCompressEvent, ShaExtendEvent,
-    Uint256MulEvent,
+    EllipticCurveDoubleEvent, FailedPrecompileEvent, Fp2AddSubEvent, Fp2MulEvent, FpOpEvent,
+    KeccakPermuteEvent, LookupId, MemoryInitializeFinalizeEvent, MemoryRecordEnum,
+    ShaCompressEvent, ShaExtendEvent, Uint256MulEvent,
 };
 
 /// A record of the execution of a program.
@@ -86,6 +88,8 @@ pub struct ExecutionRecord {
     pub bn254_fp2_addsub_events: Vec<Fp2AddSubEvent>,
     /// A trace of the bn254 fp2 mul events.
     pub bn254_fp2_mul_events: Vec<Fp2MulEvent>,
+    /// A trace of the invocations of disabled precompiles.
+    pub failed_precompile_events: Vec<FailedPrecompileEvent>,
     /// The public values.
     pub public_values: PublicValues<u32, u32>,
     /// The nonce lookup.
@@ -99,6 +103,100 @@ impl ExecutionRecord {
         Self { program, ..Default::default() }
     }
 
+    /// The number of memory accesses made by the elliptic curve precompile events of the record.
+    ///
+    /// Every point read or written by an add, double, or decompress event is counted once per
+    /// word, which is the multiplicity the EC chips contribute to the memory argument.
+    #[must_use]
+    pub fn ec_memory_access_count(&self) -> usize {
+        let add = |events: &[EllipticCurveAddEvent]| {
+            events
+                .iter()
+                .map(|event| event.p_memory_records.len() + event.q_memory_records.len())
+                .sum::<usize>()
+        };
+        let double = |events: &[EllipticCurveDoubleEvent]| {
+            events.iter().map(|event| event.p_memory_records.len()).sum::<usize>()
+        };
+        let decompress = |events: &[EllipticCurveDecompressEvent]| {
+            events
+                .iter()
+                .map(|event| event.x_memory_records.len() + event.y_memory_records.len())
+                .sum::<usize>()
+        };
+        let ed_decompress = self
+            .ed_decompress_events
+            .iter()
+            .map(|event| event.x_memory_records.len() + event.y_memory_records.len())
+            .sum::<usize>();
+
+        add(&self.ed_add_events)
+            + add(&self.secp256k1_add_events)
+            + add(&self.bn254_add_events)
+            + add(&self.bls12381_add_events)
+            + double(&self.secp256k1_double_events)
+            + double(&self.bn254_double_events)
+            + double(&self.bls12381_double_events)
+            + decompress(&self.k256_decompress_events)
+            + decompress(&self.bls12381_decompress_events)
+            + ed_decompress
+    }
+
+    /// Asserts that the precompile events created by the syscall of `cpu_event` carry lookup ids
+    /// consistent with it.
+    ///
+    /// The CPU sends the syscall interaction with `syscall_lookup_id`, so every precompile event
+    /// of the syscall must receive that same id, and the id must have a nonce registered in
+    /// the record for the precompile chips to look up.
+    pub fn assert_syscall_lookup_ids(
+        &self,
+        cpu_event: &CpuEvent,
+        lookup_ids: impl IntoIterator<Item = LookupId>,
+    ) {
+        let syscall_lookup_id = cpu_event.syscall_lookup_id;
+        assert_ne!(syscall_lookup_id, LookupId::default(), "the syscall has no lookup id");
+        assert!(
+            self.nonce_lookup.contains_key(&syscall_lookup_id),
+            "the syscall lookup id has no registered nonce"
+        );
+        for lookup_id in lookup_ids {
+            assert_eq!(
+                lookup_id, syscall_lookup_id,
+                "precompile event lookup id differs from its syscall's"
+            );
+        }
+    }
+
+    /// Moves the events selected by `events` out of the record and appends them to `buffer`, so
+    /// that they no longer take memory.
+    ///
+    /// Events collected afterwards keep their order relative to the spilled ones once they are
+    /// read back with [`Self::restore_events`].
+    #[cfg(feature = "disk-events")]
+    pub fn spill_events<T: Serialize + serde::de::DeserializeOwned>(
+        &mut self,
+        events: fn(&mut Self) -> &mut Vec<T>,
+        buffer: &mut DiskEventBuffer<T>,
+    ) -> bincode::Result<()> {
+        for event in std::mem::take(events(self)) {
+            buffer.push(&event)?;
+        }
+        Ok(())
+    }
+
+    /// Reads the events spilled to `buffer` back in front of the events selected by `events`.
+    #[cfg(feature = "disk-events")]
+    pub fn restore_events<T: Serialize + serde::de::DeserializeOwned>(
+        &mut self,
+        events: fn(&mut Self) -> &mut Vec<T>,
+        buffer: DiskEventBuffer<T>,
+    ) -> bincode::Result<()> {
+        let mut restored = buffer.into_events()?;
+        restored.append(events(self));
+        *events(self) = restored;
+        Ok(())
+    }
+
     /// Add a mul event to the execution record.
     pub fn add_mul_event(&mut self, mul_event: AluEvent) {
         self.mul_events.push(mul_event);
@@ -328,6 +426,7 @@ impl MachineRecord for ExecutionRecord {
             "bls12381_decompress_events".to_string(),
             self.bls12381_decompress_events.len(),
         );
+        stats.insert("failed_precompile_events".to_string(), self.failed_precompile_events.len());
         stats.insert("memory_initialize_events".to_string(), self.memory_initialize_events.len());
         stats.insert("memory_finalize_events".to_string(), self.memory_finalize_events.len());
         if !self.cpu_events.is_empty() {
@@ -371,6 +470,7 @@ impl MachineRecord for ExecutionRecord {
         self.bn254_fp_events.append(&mut other.bn254_fp_events);
         self.bn254_fp2_addsub_events.append(&mut other.bn254_fp2_addsub_events);
         self.bn254_fp2_mul_events.append(&mut other.bn254_fp2_mul_events);
+        self.failed_precompile_events.append(&mut other.failed_precompile_events);
         self.bls12381_decompress_events.append(&mut other.bls12381_decompress_events);
 
         self.bls12381_decompress_events.append(&mut other.bls12381_decompress_events);
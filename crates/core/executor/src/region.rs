@@ -0,0 +1,94 @@
+use std::ops::Range;
+
+use serde::{Deserialize, Serialize};
+use sp1_primitives::consts::{MAX_MEMORY, STACK_TOP, WORD_SIZE};
+
+use crate::Program;
+
+/// The number of addresses reserved for the registers at the bottom of memory.
+const NUM_REGISTER_ADDRESSES: u32 = 32;
+
+/// A region of guest memory.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum MemoryRegion {
+    /// The stack, below [`sp1_primitives::consts::STACK_TOP`].
+    Stack,
+    /// The program image: its instructions and initialized data.
+    Program,
+    /// The heap, from the end of the program image up to [`sp1_primitives::consts::MAX_MEMORY`].
+    Heap,
+}
+
+/// The layout of guest memory into [`MemoryRegion`]s.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MemoryRegionMap {
+    /// The start of the program image, which is also the end of the stack.
+    pub stack_top: u32,
+    /// The end of the program image, which is also the start of the heap.
+    pub heap_start: u32,
+    /// The end of the heap.
+    pub heap_end: u32,
+}
+
+impl MemoryRegionMap {
+    /// The layout of memory for `program`, whose heap starts right after its image.
+    #[must_use]
+    pub fn new(program: &Program) -> Self {
+        let instructions_end = program.pc_base + (WORD_SIZE * program.instructions.len()) as u32;
+        let data_end =
+            program.memory_image.last_key_value().map_or(0, |(addr, _)| addr + WORD_SIZE as u32);
+        let heap_start = instructions_end.max(data_end).max(STACK_TOP);
+        Self { stack_top: STACK_TOP, heap_start, heap_end: MAX_MEMORY }
+    }
+
+    /// The region containing `addr`, or `None` if it is a register or past the heap.
+    #[must_use]
+    pub fn region(&self, addr: u32) -> Option<MemoryRegion> {
+        self.regions()
+            .into_iter()
+            .find(|(_, range)| range.contains(&addr))
+            .map(|(region, _)| region)
+    }
+
+    /// The region containing every address of the `num_words` words starting at `ptr`, or `None`
+    /// if the slice straddles a region boundary or leaves the mapped memory.
+    #[must_use]
+    pub fn slice_region(&self, ptr: u32, num_words: usize) -> Option<MemoryRegion> {
+        let last = ptr.checked_add((WORD_SIZE * num_words.checked_sub(1)?) as u32)?;
+        let region = self.region(ptr)?;
+        (self.region(last) == Some(region)).then_some(region)
+    }
+
+    fn regions(&self) -> [(MemoryRegion, Range<u32>); 3] {
+        [
+            (MemoryRegion::Stack, NUM_REGISTER_ADDRESSES..self.stack_top),
+            (MemoryRegion::Program, self.stack_top..self.heap_start),
+            (MemoryRegion::Heap, self.heap_start..self.heap_end),
+        ]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use sp1_primitives::consts::{MAX_MEMORY, STACK_TOP};
+
+    use super::*;
+
+    #[test]
+    fn test_memory_regions() {
+        let mut program = Program::new(vec![], 0x0020_0800, 0x0020_0800);
+        program.memory_image.insert(0x0030_0000, 0);
+        let regions = MemoryRegionMap::new(&program);
+        assert_eq!(regions.heap_start, 0x0030_0004);
+
+        assert_eq!(regions.region(0), None);
+        assert_eq!(regions.region(0x1000), Some(MemoryRegion::Stack));
+        assert_eq!(regions.region(0x0020_0800), Some(MemoryRegion::Program));
+        assert_eq!(regions.region(0x0030_0004), Some(MemoryRegion::Heap));
+        assert_eq!(regions.region(MAX_MEMORY), None);
+
+        assert_eq!(regions.slice_region(STACK_TOP - 64, 16), Some(MemoryRegion::Stack));
+        assert_eq!(regions.slice_region(STACK_TOP - 32, 16), None);
+        assert_eq!(regions.slice_region(MAX_MEMORY - 4, 2), None);
+    }
+}
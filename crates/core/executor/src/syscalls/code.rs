@@ -192,6 +192,24 @@ impl SyscallCode {
         (self as u32).to_le_bytes()[2].into()
     }
 
+    /// Get whether the system call is a precompile, i.e. not a system operation such as halting,
+    /// writing, committing, or reading hints.
+    #[must_use]
+    pub fn is_precompile(self) -> bool {
+        !matches!(
+            self,
+            SyscallCode::HALT
+                | SyscallCode::WRITE
+                | SyscallCode::ENTER_UNCONSTRAINED
+                | SyscallCode::EXIT_UNCONSTRAINED
+                | SyscallCode::COMMIT
+                | SyscallCode::COMMIT_DEFERRED_PROOFS
+                | SyscallCode::VERIFY_SP1_PROOF
+                | SyscallCode::HINT_LEN
+                | SyscallCode::HINT_READ
+        )
+    }
+
     /// Map a syscall to another one in order to coalesce their counts.
     #[must_use]
     #[allow(clippy::match_same_arms)]
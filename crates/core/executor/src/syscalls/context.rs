@@ -1,9 +1,16 @@
 use crate::{
     events::{LookupId, MemoryReadRecord, MemoryWriteRecord},
     record::ExecutionRecord,
-    Executor, Register,
+    syscalls::{SyscallCode, SyscallError},
+    Executor, MemoryRegion, Register,
 };
 
+/// The number of cycles between the reads of a precompile and its writes.
+///
+/// A precompile may write to an address it has read, and the accesses to an address must be at
+/// strictly increasing timestamps, so the writes are made after the reads.
+pub const PRECOMPILE_WRITE_CLK_OFFSET: u32 = 1;
+
 /// A runtime for syscalls that is protected so that developers cannot arbitrarily modify the
 /// runtime.
 #[allow(dead_code)]
@@ -20,6 +27,10 @@ pub struct SyscallContext<'a, 'b: 'a> {
     pub rt: &'a mut Executor<'b>,
     /// The syscall lookup id.
     pub syscall_lookup_id: LookupId,
+    /// The code of the syscall being executed.
+    pub syscall_code: SyscallCode,
+    /// The error the syscall trapped with, if any.
+    pub error: Option<SyscallError>,
 }
 
 impl<'a, 'b> SyscallContext<'a, 'b> {
@@ -34,9 +45,48 @@ impl<'a, 'b> SyscallContext<'a, 'b> {
             exit_code: 0,
             rt: runtime,
             syscall_lookup_id: LookupId::default(),
+            syscall_code: SyscallCode::HALT,
+            error: None,
+        }
+    }
+
+    /// Traps the guest with `error`, which the executor reports once the syscall returns.
+    pub fn trap(&mut self, error: SyscallError) {
+        self.error = Some(error);
+    }
+
+    /// Returns an [`SyscallError::UnalignedPointer`] error if `ptr` is not word aligned.
+    pub fn check_aligned(&self, ptr: u32) -> Result<(), SyscallError> {
+        if ptr % 4 == 0 {
+            Ok(())
+        } else {
+            Err(SyscallError::UnalignedPointer { ptr, syscall: self.syscall_code })
+        }
+    }
+
+    /// Returns a [`SyscallError::MemoryOutOfBounds`] error if the `num_words` words at `ptr` do not
+    /// all fit below the end of the address space, so that their addresses would wrap around.
+    pub fn check_bounds(&self, ptr: u32, num_words: usize) -> Result<(), SyscallError> {
+        if u64::from(ptr) + 4 * num_words as u64 <= 1 << 32 {
+            Ok(())
+        } else {
+            Err(SyscallError::MemoryOutOfBounds { ptr, syscall: self.syscall_code })
         }
     }
 
+    /// The memory region holding the `num_words` words at `ptr`, or `None` if they are not all in
+    /// the same region.
+    #[must_use]
+    pub fn memory_region(&self, ptr: u32, num_words: usize) -> Option<MemoryRegion> {
+        self.rt.memory_regions.slice_region(ptr, num_words)
+    }
+
+    /// Advances the clock by [`PRECOMPILE_WRITE_CLK_OFFSET`], past the reads of a precompile and
+    /// before its writes.
+    pub fn advance_clk_for_write(&mut self) {
+        self.clk += PRECOMPILE_WRITE_CLK_OFFSET;
+    }
+
     /// Get a mutable reference to the execution record.
     pub fn record_mut(&mut self) -> &mut ExecutionRecord {
         &mut self.rt.record
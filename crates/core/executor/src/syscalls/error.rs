@@ -0,0 +1,91 @@
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+use super::SyscallCode;
+
+/// An error in the arguments that a guest passed to a syscall.
+///
+/// Syscalls report these through [`super::SyscallContext::trap`] instead of panicking, so that the
+/// executor can stop the guest with an [`crate::ExecutionError`].
+#[derive(Error, Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum SyscallError {
+    /// A pointer argument is not aligned to a word.
+    #[error("unaligned pointer {ptr:#x} passed to syscall {syscall:?}")]
+    UnalignedPointer {
+        /// The offending address.
+        ptr: u32,
+        /// The syscall that received the pointer.
+        syscall: SyscallCode,
+    },
+
+    /// The syscall is a precompile that is disabled for the program.
+    #[error("disabled precompile {syscall:?} invoked")]
+    DisabledPrecompile {
+        /// The disabled precompile.
+        syscall: SyscallCode,
+    },
+
+    /// A pointer argument addresses a slice that runs past the end of the address space.
+    #[error("slice at {ptr:#x} passed to syscall {syscall:?} runs past the end of memory")]
+    MemoryOutOfBounds {
+        /// The address of the slice.
+        ptr: u32,
+        /// The syscall that received the pointer.
+        syscall: SyscallCode,
+    },
+
+    /// A memory access of a precompile event is attributed to another shard or channel than the
+    /// event.
+    #[error("syscall {syscall:?} accessed memory outside shard {shard} or channel {channel}")]
+    MisattributedAccess {
+        /// The shard of the event.
+        shard: u32,
+        /// The channel of the event.
+        channel: u8,
+        /// The syscall that created the event.
+        syscall: SyscallCode,
+    },
+
+    /// The program's encoding of the identity could also be the encoding of a point of the curve.
+    #[error("identity encoding of the program is ambiguous for syscall {syscall:?}")]
+    AmbiguousIdentityEncoding {
+        /// The syscall whose curve the encoding does not support.
+        syscall: SyscallCode,
+    },
+
+    /// A point argument is the identity, which has no affine encoding.
+    #[error("point at infinity at {ptr:#x} passed to syscall {syscall:?}")]
+    PointAtInfinity {
+        /// The address of the point.
+        ptr: u32,
+        /// The syscall that received the point.
+        syscall: SyscallCode,
+    },
+
+    /// A compressed point's `x` coordinate is not the coordinate of any point on the curve.
+    #[error("x coordinate at {ptr:#x} passed to syscall {syscall:?} is not on the curve")]
+    NotOnCurve {
+        /// The address of the compressed point.
+        ptr: u32,
+        /// The syscall that received the point.
+        syscall: SyscallCode,
+    },
+
+    /// A decompressed point is on the curve but not in its prime-order subgroup.
+    #[error("point at {ptr:#x} passed to syscall {syscall:?} is not in the prime-order subgroup")]
+    NotInSubgroup {
+        /// The address of the compressed point.
+        ptr: u32,
+        /// The syscall that received the point.
+        syscall: SyscallCode,
+    },
+
+    /// A sign bit argument is neither zero nor one.
+    #[error("invalid sign bit {sign_bit} passed to syscall {syscall:?}")]
+    InvalidSignBit {
+        /// The offending sign bit.
+        sign_bit: u32,
+        /// The syscall that received the sign bit.
+        syscall: SyscallCode,
+    },
+}
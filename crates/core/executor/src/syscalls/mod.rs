@@ -4,6 +4,7 @@ mod code;
 mod commit;
 mod context;
 mod deferred;
+mod error;
 mod halt;
 mod hint;
 mod precompiles;
@@ -20,6 +21,7 @@ use hashbrown::HashMap;
 
 pub use code::*;
 pub use context::*;
+pub use error::*;
 use hint::{HintLenSyscall, HintReadSyscall};
 use precompiles::{
     edwards::{add::EdwardsAddAssignSyscall, decompress::EdwardsDecompressSyscall},
@@ -3,7 +3,7 @@ use std::marker::PhantomData;
 use sp1_curves::{edwards::EdwardsParameters, EllipticCurve};
 
 use crate::{
-    events::create_ec_add_event,
+    events::create_ed_add_event,
     syscalls::{Syscall, SyscallContext},
 };
 
@@ -24,7 +24,13 @@ impl<E: EllipticCurve + EdwardsParameters> Syscall for EdwardsAddAssignSyscall<E
     }
 
     fn execute(&self, rt: &mut SyscallContext, arg1: u32, arg2: u32) -> Option<u32> {
-        let event = create_ec_add_event::<E>(rt, arg1, arg2);
+        let event = match create_ed_add_event::<E>(rt, arg1, arg2) {
+            Ok(event) => event,
+            Err(error) => {
+                rt.trap(error);
+                return None;
+            }
+        };
         rt.record_mut().ed_add_events.push(event);
         None
     }
@@ -20,7 +20,13 @@ impl<E: EllipticCurve> WeierstrassAddAssignSyscall<E> {
 
 impl<E: EllipticCurve> Syscall for WeierstrassAddAssignSyscall<E> {
     fn execute(&self, rt: &mut SyscallContext, arg1: u32, arg2: u32) -> Option<u32> {
-        let event = create_ec_add_event::<E>(rt, arg1, arg2);
+        let event = match create_ec_add_event::<E>(rt, arg1, arg2) {
+            Ok(event) => event,
+            Err(error) => {
+                rt.trap(error);
+                return None;
+            }
+        };
         match E::CURVE_TYPE {
             CurveType::Secp256k1 => rt.record_mut().secp256k1_add_events.push(event),
             CurveType::Bn254 => rt.record_mut().bn254_add_events.push(event),
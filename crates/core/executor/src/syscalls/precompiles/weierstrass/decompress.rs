@@ -3,7 +3,7 @@ use std::marker::PhantomData;
 use sp1_curves::{CurveType, EllipticCurve};
 
 use crate::{
-    events::create_ec_decompress_event,
+    events::create_ec_decompress_event_with_subgroup_check,
     syscalls::{Syscall, SyscallContext},
 };
 
@@ -20,7 +20,20 @@ impl<E: EllipticCurve> WeierstrassDecompressSyscall<E> {
 
 impl<E: EllipticCurve> Syscall for WeierstrassDecompressSyscall<E> {
     fn execute(&self, rt: &mut SyscallContext, arg1: u32, arg2: u32) -> Option<u32> {
-        let event = create_ec_decompress_event::<E>(rt, arg1, arg2);
+        // Only bls12-381 has a cofactor, so the subgroup check is a no-op on the other curves.
+        let check_subgroup = rt.rt.program.ec_subgroup_check;
+        let event = match create_ec_decompress_event_with_subgroup_check::<E>(
+            rt,
+            arg1,
+            arg2,
+            check_subgroup,
+        ) {
+            Ok(event) => event,
+            Err(error) => {
+                rt.trap(error);
+                return None;
+            }
+        };
         match E::CURVE_TYPE {
             CurveType::Secp256k1 => rt.record_mut().k256_decompress_events.push(event),
             CurveType::Bls12381 => rt.record_mut().bls12381_decompress_events.push(event),
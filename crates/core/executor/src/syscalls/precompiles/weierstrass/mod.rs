@@ -1,3 +1,213 @@
 pub mod add;
 pub mod decompress;
 pub mod double;
+
+#[cfg(test)]
+mod tests {
+    use num::{BigUint, Num, One};
+    use sha2::{Digest, Sha256};
+    use sp1_curves::{
+        weierstrass::{bls12_381::Bls12381, secp256k1::Secp256k1},
+        AffinePoint, EllipticCurve,
+    };
+    use sp1_primitives::consts::{bytes_to_words_le, words_to_bytes_le_vec};
+    use sp1_stark::SP1CoreOpts;
+
+    use crate::{
+        events::MemoryRecord,
+        syscalls::{default_syscall_map, SyscallCode, SyscallContext, SyscallError},
+        Executor, Program,
+    };
+
+    /// The order of the secp256k1 group.
+    const SECP256K1_N: &str = "fffffffffffffffffffffffffffffffebaaedce6af48a03bbfd25e8cd0364141";
+
+    const P_PTR: u32 = 0x1000;
+    const Q_PTR: u32 = 0x1100;
+    const X_PTR: u32 = 0x1200;
+    const Y_PTR: u32 = 0x1300;
+
+    /// A test vector for deterministic ECDSA over secp256k1 with SHA-256, as specified by
+    /// RFC 6979.
+    struct Rfc6979Vector {
+        private_key: &'static str,
+        message: &'static str,
+        k: &'static str,
+        signature: &'static str,
+    }
+
+    const RFC6979_VECTORS: [Rfc6979Vector; 3] = [
+        Rfc6979Vector {
+            private_key: "1",
+            message: "Satoshi Nakamoto",
+            k: "8f8a276c19f4149656b280621e358cce24f5f52542772691ee69063b74f15d15",
+            signature: "934b1ea10a4b3c1757e2b0c017d0b6143ce3c9a7e6a4a49860d7a6ab210ee3d8\
+                        2442ce9d2b916064108014783e923ec36b49743e2ffa1c4496f01a512aafd9e5",
+        },
+        Rfc6979Vector {
+            private_key: "1",
+            message: "All those moments will be lost in time, like tears in rain. Time to die...",
+            k: "38aa22d72376b4dbc472e06c3ba403ee0a394da63fc58d88686c611aba98d6b3",
+            signature: "8600dbd41e348fe5c9465ab92d23e3db8b98b873beecd930736488696438cb6b\
+                        547fe64427496db33bf66019dacbf0039c04199abb0122918601db38a72cfc21",
+        },
+        Rfc6979Vector {
+            private_key: "fffffffffffffffffffffffffffffffebaaedce6af48a03bbfd25e8cd0364140",
+            message: "Satoshi Nakamoto",
+            k: "33a19b60e25fb6f4435af53a3d42d493644827367e6453928554f43e49aa6f90",
+            signature: "fd567d121db66e382991534ada77a6bd3106f0a1098c231e47993447cd6af2d0\
+                        6b39cd0eb1bc8603e159ef5c20a5c8ad685a45b06ce9bebed3f153d10d93bed5",
+        },
+    ];
+
+    /// Signs messages with the precompiles in the same way a guest would: the nonce point is
+    /// computed with the add and double syscalls, and all arithmetic modulo the group order,
+    /// including the nonce inversion, goes through the uint256 multiplication syscall.
+    struct PrecompileSigner<'a, 'b> {
+        ctx: SyscallContext<'a, 'b>,
+    }
+
+    impl PrecompileSigner<'_, '_> {
+        fn syscall(&mut self, code: SyscallCode, arg1: u32, arg2: u32) {
+            let syscall = default_syscall_map()[&code].clone();
+            syscall.execute(&mut self.ctx, arg1, arg2);
+            self.ctx.clk += 1;
+        }
+
+        fn write(&mut self, ptr: u32, words: &[u32]) {
+            self.ctx.mw_slice(ptr, words);
+            self.ctx.clk += 1;
+        }
+
+        /// Computes `[scalar]G` by double-and-add over the secp256k1 syscalls.
+        fn mul_generator(&mut self, scalar: &BigUint) -> AffinePoint<Secp256k1> {
+            let generator = Secp256k1::ec_generator().to_words_le();
+            self.write(P_PTR, &generator);
+            self.write(Q_PTR, &generator);
+            for bit in (0..scalar.bits() - 1).rev() {
+                self.syscall(SyscallCode::SECP256K1_DOUBLE, P_PTR, 0);
+                if scalar.bit(bit) {
+                    self.syscall(SyscallCode::SECP256K1_ADD, P_PTR, Q_PTR);
+                }
+            }
+            AffinePoint::from_words_le(&self.ctx.slice_unsafe(P_PTR, generator.len()))
+        }
+
+        /// Computes `x * y mod modulus` with the uint256 multiplication syscall.
+        fn mul_mod(&mut self, x: &BigUint, y: &BigUint, modulus: &BigUint) -> BigUint {
+            self.write(X_PTR, &to_words(x));
+            self.write(Y_PTR, &to_words(y));
+            self.write(Y_PTR + 32, &to_words(modulus));
+            self.syscall(SyscallCode::UINT256_MUL, X_PTR, Y_PTR);
+            BigUint::from_bytes_le(&words_to_bytes_le_vec(&self.ctx.slice_unsafe(X_PTR, 8)))
+        }
+
+        /// Computes `x^-1 mod modulus` for a prime modulus via Fermat's little theorem.
+        fn inv_mod(&mut self, x: &BigUint, modulus: &BigUint) -> BigUint {
+            let exponent = modulus - 2u32;
+            let mut result = BigUint::one();
+            for bit in (0..exponent.bits()).rev() {
+                result = self.mul_mod(&result, &result, modulus);
+                if exponent.bit(bit) {
+                    result = self.mul_mod(&result, x, modulus);
+                }
+            }
+            result
+        }
+
+        /// Produces a low-S ECDSA signature `r || s` over the SHA-256 digest of `message`.
+        fn sign(&mut self, private_key: &BigUint, nonce: &BigUint, message: &[u8]) -> Vec<u8> {
+            let order = BigUint::from_str_radix(SECP256K1_N, 16).unwrap();
+            let digest = BigUint::from_bytes_be(&Sha256::digest(message)) % &order;
+
+            let r = self.mul_generator(nonce).x % &order;
+            let nonce_inv = self.inv_mod(nonce, &order);
+            let rd = self.mul_mod(&r, private_key, &order);
+            let mut s = self.mul_mod(&nonce_inv, &((digest + rd) % &order), &order);
+            if s > &order >> 1 {
+                s = &order - s;
+            }
+
+            [r, s].iter().flat_map(to_bytes_be).collect()
+        }
+    }
+
+    fn to_words(x: &BigUint) -> [u32; 8] {
+        let mut bytes = x.to_bytes_le();
+        bytes.resize(32, 0);
+        bytes_to_words_le::<8>(&bytes)
+    }
+
+    fn to_bytes_be(x: &BigUint) -> Vec<u8> {
+        let bytes = x.to_bytes_be();
+        let mut padded = vec![0u8; 32 - bytes.len()];
+        padded.extend(bytes);
+        padded
+    }
+
+    #[test]
+    fn test_secp256k1_rfc6979_signing() {
+        for vector in RFC6979_VECTORS {
+            let mut rt = Executor::new(Program::new(vec![], 0, 0), SP1CoreOpts::default());
+            for ptr in (P_PTR..Y_PTR + 64).step_by(4) {
+                rt.state.memory.insert(ptr, MemoryRecord { value: 0, shard: 0, timestamp: 0 });
+            }
+            let mut signer = PrecompileSigner { ctx: SyscallContext::new(&mut rt) };
+
+            let private_key = BigUint::from_str_radix(vector.private_key, 16).unwrap();
+            let k = BigUint::from_str_radix(vector.k, 16).unwrap();
+            let signature = signer.sign(&private_key, &k, vector.message.as_bytes());
+            assert_eq!(hex::encode(signature), vector.signature);
+
+            let record = signer.ctx.record_mut();
+            assert_eq!(record.secp256k1_double_events.len() as u64, k.bits() - 1);
+            assert_eq!(record.secp256k1_add_events.len() as u64, k.count_ones() - 1);
+            assert!(!record.uint256_mul_events.is_empty());
+        }
+    }
+
+    #[test]
+    fn test_subgroup_check_is_off_by_default() {
+        assert!(!Program::new(vec![], 0, 0).ec_subgroup_check);
+    }
+
+    #[test]
+    fn test_bls12381_decompress_checks_subgroup() {
+        // x = 4 is the coordinate of a point on the curve outside of the prime-order subgroup.
+        for (x, in_subgroup, ec_subgroup_check) in [
+            (Bls12381::ec_generator().x, true, true),
+            (BigUint::from(4u32), false, true),
+            (BigUint::from(4u32), false, false),
+        ] {
+            let program = Program::new(vec![], 0, 0).with_ec_subgroup_check(ec_subgroup_check);
+            let mut rt = Executor::new(program, SP1CoreOpts::default());
+            let mut ctx = SyscallContext::new(&mut rt);
+            let mut x_bytes = x.to_bytes_le();
+            x_bytes.resize(48, 0);
+            let x_words = x_bytes
+                .chunks(4)
+                .map(|chunk| u32::from_le_bytes(chunk.try_into().unwrap()))
+                .collect::<Vec<_>>();
+            ctx.mw_slice(P_PTR + 48, &x_words);
+            ctx.clk += 1;
+
+            ctx.syscall_code = SyscallCode::BLS12381_DECOMPRESS;
+            let syscall = default_syscall_map()[&SyscallCode::BLS12381_DECOMPRESS].clone();
+            syscall.execute(&mut ctx, P_PTR, 0);
+            // Without the check, a point outside of the subgroup is decompressed as any other.
+            if in_subgroup || !ec_subgroup_check {
+                assert_eq!(ctx.error, None);
+                assert_eq!(ctx.record_mut().bls12381_decompress_events.len(), 1);
+            } else {
+                assert_eq!(
+                    ctx.error,
+                    Some(SyscallError::NotInSubgroup {
+                        ptr: P_PTR,
+                        syscall: SyscallCode::BLS12381_DECOMPRESS
+                    })
+                );
+                assert!(ctx.record_mut().bls12381_decompress_events.is_empty());
+            }
+        }
+    }
+}
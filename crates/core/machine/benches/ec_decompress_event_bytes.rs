@@ -0,0 +1,69 @@
+use criterion::*;
+
+use sp1_core_executor::{
+    events::{create_ec_decompress_event, EllipticCurveDecompressEvent, MemoryRecord},
+    syscalls::SyscallContext,
+    Executor, Program,
+};
+use sp1_curves::{weierstrass::secp256k1::Secp256k1, EllipticCurve};
+use sp1_stark::SP1CoreOpts;
+
+const NUM_EVENTS: usize = 1 << 12;
+
+/// Decompresses the secp256k1 generator through the precompile.
+fn decompress_event() -> EllipticCurveDecompressEvent {
+    let ptr = 0x1000;
+    let mut x_bytes = Secp256k1::ec_generator().x.to_bytes_le();
+    x_bytes.resize(32, 0);
+
+    let mut rt = Executor::new(Program::new(vec![], 0, 0), SP1CoreOpts::default());
+    for (i, chunk) in x_bytes.chunks(4).enumerate() {
+        let value = u32::from_le_bytes(chunk.try_into().unwrap());
+        let record = MemoryRecord { value, shard: 0, timestamp: 0 };
+        rt.state.memory.insert(ptr + 32 + i as u32 * 4, record);
+    }
+    let mut ctx = SyscallContext::new(&mut rt);
+    create_ec_decompress_event::<Secp256k1>(&mut ctx, ptr, 0).unwrap()
+}
+
+fn decompress_event_encoding(c: &mut Criterion) {
+    let mut group = c.benchmark_group("secp256k1_decompress_event_encoding");
+    let events = vec![decompress_event(); NUM_EVENTS];
+
+    group.bench_function("bincode_serialize", |b| {
+        b.iter(|| bincode::serialize(black_box(&events)).unwrap())
+    });
+    group.bench_function("to_bytes", |b| {
+        b.iter(|| {
+            let mut out = Vec::new();
+            for event in black_box(&events) {
+                event.write_bytes(&mut out);
+            }
+            out
+        })
+    });
+
+    let bincode_bytes = bincode::serialize(&events).unwrap();
+    let mut raw_bytes = Vec::new();
+    for event in &events {
+        event.write_bytes(&mut raw_bytes);
+    }
+    group.bench_function("bincode_deserialize", |b| {
+        b.iter(|| {
+            bincode::deserialize::<Vec<EllipticCurveDecompressEvent>>(black_box(&bincode_bytes))
+                .unwrap()
+        })
+    });
+    group.bench_function("from_bytes", |b| {
+        b.iter(|| {
+            let mut reader = black_box(raw_bytes.as_slice());
+            (0..NUM_EVENTS)
+                .map(|_| EllipticCurveDecompressEvent::read_bytes(&mut reader).unwrap())
+                .collect::<Vec<_>>()
+        })
+    });
+    group.finish();
+}
+
+criterion_group!(benches, decompress_event_encoding);
+criterion_main!(benches);
@@ -185,7 +185,7 @@ mod tests {
     use p3_baby_bear::BabyBear;
 
     use p3_matrix::dense::RowMajorMatrix;
-    use sp1_core_executor::{ExecutionRecord, Instruction, Opcode, Program};
+    use sp1_core_executor::{EcIdentityEncoding, ExecutionRecord, Instruction, Opcode, Program};
     use sp1_stark::air::MachineAir;
 
     use crate::program::ProgramChip;
@@ -207,6 +207,9 @@ mod tests {
                 pc_start: 0,
                 pc_base: 0,
                 memory_image: BTreeMap::new(),
+                enabled_precompiles: None,
+                ec_identity_encoding: EcIdentityEncoding::Zero,
+                ec_subgroup_check: false,
             }),
             ..Default::default()
         };
@@ -331,15 +331,20 @@ pub mod tests {
         utils::{prove, run_test, setup_logger},
     };
 
+    use p3_baby_bear::BabyBear;
+    use p3_challenger::CanObserve;
+    use p3_field::AbstractField;
+    use sp1_core_executor::Executor;
     use sp1_core_executor::{
         programs::tests::{
             fibonacci_program, simple_memory_program, simple_program, ssz_withdrawals_program,
         },
-        Instruction, Opcode, Program,
+        ExecutionRecord, Instruction, Opcode, Program,
     };
     use sp1_stark::{
-        baby_bear_poseidon2::BabyBearPoseidon2, CpuProver, SP1CoreOpts, StarkProvingKey,
-        StarkVerifyingKey,
+        baby_bear_poseidon2::BabyBearPoseidon2, vk_fingerprint, ChipReproduction, CpuProver,
+        MachineProver, MachineRecord, SP1CoreOpts, StarkGenericConfig, StarkProvingKey,
+        StarkVerifyingKey, VerificationCheck,
     };
 
     #[test]
@@ -513,6 +518,115 @@ pub mod tests {
         run_test::<CpuProver<_, _>>(program).unwrap();
     }
 
+    #[test]
+    fn test_verify_with_seed() {
+        setup_logger();
+        let program = simple_program();
+        let opts = SP1CoreOpts::default();
+        let mut runtime = Executor::new(program.clone(), opts);
+        runtime.run().unwrap();
+
+        // Move the memory events into their own shards, as the core prover does.
+        let mut records = runtime.records;
+        let mut deferred = ExecutionRecord::default();
+        for record in records.iter_mut() {
+            deferred.append(&mut record.defer());
+        }
+        records.extend(deferred.split(true, opts.split_opts));
+        records.iter_mut().enumerate().for_each(|(i, shard)| {
+            shard.public_values.shard = (i + 1) as u32;
+        });
+
+        let prover = CpuProver::new(RiscvAir::machine(BabyBearPoseidon2::new()));
+        let (pk, vk) = prover.setup(&program);
+        let seed = [BabyBear::from_canonical_u32(0xBEAC0); 8];
+        let mut challenger = prover.config().challenger();
+        challenger.observe_slice(&seed);
+        let proof = prover.prove(&pk, records, &mut challenger, opts).unwrap();
+
+        let verify = |seed: Option<&[BabyBear]>| {
+            let mut challenger = prover.config().challenger();
+            prover.machine().verify_with_seed(&vk, &proof, &mut challenger, seed)
+        };
+        assert!(verify(Some(&seed)).is_ok());
+        assert!(verify(Some(&[BabyBear::from_canonical_u32(0xBEAC1); 8])).is_err());
+        assert!(verify(None).is_err());
+    }
+
+    #[test]
+    fn test_chip_reproduction() {
+        setup_logger();
+        let program = simple_program();
+        let mut runtime = Executor::new(program.clone(), SP1CoreOpts::default());
+        runtime.run().unwrap();
+        runtime.records.iter_mut().enumerate().for_each(|(i, shard)| {
+            shard.public_values.shard = (i + 1) as u32;
+        });
+
+        let prover = CpuProver::new(RiscvAir::machine(BabyBearPoseidon2::new()));
+        let (pk, vk) = prover.setup(&program);
+        let mut challenger = prover.config().challenger();
+        let mut proof =
+            prover.prove(&pk, runtime.records, &mut challenger, SP1CoreOpts::default()).unwrap();
+
+        // Corrupt one opened value of the CPU chip.
+        let shard_proof = &mut proof.shard_proofs[0];
+        let cpu_index = shard_proof.chip_ordering["CPU"];
+        shard_proof.opened_values.chips[cpu_index].main.local[0] += BabyBear::one();
+
+        let machine = prover.machine();
+        let reproduction = machine.extract_chip_reproduction(&vk, &proof, 0, "CPU").unwrap();
+        let reproduction: ChipReproduction<BabyBearPoseidon2> =
+            bincode::deserialize(&bincode::serialize(&reproduction).unwrap()).unwrap();
+        assert!(machine.reproduce_chip(&reproduction).is_err());
+
+        let reproduction = machine.extract_chip_reproduction(&vk, &proof, 0, "Program").unwrap();
+        assert!(reproduction.chip_information.is_some());
+        assert!(machine.reproduce_chip(&reproduction).is_ok());
+
+        assert!(machine.extract_chip_reproduction(&vk, &proof, 1, "CPU").is_none());
+    }
+
+    #[test]
+    fn test_verification_report() {
+        setup_logger();
+        let program = simple_program();
+        let mut runtime = Executor::new(program.clone(), SP1CoreOpts::default());
+        runtime.run().unwrap();
+        runtime.records.iter_mut().enumerate().for_each(|(i, shard)| {
+            shard.public_values.shard = (i + 1) as u32;
+        });
+
+        let prover = CpuProver::new(RiscvAir::machine(BabyBearPoseidon2::new()));
+        let (pk, vk) = prover.setup(&program);
+        let mut challenger = prover.config().challenger();
+        let mut proof =
+            prover.prove(&pk, runtime.records, &mut challenger, SP1CoreOpts::default()).unwrap();
+
+        let machine = prover.machine();
+        let (result, report) = machine.verify_shard_with_report(&vk, &proof, 0).unwrap();
+        assert!(result.is_ok());
+        assert!(report.is_success());
+        assert_eq!(report.num_chips, proof.shard_proofs[0].opened_values.chips.len());
+        assert!(report.challenges_digest.is_some());
+        assert!(report.passed_checks.contains(&VerificationCheck::Constraints("CPU".to_string())));
+
+        // Corrupting an opened value breaks the opening proof before any constraint is checked.
+        let shard_proof = &mut proof.shard_proofs[0];
+        let cpu_index = shard_proof.chip_ordering["CPU"];
+        shard_proof.opened_values.chips[cpu_index].main.local[0] += BabyBear::one();
+
+        let (result, report) = machine.verify_shard_with_report(&vk, &proof, 0).unwrap();
+        assert!(result.is_err());
+        assert_eq!(report.passed_checks, vec![VerificationCheck::ChipCount]);
+        let (failed_check, _) = report.failed_check.as_ref().unwrap();
+        assert_eq!(failed_check, &VerificationCheck::OpeningProof);
+        assert!(report.to_string().contains("failed: opening proof"));
+
+        let num_shards = proof.shard_proofs.len();
+        assert!(machine.verify_shard_with_report(&vk, &proof, num_shards).is_none());
+    }
+
     #[test]
     fn test_key_serde() {
         let program = ssz_withdrawals_program();
@@ -543,5 +657,53 @@ pub mod tests {
             assert_eq!(a.2.width, b.2.width);
         }
         assert_eq!(vk.chip_ordering, deserialized_vk.chip_ordering);
+        assert_eq!(vk.chip_quotient_degrees, deserialized_vk.chip_quotient_degrees);
+    }
+
+    #[test]
+    fn test_vk_chip_quotient_degrees() {
+        setup_logger();
+        let machine = RiscvAir::machine(BabyBearPoseidon2::new());
+        let (_, vk) = machine.setup(&simple_program());
+        let degrees = vk.chip_quotient_degrees();
+        let expected = machine
+            .chips()
+            .iter()
+            .map(|chip| 1 << chip.log_quotient_degree())
+            .collect::<Vec<usize>>();
+        assert_eq!(degrees, expected);
+        assert!(degrees.iter().all(|degree| degree.is_power_of_two()));
+
+        // Every chip of a shard opens one quotient chunk per unit of its degree bound.
+        let (proof, _, _) = prove::<_, CpuProver<_, _>>(
+            simple_program(),
+            &SP1Stdin::new(),
+            BabyBearPoseidon2::new(),
+            SP1CoreOpts::default(),
+        )
+        .unwrap();
+        for shard_proof in &proof.shard_proofs {
+            for (degree, index) in degrees.iter().zip(machine.chips_sorted_indices(shard_proof)) {
+                if let Some(index) = index {
+                    assert_eq!(shard_proof.opened_values.chips[index].quotient.len(), *degree);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_vk_fingerprint() {
+        let machine = RiscvAir::machine(BabyBearPoseidon2::new());
+        let (_, fibonacci_vk) = machine.setup(&fibonacci_program());
+        let (_, fibonacci_vk_again) = machine.setup(&fibonacci_program());
+        let (_, simple_vk) = machine.setup(&simple_program());
+
+        let fingerprint = vk_fingerprint(&fibonacci_vk);
+        assert_eq!(fingerprint, vk_fingerprint(&fibonacci_vk_again));
+        assert_ne!(fingerprint, vk_fingerprint(&simple_vk));
+
+        let mut shifted_vk = fibonacci_vk_again;
+        shifted_vk.pc_start += BabyBear::one();
+        assert_ne!(fingerprint, vk_fingerprint(&shifted_vk));
     }
 }
@@ -1,3 +1,10 @@
+use hashbrown::HashMap;
+use p3_baby_bear::BabyBear;
+use sp1_curves::{weierstrass::WeierstrassParameters, CurveType, EllipticCurve};
+use sp1_stark::air::MachineAir;
+
+use crate::utils::next_power_of_two;
+
 mod weierstrass_add;
 mod weierstrass_decompress;
 mod weierstrass_double;
@@ -5,3 +12,30 @@ mod weierstrass_double;
 pub use weierstrass_add::*;
 pub use weierstrass_decompress::*;
 pub use weierstrass_double::*;
+
+/// The trace heights of the Weierstrass chips of `E` in a shard with the given numbers of add,
+/// double, and decompress events, keyed by chip name.
+///
+/// Each event takes one row, and every trace is padded to the next power of two of at least 16.
+/// Curves without a decompress chip have no decompress height.
+pub fn ec_chip_heights<E: EllipticCurve + WeierstrassParameters>(
+    add: usize,
+    double: usize,
+    decompress: usize,
+) -> HashMap<String, usize> {
+    let mut heights = HashMap::from([
+        (
+            MachineAir::<BabyBear>::name(&WeierstrassAddAssignChip::<E>::new()),
+            next_power_of_two(add, None),
+        ),
+        (
+            MachineAir::<BabyBear>::name(&WeierstrassDoubleAssignChip::<E>::new()),
+            next_power_of_two(double, None),
+        ),
+    ]);
+    if matches!(E::CURVE_TYPE, CurveType::Secp256k1 | CurveType::Bls12381) {
+        let chip = WeierstrassDecompressChip::<E>::new(SignChoiceRule::LeastSignificantBit);
+        heights.insert(MachineAir::<BabyBear>::name(&chip), next_power_of_two(decompress, None));
+    }
+    heights
+}
@@ -21,7 +21,7 @@ use sp1_curves::{
     AffinePoint, CurveType, EllipticCurve,
 };
 use sp1_derive::AlignedBorrow;
-use sp1_stark::air::{MachineAir, SP1AirBuilder};
+use sp1_stark::air::{MachineAir, Polynomial, SP1AirBuilder};
 use typenum::Unsigned;
 
 use crate::{
@@ -36,6 +36,10 @@ pub const fn num_weierstrass_add_cols<P: FieldParameters + NumWords>() -> usize
 
 /// A set of columns to compute `WeierstrassAdd` that add two points on a Weierstrass curve.
 ///
+/// The chord slope of the addition formula is undefined when both points are equal, in which case
+/// `is_double` is set and the slope of the tangent is used instead, as in
+/// [`super::WeierstrassDoubleAssignChip`].
+///
 /// Right now the number of limbs is assumed to be a constant, although this could be macro-ed or
 /// made generic in the future.
 #[derive(Debug, Clone, AlignedBorrow)]
@@ -50,8 +54,13 @@ pub struct WeierstrassAddAssignCols<T, P: FieldParameters + NumWords> {
     pub q_ptr: T,
     pub p_access: GenericArray<MemoryWriteCols<T>, P::WordsCurvePoint>,
     pub q_access: GenericArray<MemoryReadCols<T>, P::WordsCurvePoint>,
+    pub is_double: T,
     pub(crate) slope_denominator: FieldOpCols<T, P>,
     pub(crate) slope_numerator: FieldOpCols<T, P>,
+    pub(crate) p_x_squared: FieldOpCols<T, P>,
+    pub(crate) p_x_squared_times_3: FieldOpCols<T, P>,
+    pub(crate) tangent_numerator: FieldOpCols<T, P>,
+    pub(crate) tangent_denominator: FieldOpCols<T, P>,
     pub(crate) slope: FieldOpCols<T, P>,
     pub(crate) slope_squared: FieldOpCols<T, P>,
     pub(crate) p_x_plus_q_x: FieldOpCols<T, P>,
@@ -66,7 +75,7 @@ pub struct WeierstrassAddAssignChip<E> {
     _marker: PhantomData<E>,
 }
 
-impl<E: EllipticCurve> WeierstrassAddAssignChip<E> {
+impl<E: EllipticCurve + WeierstrassParameters> WeierstrassAddAssignChip<E> {
     pub const fn new() -> Self {
         Self { _marker: PhantomData }
     }
@@ -81,12 +90,46 @@ impl<E: EllipticCurve> WeierstrassAddAssignChip<E> {
         p_y: BigUint,
         q_x: BigUint,
         q_y: BigUint,
+        is_double: bool,
     ) {
         // This populates necessary field operations to calculate the addition of two points on a
         // Weierstrass curve.
 
-        // slope = (q.y - p.y) / (q.x - p.x).
+        // slope = (q.y - p.y) / (q.x - p.x), or (a + 3 * p.x^2) / (2 * p.y) when doubling.
         let slope = {
+            let p_x_squared = cols.p_x_squared.populate(
+                blu_events,
+                shard,
+                channel,
+                &p_x,
+                &p_x,
+                FieldOperation::Mul,
+            );
+            let p_x_squared_times_3 = cols.p_x_squared_times_3.populate(
+                blu_events,
+                shard,
+                channel,
+                &p_x_squared,
+                &BigUint::from(3u32),
+                FieldOperation::Mul,
+            );
+            let tangent_numerator = cols.tangent_numerator.populate(
+                blu_events,
+                shard,
+                channel,
+                &E::a_int(),
+                &p_x_squared_times_3,
+                FieldOperation::Add,
+            );
+            let tangent_denominator = cols.tangent_denominator.populate(
+                blu_events,
+                shard,
+                channel,
+                &BigUint::from(2u32),
+                &p_y,
+                FieldOperation::Mul,
+            );
+
             let slope_numerator = cols.slope_numerator.populate(
                 blu_events,
                 shard,
@@ -105,12 +148,17 @@ impl<E: EllipticCurve> WeierstrassAddAssignChip<E> {
                 FieldOperation::Sub,
             );
 
+            let (numerator, denominator) = if is_double {
+                (tangent_numerator, tangent_denominator)
+            } else {
+                (slope_numerator, slope_denominator)
+            };
             cols.slope.populate(
                 blu_events,
                 shard,
                 channel,
-                &slope_numerator,
-                &slope_denominator,
+                &numerator,
+                &denominator,
                 FieldOperation::Div,
             )
         };
@@ -225,6 +273,9 @@ impl<F: PrimeField32, E: EllipticCurve + WeierstrassParameters> MachineAir<F>
             cols.clk = F::from_canonical_u32(event.clk);
             cols.p_ptr = F::from_canonical_u32(event.p_ptr);
             cols.q_ptr = F::from_canonical_u32(event.q_ptr);
+            let is_double = p_x == q_x && p_y == q_y;
+            debug_assert!(is_double || !event.operands_alias, "aliased operands were not doubled");
+            cols.is_double = F::from_bool(is_double);
 
             Self::populate_field_ops(
                 &mut new_byte_lookup_events,
@@ -235,6 +286,7 @@ impl<F: PrimeField32, E: EllipticCurve + WeierstrassParameters> MachineAir<F>
                 p_y,
                 q_x,
                 q_y,
+                is_double,
             );
 
             // Populate the memory access columns.
@@ -271,6 +323,7 @@ impl<F: PrimeField32, E: EllipticCurve + WeierstrassParameters> MachineAir<F>
                 zero.clone(),
                 zero.clone(),
                 zero,
+                false,
             );
             row
         });
@@ -309,7 +362,7 @@ impl<F, E: EllipticCurve> BaseAir<F> for WeierstrassAddAssignChip<E> {
     }
 }
 
-impl<AB, E: EllipticCurve> Air<AB> for WeierstrassAddAssignChip<E>
+impl<AB, E: EllipticCurve + WeierstrassParameters> Air<AB> for WeierstrassAddAssignChip<E>
 where
     AB: SP1AirBuilder,
     Limbs<AB::Var, <E::BaseField as NumLimbs>::Limbs>: Copy,
@@ -333,8 +386,56 @@ where
         let q_x = limbs_from_prev_access(&local.q_access[0..num_words_field_element]);
         let q_y = limbs_from_prev_access(&local.q_access[num_words_field_element..]);
 
-        // slope = (q.y - p.y) / (q.x - p.x).
+        // Doubling is only allowed on real rows whose points are equal.
+        builder.assert_bool(local.is_double);
+        builder.when(local.is_double).assert_one(local.is_real);
+        for i in 0..E::BaseField::NB_LIMBS {
+            builder.when(local.is_double).assert_eq(p_x[i], q_x[i]);
+            builder.when(local.is_double).assert_eq(p_y[i], q_y[i]);
+        }
+
+        // slope = (q.y - p.y) / (q.x - p.x), or (a + 3 * p.x^2) / (2 * p.y) when doubling.
         let slope = {
+            local.p_x_squared.eval(
+                builder,
+                &p_x,
+                &p_x,
+                FieldOperation::Mul,
+                local.shard,
+                local.channel,
+                local.is_real,
+            );
+
+            local.p_x_squared_times_3.eval(
+                builder,
+                &local.p_x_squared.result,
+                &E::BaseField::to_limbs_field::<AB::Expr, _>(&BigUint::from(3u32)),
+                FieldOperation::Mul,
+                local.shard,
+                local.channel,
+                local.is_real,
+            );
+
+            local.tangent_numerator.eval(
+                builder,
+                &E::BaseField::to_limbs_field::<AB::Expr, _>(&E::a_int()),
+                &local.p_x_squared_times_3.result,
+                FieldOperation::Add,
+                local.shard,
+                local.channel,
+                local.is_real,
+            );
+
+            local.tangent_denominator.eval(
+                builder,
+                &E::BaseField::to_limbs_field::<AB::Expr, _>(&BigUint::from(2u32)),
+                &p_y,
+                FieldOperation::Mul,
+                local.shard,
+                local.channel,
+                local.is_real,
+            );
+
             local.slope_numerator.eval(
                 builder,
                 &q_y,
@@ -355,10 +456,16 @@ where
                 local.is_real,
             );
 
+            let is_double: AB::Expr = local.is_double.into();
+            let is_chord = AB::Expr::one() - is_double.clone();
+            let numerator = Polynomial::from(local.tangent_numerator.result) * is_double.clone()
+                + Polynomial::from(local.slope_numerator.result) * is_chord.clone();
+            let denominator = Polynomial::from(local.tangent_denominator.result) * is_double
+                + Polynomial::from(local.slope_denominator.result) * is_chord;
             local.slope.eval(
                 builder,
-                &local.slope_numerator.result,
-                &local.slope_denominator.result,
+                &numerator,
+                &denominator,
                 FieldOperation::Div,
                 local.shard,
                 local.channel,
@@ -494,17 +601,65 @@ where
 #[cfg(test)]
 mod tests {
 
-    use sp1_core_executor::Program;
-    use sp1_stark::CpuProver;
+    use p3_baby_bear::BabyBear;
+    use p3_matrix::dense::RowMajorMatrix;
+    use sp1_core_executor::{
+        events::{create_ec_add_event, EllipticCurveAddEvent, MemoryRecord},
+        syscalls::SyscallContext,
+        ExecutionRecord, Executor, Program,
+    };
+    use sp1_curves::{weierstrass::secp256k1::Secp256k1, AffinePoint, EllipticCurve};
+    use sp1_stark::{
+        air::MachineAir, baby_bear_poseidon2::BabyBearPoseidon2, CpuProver, SP1CoreOpts,
+        StarkGenericConfig,
+    };
 
+    use super::WeierstrassAddAssignChip;
     use crate::utils::{
         run_test, setup_logger,
         tests::{
             BLS12381_ADD_ELF, BLS12381_DOUBLE_ELF, BLS12381_MUL_ELF, BN254_ADD_ELF, BN254_MUL_ELF,
             SECP256K1_ADD_ELF, SECP256K1_MUL_ELF,
         },
+        uni_stark_prove, uni_stark_verify,
     };
 
+    /// Adds `q` to `p` through the precompile.
+    fn add_event(p: &AffinePoint<Secp256k1>, q: &AffinePoint<Secp256k1>) -> EllipticCurveAddEvent {
+        let (p_ptr, q_ptr) = (0x1000, 0x1100);
+        let mut rt = Executor::new(Program::new(vec![], 0, 0), SP1CoreOpts::default());
+        for (ptr, point) in [(p_ptr, p), (q_ptr, q)] {
+            for (i, word) in point.to_words_le().into_iter().enumerate() {
+                let record = MemoryRecord { value: word, shard: 0, timestamp: 0 };
+                rt.state.memory.insert(ptr + i as u32 * 4, record);
+            }
+        }
+        let mut ctx = SyscallContext::new(&mut rt);
+        create_ec_add_event::<Secp256k1>(&mut ctx, p_ptr, q_ptr).unwrap()
+    }
+
+    #[test]
+    fn test_secp256k1_add_equal_points_doubles() {
+        let generator = Secp256k1::ec_generator();
+        let doubled = Secp256k1::ec_double(&generator);
+        let double_event = add_event(&generator, &generator);
+        assert_eq!(double_event.result_point::<Secp256k1>(), doubled);
+
+        let shard = ExecutionRecord {
+            secp256k1_add_events: vec![double_event, add_event(&doubled, &generator)],
+            ..Default::default()
+        };
+        let chip = WeierstrassAddAssignChip::<Secp256k1>::new();
+        let trace: RowMajorMatrix<BabyBear> =
+            chip.generate_trace(&shard, &mut ExecutionRecord::default());
+
+        let config = BabyBearPoseidon2::new();
+        let mut challenger = config.challenger();
+        let proof = uni_stark_prove::<BabyBearPoseidon2, _>(&config, &chip, &mut challenger, trace);
+        let mut challenger = config.challenger();
+        uni_stark_verify(&config, &chip, &mut challenger, &proof).unwrap();
+    }
+
     #[test]
     fn test_secp256k1_add_simple() {
         setup_logger();
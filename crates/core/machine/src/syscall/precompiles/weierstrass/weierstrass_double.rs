@@ -520,12 +520,23 @@ where
 #[cfg(test)]
 pub mod tests {
 
-    use sp1_core_executor::Program;
-    use sp1_stark::CpuProver;
-
-    use crate::utils::{
-        run_test, setup_logger,
-        tests::{BLS12381_DOUBLE_ELF, BN254_DOUBLE_ELF, SECP256K1_DOUBLE_ELF},
+    use p3_baby_bear::BabyBear;
+    use p3_matrix::{dense::RowMajorMatrix, Matrix};
+    use sp1_core_executor::{ExecutionRecord, Executor, Program};
+    use sp1_curves::weierstrass::{
+        bn254::Bn254Parameters, secp256k1::Secp256k1Parameters, SwCurve,
+    };
+    use sp1_stark::{air::MachineAir, CpuProver, SP1CoreOpts};
+
+    use crate::{
+        syscall::precompiles::weierstrass::{
+            ec_chip_heights, SignChoiceRule, WeierstrassAddAssignChip, WeierstrassDecompressChip,
+            WeierstrassDoubleAssignChip,
+        },
+        utils::{
+            run_test, setup_logger,
+            tests::{BLS12381_DOUBLE_ELF, BN254_DOUBLE_ELF, SECP256K1_DOUBLE_ELF},
+        },
     };
 
     #[test]
@@ -548,4 +559,30 @@ pub mod tests {
         let program = Program::from(BLS12381_DOUBLE_ELF).unwrap();
         run_test::<CpuProver<_, _>>(program).unwrap();
     }
+
+    #[test]
+    fn test_ec_chip_heights() {
+        type Secp256k1 = SwCurve<Secp256k1Parameters>;
+        type Bn254 = SwCurve<Bn254Parameters>;
+        let add = MachineAir::<BabyBear>::name(&WeierstrassAddAssignChip::<Secp256k1>::new());
+        let double = MachineAir::<BabyBear>::name(&WeierstrassDoubleAssignChip::<Secp256k1>::new());
+        let decompress = MachineAir::<BabyBear>::name(
+            &WeierstrassDecompressChip::<Secp256k1>::new(SignChoiceRule::LeastSignificantBit),
+        );
+        let heights = ec_chip_heights::<Secp256k1>(0, 17, 64);
+        assert_eq!(heights[&add], 16);
+        assert_eq!(heights[&double], 32);
+        assert_eq!(heights[&decompress], 64);
+        assert_eq!(ec_chip_heights::<Bn254>(0, 0, 0).len(), 2);
+
+        let mut runtime =
+            Executor::new(Program::from(SECP256K1_DOUBLE_ELF).unwrap(), SP1CoreOpts::default());
+        runtime.run().unwrap();
+        let record = &runtime.records[0];
+        let chip = WeierstrassDoubleAssignChip::<Secp256k1>::new();
+        let trace: RowMajorMatrix<BabyBear> =
+            chip.generate_trace(record, &mut ExecutionRecord::default());
+        let heights = ec_chip_heights::<Secp256k1>(0, record.secp256k1_double_events.len(), 0);
+        assert_eq!(trace.height(), heights[&double]);
+    }
 }
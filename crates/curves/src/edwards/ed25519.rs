@@ -73,6 +73,12 @@ impl EdwardsParameters for Ed25519Parameters {
 /// This function always returns the nonnegative square root, in the sense that the least
 /// significant bit of the result is always 0.
 pub fn ed25519_sqrt(a: &BigUint) -> BigUint {
+    ed25519_try_sqrt(a).expect("a is not a square")
+}
+
+/// Computes the nonnegative square root of a number in the base field of Ed25519, or `None` if it
+/// is not a square.
+pub fn ed25519_try_sqrt(a: &BigUint) -> Option<BigUint> {
     // Here is a description of how to calculate sqrt in the Curve25519 base field:
     // ssh://git@github.com/succinctlabs/curve25519-dalek/blob/
     // e2d1bd10d6d772af07cac5c8161cd7655016af6d/curve25519-dalek/src/field.rs#L256
@@ -108,7 +114,7 @@ pub fn ed25519_sqrt(a: &BigUint) -> BigUint {
     let flipped_sign_sqrt = beta_squared == neg_a;
 
     if !correct_sign_sqrt && !flipped_sign_sqrt {
-        panic!("a is not a square");
+        return None;
     }
 
     let beta_bytes = beta.to_bytes_le();
@@ -116,10 +122,19 @@ pub fn ed25519_sqrt(a: &BigUint) -> BigUint {
         beta = (&modulus - &beta) % &modulus;
     }
 
-    beta
+    Some(beta)
 }
 
+/// Decompresses a point from its RFC 8032 encoding, panicking if it does not encode a point.
 pub fn decompress(compressed_point: &CompressedEdwardsY) -> AffinePoint<Ed25519> {
+    try_decompress(compressed_point).expect("not a compressed point")
+}
+
+/// Decompresses a point from its RFC 8032 encoding: the little-endian `y` coordinate, with the
+/// sign of `x` in the top bit of the last byte.
+///
+/// Returns `None` if no point has that `y` coordinate.
+pub fn try_decompress(compressed_point: &CompressedEdwardsY) -> Option<AffinePoint<Ed25519>> {
     let mut point_bytes = *compressed_point.as_bytes();
     let sign = point_bytes[31] >> 7 == 1;
     // mask out the sign bit
@@ -134,7 +149,7 @@ pub fn decompress(compressed_point: &CompressedEdwardsY) -> AffinePoint<Ed25519>
     let v_inv = v.modpow(&(modulus - BigUint::from(2u64)), modulus);
     let u_div_v = (u * &v_inv) % modulus;
 
-    let mut x = ed25519_sqrt(&u_div_v);
+    let mut x = ed25519_try_sqrt(&u_div_v)?;
 
     // sqrt always returns the nonnegative square root,
     // so we negate according to the supplied sign bit.
@@ -142,7 +157,7 @@ pub fn decompress(compressed_point: &CompressedEdwardsY) -> AffinePoint<Ed25519>
         x = modulus - &x;
     }
 
-    AffinePoint::new(x, y.clone())
+    Some(AffinePoint::new(x, y.clone()))
 }
 
 #[cfg(test)]
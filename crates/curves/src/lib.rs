@@ -26,7 +26,7 @@ use std::{
 use typenum::Unsigned;
 
 use num::BigUint;
-use serde::{de::DeserializeOwned, Serialize};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
 
 pub const NUM_WORDS_FIELD_ELEMENT: usize = 8;
 pub const NUM_BYTES_FIELD_ELEMENT: usize = NUM_WORDS_FIELD_ELEMENT * WORD_SIZE;
@@ -36,21 +36,25 @@ pub const COMPRESSED_POINT_BYTES: usize = 32;
 /// words needed to represent a field element as a point consists of the x and y coordinates.
 pub const NUM_WORDS_EC_POINT: usize = 2 * NUM_WORDS_FIELD_ELEMENT;
 
-#[derive(Debug, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 pub enum CurveType {
     Secp256k1,
+    Secp256r1,
     Bn254,
     Ed25519,
     Bls12381,
+    Bls12381G2,
 }
 
 impl Display for CurveType {
     fn fmt(&self, f: &mut Formatter<'_>) -> Result {
         match self {
             CurveType::Secp256k1 => write!(f, "Secp256k1"),
+            CurveType::Secp256r1 => write!(f, "Secp256r1"),
             CurveType::Bn254 => write!(f, "Bn254"),
             CurveType::Ed25519 => write!(f, "Ed25519"),
             CurveType::Bls12381 => write!(f, "Bls12381"),
+            CurveType::Bls12381G2 => write!(f, "Bls12381G2"),
         }
     }
 }
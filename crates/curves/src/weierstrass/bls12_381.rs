@@ -1,8 +1,8 @@
 use amcl::bls381::{big::Big, bls381::utils::deserialize_g1, fp::FP};
 use generic_array::GenericArray;
-use num::{BigUint, Num, Zero};
+use num::{BigUint, Num, One, Zero};
 use serde::{Deserialize, Serialize};
-use typenum::{U48, U94};
+use typenum::{U188, U48, U94, U96};
 
 use super::{FieldType, FpOpField, SwCurve, WeierstrassParameters};
 use crate::{
@@ -111,7 +111,12 @@ impl WeierstrassParameters for Bls12381Parameters {
     }
 }
 
-pub fn bls12381_decompress<E: EllipticCurve>(bytes_be: &[u8], sign_bit: u32) -> AffinePoint<E> {
+/// Decompresses a point from its big-endian `x` coordinate, returning `None` if `x` is not the
+/// coordinate of a point on the curve.
+pub fn bls12381_decompress<E: EllipticCurve>(
+    bytes_be: &[u8],
+    sign_bit: u32,
+) -> Option<AffinePoint<E>> {
     let mut g1_bytes_be: [u8; 48] = bytes_be.try_into().unwrap();
     let mut flags = COMPRESION_FLAG;
     if sign_bit == 1 {
@@ -120,14 +125,14 @@ pub fn bls12381_decompress<E: EllipticCurve>(bytes_be: &[u8], sign_bit: u32) ->
 
     // set sign and compression flag
     g1_bytes_be[0] |= flags;
-    let point = deserialize_g1(&g1_bytes_be).unwrap();
+    let point = deserialize_g1(&g1_bytes_be).ok()?;
 
     let x_str = point.getx().to_string();
     let x = BigUint::from_str_radix(x_str.as_str(), 16).unwrap();
     let y_str = point.gety().to_string();
     let y = BigUint::from_str_radix(y_str.as_str(), 16).unwrap();
 
-    AffinePoint::new(x, y)
+    Some(AffinePoint::new(x, y))
 }
 
 pub fn bls12381_sqrt(a: &BigUint) -> BigUint {
@@ -138,6 +143,249 @@ pub fn bls12381_sqrt(a: &BigUint) -> BigUint {
     BigUint::from_str_radix(a_sqrt.to_string().as_str(), 16).unwrap()
 }
 
+/// The order `r` of the prime-order subgroup G1 of [`Bls12381`], in big-endian hex.
+const G1_ORDER_HEX: &str = "73eda753299d7d483339d80809a1d80553bda402fffe5bfeffffffff00000001";
+
+/// A point of [`Bls12381`] in Jacobian coordinates `(X, Y, Z)`, standing for the affine point
+/// `(X / Z^2, Y / Z^3)`, or for the identity if `Z` is zero.
+type Jacobian = (BigUint, BigUint, BigUint);
+
+fn jacobian_identity() -> Jacobian {
+    (BigUint::one(), BigUint::one(), BigUint::zero())
+}
+
+/// Doubles a point in Jacobian coordinates, for a curve with `a = 0`.
+fn jacobian_double((x, y, z): &Jacobian, p: &BigUint) -> Jacobian {
+    if z.is_zero() || y.is_zero() {
+        return jacobian_identity();
+    }
+    let a = x * x % p;
+    let b = y * y % p;
+    let c = &b * &b % p;
+    let x_plus_b = (x + &b) % p;
+    let d = 2u32 * ((&x_plus_b * &x_plus_b % p + 2u32 * p - &a - &c) % p) % p;
+    let e = 3u32 * &a % p;
+    let x3 = (&e * &e % p + 2u32 * p - 2u32 * &d) % p;
+    let y3 = (&e * ((&d + p - &x3) % p) % p + 8u32 * p - 8u32 * &c) % p;
+    let z3 = 2u32 * y * z % p;
+    (x3, y3, z3)
+}
+
+/// Adds an affine point to a point in Jacobian coordinates.
+fn jacobian_add_affine(point: &Jacobian, x2: &BigUint, y2: &BigUint, p: &BigUint) -> Jacobian {
+    let (x1, y1, z1) = point;
+    if z1.is_zero() {
+        return (x2.clone(), y2.clone(), BigUint::one());
+    }
+    let z1_z1 = z1 * z1 % p;
+    let u2 = x2 * &z1_z1 % p;
+    let s2 = y2 * z1 % p * &z1_z1 % p;
+    let h = (&u2 + p - x1) % p;
+    let r = (&s2 + p - y1) % p;
+    if h.is_zero() {
+        return if r.is_zero() { jacobian_double(point, p) } else { jacobian_identity() };
+    }
+    let h_h = &h * &h % p;
+    let h_h_h = &h * &h_h % p;
+    let v = x1 * &h_h % p;
+    let x3 = (&r * &r % p + 3u32 * p - &h_h_h - 2u32 * &v) % p;
+    let y3 = (&r * ((&v + p - &x3) % p) % p + p - y1 * &h_h_h % p) % p;
+    let z3 = z1 * &h % p;
+    (x3, y3, z3)
+}
+
+/// Whether `point`, a point of [`Bls12381`] with reduced coordinates, is in the prime-order
+/// subgroup G1.
+///
+/// The curve has a large cofactor, so a point decompressed from an arbitrary `x` is almost never in
+/// G1. The check computes `[r]P` in Jacobian coordinates, where the identity is representable, and
+/// compares it to the identity. This costs a full scalar multiplication, about 255 doublings and
+/// 128 additions, which is far more than decompressing the point. The endomorphism-based subgroup
+/// tests are several times faster, but this one needs nothing beyond the curve's group law.
+#[must_use]
+pub fn bls12381_g1_in_subgroup(point: &AffinePoint<Bls12381>) -> bool {
+    let p = Bls12381BaseField::modulus();
+    let order = BigUint::from_str_radix(G1_ORDER_HEX, 16).unwrap();
+    let mut result = jacobian_identity();
+    for i in (0..order.bits()).rev() {
+        result = jacobian_double(&result, &p);
+        if order.bit(i) {
+            result = jacobian_add_affine(&result, &point.x, &point.y, &p);
+        }
+    }
+    result.2.is_zero()
+}
+
+/// The number of bits of each coefficient of an [`Bls12381Fp2`] in its packed encoding.
+const FP2_COEFFICIENT_BITS: usize = 384;
+
+/// An element `c0 + c1 * u` of the quadratic extension `Fp2 = Fp[u] / (u^2 + 1)` of the Bls12381
+/// base field, over which the G2 group is defined.
+///
+/// In a point of [`Bls12381G2`], each coordinate is packed into a single integer
+/// `c0 + c1 * 2^384`, so that its words in memory are the words of `c0` followed by those of `c1`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Bls12381Fp2 {
+    pub c0: BigUint,
+    pub c1: BigUint,
+}
+
+impl Bls12381Fp2 {
+    pub fn new(c0: BigUint, c1: BigUint) -> Self {
+        Self { c0, c1 }
+    }
+
+    /// Unpacks an element from its encoding `c0 + c1 * 2^384`.
+    pub fn from_packed(packed: &BigUint) -> Self {
+        let mask = (BigUint::from(1u32) << FP2_COEFFICIENT_BITS) - 1u32;
+        Self::new(packed & &mask, packed >> FP2_COEFFICIENT_BITS)
+    }
+
+    /// Packs the element into the encoding `c0 + c1 * 2^384`.
+    pub fn to_packed(&self) -> BigUint {
+        &self.c0 + (&self.c1 << FP2_COEFFICIENT_BITS)
+    }
+
+    fn add(&self, other: &Self) -> Self {
+        let modulus = Bls12381BaseField::modulus();
+        Self::new((&self.c0 + &other.c0) % &modulus, (&self.c1 + &other.c1) % &modulus)
+    }
+
+    fn sub(&self, other: &Self) -> Self {
+        let modulus = Bls12381BaseField::modulus();
+        Self::new(
+            (&self.c0 + &modulus - &other.c0 % &modulus) % &modulus,
+            (&self.c1 + &modulus - &other.c1 % &modulus) % &modulus,
+        )
+    }
+
+    fn mul(&self, other: &Self) -> Self {
+        let modulus = Bls12381BaseField::modulus();
+        // (a0 + a1 u)(b0 + b1 u) = (a0 b0 - a1 b1) + (a0 b1 + a1 b0) u, since u^2 = -1.
+        let c0 = (&self.c0 * &other.c0 + &modulus - &self.c1 * &other.c1 % &modulus) % &modulus;
+        let c1 = (&self.c0 * &other.c1 + &self.c1 * &other.c0) % &modulus;
+        Self::new(c0, c1)
+    }
+
+    fn scale(&self, k: u32) -> Self {
+        let modulus = Bls12381BaseField::modulus();
+        Self::new(&self.c0 * k % &modulus, &self.c1 * k % &modulus)
+    }
+
+    fn neg(&self) -> Self {
+        Self::new(BigUint::zero(), BigUint::zero()).sub(self)
+    }
+
+    fn inv(&self) -> Self {
+        let modulus = Bls12381BaseField::modulus();
+        // 1 / (c0 + c1 u) = (c0 - c1 u) / (c0^2 + c1^2).
+        let norm = (&self.c0 * &self.c0 + &self.c1 * &self.c1) % &modulus;
+        assert!(!norm.is_zero(), "inverse of zero");
+        let norm_inv = norm.modpow(&(&modulus - 2u32), &modulus);
+        Self::new(
+            &self.c0 * &norm_inv % &modulus,
+            (&modulus - &self.c1 % &modulus) * &norm_inv % &modulus,
+        )
+    }
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Serialize, Deserialize)]
+/// Bls12381 quadratic extension field parameter, for the coordinates of G2 points.
+///
+/// An element has twice the limbs of a [`Bls12381BaseField`] element, and each of its two
+/// coefficients is reduced modulo the base field modulus.
+pub struct Bls12381Fp2Field;
+
+impl FieldParameters for Bls12381Fp2Field {
+    const MODULUS: &'static [u8] = Bls12381BaseField::MODULUS;
+
+    const WITNESS_OFFSET: usize = Bls12381BaseField::WITNESS_OFFSET;
+
+    fn modulus() -> BigUint {
+        Bls12381BaseField::modulus()
+    }
+}
+
+impl NumLimbs for Bls12381Fp2Field {
+    type Limbs = U96;
+    type Witness = U188;
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+/// The G2 group of Bls12381: the curve `y^2 = x^3 + 4(1 + u)` over [`Bls12381Fp2`].
+pub struct Bls12381G2;
+
+impl Bls12381G2 {
+    /// The `b` coefficient of the curve, `4(1 + u)`.
+    fn b() -> Bls12381Fp2 {
+        Bls12381Fp2::new(BigUint::from(4u32), BigUint::from(4u32))
+    }
+
+    fn coordinates(p: &AffinePoint<Self>) -> (Bls12381Fp2, Bls12381Fp2) {
+        (Bls12381Fp2::from_packed(&p.x), Bls12381Fp2::from_packed(&p.y))
+    }
+
+    fn point(x: &Bls12381Fp2, y: &Bls12381Fp2) -> AffinePoint<Self> {
+        AffinePoint::new(x.to_packed(), y.to_packed())
+    }
+}
+
+impl EllipticCurveParameters for Bls12381G2 {
+    type BaseField = Bls12381Fp2Field;
+    const CURVE_TYPE: CurveType = CurveType::Bls12381G2;
+}
+
+impl EllipticCurve for Bls12381G2 {
+    fn ec_add(p: &AffinePoint<Self>, q: &AffinePoint<Self>) -> AffinePoint<Self> {
+        let (px, py) = Self::coordinates(p);
+        let (qx, qy) = Self::coordinates(q);
+        let lambda = qy.sub(&py).mul(&qx.sub(&px).inv());
+        let x = lambda.mul(&lambda).sub(&px).sub(&qx);
+        let y = lambda.mul(&px.sub(&x)).sub(&py);
+        Self::point(&x, &y)
+    }
+
+    fn ec_double(p: &AffinePoint<Self>) -> AffinePoint<Self> {
+        let (px, py) = Self::coordinates(p);
+        let lambda = px.mul(&px).scale(3).mul(&py.scale(2).inv());
+        let x = lambda.mul(&lambda).sub(&px.scale(2));
+        let y = lambda.mul(&px.sub(&x)).sub(&py);
+        Self::point(&x, &y)
+    }
+
+    // The generator has been taken from the IETF pairing-friendly curves draft.
+    // https://datatracker.ietf.org/doc/html/draft-irtf-cfrg-pairing-friendly-curves-11#section-4.2.1
+    fn ec_generator() -> AffinePoint<Self> {
+        let hex = |s: &str| BigUint::from_str_radix(s, 16).unwrap();
+        let x = Bls12381Fp2::new(
+            hex("024aa2b2f08f0a91260805272dc51051c6e47ad4fa403b02b4510b647ae3d1770bac0326a805bbefd48056c8c121bdb8"),
+            hex("13e02b6052719f607dacd3a088274f65596bd0d09920b61ab5da61bbdc7f5049334cf11213945d57e5ac7d055d042b7e"),
+        );
+        let y = Bls12381Fp2::new(
+            hex("0ce5d527727d6e118cc9cdc6da2e351aadfd9baa8cbdd3a76d429a695160d12c923ac9cc3baca289e193548608b82801"),
+            hex("0606c4a02ea734cc32acd2b02bc28b99cb3e287e85a763af267492ab572e99ab3f370d275cec1da1aaa9075ff05f79be"),
+        );
+        Self::point(&x, &y)
+    }
+
+    fn ec_neutral() -> Option<AffinePoint<Self>> {
+        None
+    }
+
+    fn ec_neg(p: &AffinePoint<Self>) -> AffinePoint<Self> {
+        let (x, y) = Self::coordinates(p);
+        Self::point(&x, &y.neg())
+    }
+}
+
+impl AffinePoint<Bls12381G2> {
+    /// Whether the point satisfies `y^2 = x^3 + 4(1 + u)`.
+    pub fn g2_is_on_curve(&self) -> bool {
+        let (x, y) = Bls12381G2::coordinates(self);
+        y.mul(&y) == x.mul(&x).mul(&x).add(&Bls12381G2::b())
+    }
+}
+
 #[cfg(test)]
 mod tests {
 
@@ -185,13 +433,67 @@ mod tests {
 
                 (result, is_odd)
             };
-            assert_eq!(point, bls12381_decompress(&compressed_point, is_odd));
+            assert_eq!(point, bls12381_decompress(&compressed_point, is_odd).unwrap());
 
             // Double the point to create a "random" point for the next iteration.
             point = point.clone().sw_double();
         }
     }
 
+    #[test]
+    fn test_bls12381_g1_in_subgroup() {
+        let generator = Bls12381::ec_generator();
+        assert!(bls12381_g1_in_subgroup(&generator));
+        assert!(bls12381_g1_in_subgroup(&generator.sw_double()));
+
+        // `x = 4` is on the curve, but neither of its points is in G1.
+        let modulus = Bls12381BaseField::modulus();
+        let x = BigUint::from(4u32);
+        let y = (&x * &x * &x + 4u32).modpow(&((&modulus + 1u32) / 4u32), &modulus);
+        let point = AffinePoint::<Bls12381>::new(x.clone(), y.clone());
+        assert!(point.sw_is_on_curve());
+        assert!(!bls12381_g1_in_subgroup(&point));
+        assert!(!bls12381_g1_in_subgroup(&AffinePoint::new(x, &modulus - y)));
+    }
+
+    #[test]
+    fn test_bls12381_g2_add_and_double() {
+        let hex = |s: &str| BigUint::from_str_radix(s, 16).unwrap();
+        let point = |x0: &str, x1: &str, y0: &str, y1: &str| {
+            let x = Bls12381Fp2::new(hex(x0), hex(x1));
+            let y = Bls12381Fp2::new(hex(y0), hex(y1));
+            Bls12381G2::point(&x, &y)
+        };
+        let two_g = point(
+            "1638533957d540a9d2370f17cc7ed5863bc0b995b8825e0ee1ea1e1e4d00dbae81f14b0bf3611b78c952aacab827a053",
+            "0a4edef9c1ed7f729f520e47730a124fd70662a904ba1074728114d1031e1572c6c886f6b57ec72a6178288c47c33577",
+            "0468fb440d82b0630aeb8dca2b5256789a66da69bf91009cbfe6bd221e47aa8ae88dece9764bf3bd999d95d71e4c9899",
+            "0f6d4552fa65dd2638b361543f887136a43253d9c66c411697003f7a13c308f5422e1aa0a59c8967acdefd8b6e36ccf3",
+        );
+        let three_g = point(
+            "122915c824a0857e2ee414a3dccb23ae691ae54329781315a0c75df1c04d6d7a50a030fc866f09d516020ef82324afae",
+            "09380275bbc8e5dcea7dc4dd7e0550ff2ac480905396eda55062650f8d251c96eb480673937cc6d9d6a44aaa56ca66dc",
+            "0b21da7955969e61010c7a1abc1a6f0136961d1e3b20b1a7326ac738fef5c721479dfd948b52fdf2455e44813ecfd892",
+            "08f239ba329b3967fe48d718a36cfe5f62a7e42e0bf1c1ed714150a166bfbd6bcf6b3b58b975b9edea56d53f23a0e849",
+        );
+
+        let generator = Bls12381G2::ec_generator();
+        assert!(generator.g2_is_on_curve());
+        assert_eq!(Bls12381G2::ec_double(&generator), two_g);
+        assert_eq!(Bls12381G2::ec_add(&generator, &two_g), three_g);
+        assert!(three_g.g2_is_on_curve());
+
+        // G + 3G = 2G + 2G, and adding a point's negation's negation is a no-op.
+        let four_g = Bls12381G2::ec_double(&two_g);
+        assert_eq!(Bls12381G2::ec_add(&generator, &three_g), four_g);
+        assert_eq!(-(-&three_g), three_g);
+
+        // Each coordinate spans the words of both of its coefficients.
+        let words = three_g.to_words_le();
+        assert_eq!(words.len(), 48);
+        assert_eq!(AffinePoint::<Bls12381G2>::from_words_le(&words), three_g);
+    }
+
     #[test]
     fn test_bls12381_sqrt() {
         let mut rng = thread_rng();
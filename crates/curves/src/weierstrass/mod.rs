@@ -12,6 +12,7 @@ use crate::{
 pub mod bls12_381;
 pub mod bn254;
 pub mod secp256k1;
+pub mod secp256r1;
 
 /// Parameters that specify a short Weierstrass curve : y^2 = x^3 + ax + b.
 pub trait WeierstrassParameters: EllipticCurveParameters {
@@ -168,6 +169,17 @@ pub fn dashu_modpow(
 }
 
 impl<E: WeierstrassParameters> AffinePoint<SwCurve<E>> {
+    /// Whether the point has canonical coordinates and satisfies `y^2 = x^3 + ax + b`.
+    pub fn sw_is_on_curve(&self) -> bool {
+        let p = E::BaseField::modulus();
+        if self.x >= p || self.y >= p {
+            return false;
+        }
+        let lhs = (&self.y * &self.y) % &p;
+        let rhs = (&self.x * &self.x * &self.x + E::a_int() * &self.x + E::b_int()) % &p;
+        lhs == rhs
+    }
+
     pub fn sw_add(&self, other: &AffinePoint<SwCurve<E>>) -> AffinePoint<SwCurve<E>> {
         if self.x == other.x && self.y == other.y {
             panic!("Error: Points are the same. Use sw_double instead.");
@@ -227,10 +239,11 @@ pub trait FpOpField: FieldParameters + NumWords {
 #[cfg(test)]
 mod tests {
 
-    use num::bigint::RandBigInt;
+    use num::{bigint::RandBigInt, BigUint, Num, Zero};
     use rand::thread_rng;
 
-    use super::bn254;
+    use super::{bn254, secp256r1::Secp256r1};
+    use crate::{params::FieldParameters, EllipticCurveParameters};
 
     #[test]
     fn test_weierstrass_biguint_scalar_mul() {
@@ -249,4 +262,41 @@ mod tests {
             assert_eq!(y_x_base, xy_base);
         }
     }
+
+    #[test]
+    fn test_weierstrass_double_with_nonzero_coefficients() {
+        type E = Secp256r1;
+        let p = <E as EllipticCurveParameters>::BaseField::modulus();
+        assert_eq!(E::a_int(), &p - 3u32);
+        assert!(!E::b_int().is_zero());
+
+        // Twice the generator, from the SEC 2 parameters of P-256.
+        let doubled = E::generator().sw_double();
+        assert!(doubled.sw_is_on_curve());
+        let hex = |s: &str| BigUint::from_str_radix(s, 16).unwrap();
+        assert_eq!(
+            doubled.x,
+            hex("7cf27b188d034f7e8a52380304b51ac3c08969e277f21b35a60b48fc47669978")
+        );
+        assert_eq!(
+            doubled.y,
+            hex("07775510db8ed040293d9ac69f7430dbba7dade63ce982299e04b79d227873d1")
+        );
+
+        let quadrupled = doubled.sw_double();
+        assert!(quadrupled.sw_is_on_curve());
+        assert_eq!(quadrupled, doubled.sw_add(&E::generator()).sw_add(&E::generator()));
+    }
+
+    #[test]
+    fn test_weierstrass_is_on_curve() {
+        type E = bn254::Bn254;
+        let base = E::generator();
+        assert!(base.sw_is_on_curve());
+        assert!(base.sw_double().sw_is_on_curve());
+
+        let mut off_curve = base.clone();
+        off_curve.y += 1u32;
+        assert!(!off_curve.sw_is_on_curve());
+    }
 }
@@ -92,14 +92,19 @@ impl WeierstrassParameters for Secp256k1Parameters {
     }
 }
 
-pub fn secp256k1_decompress<E: EllipticCurve>(bytes_be: &[u8], sign: u32) -> AffinePoint<E> {
-    let computed_point =
-        k256::AffinePoint::decompress(bytes_be.into(), Choice::from(sign as u8)).unwrap();
+/// Decompresses a point from its big-endian `x` coordinate, returning `None` if `x` is not the
+/// coordinate of a point on the curve.
+pub fn secp256k1_decompress<E: EllipticCurve>(
+    bytes_be: &[u8],
+    sign: u32,
+) -> Option<AffinePoint<E>> {
+    let computed_point: k256::AffinePoint =
+        Option::from(k256::AffinePoint::decompress(bytes_be.into(), Choice::from(sign as u8)))?;
     let point = computed_point.to_encoded_point(false);
 
     let x = BigUint::from_bytes_be(point.x().unwrap());
     let y = BigUint::from_bytes_be(point.y().unwrap());
-    AffinePoint::<E>::new(x, y)
+    Some(AffinePoint::<E>::new(x, y))
 }
 
 pub fn secp256k1_sqrt(n: &BigUint) -> BigUint {
@@ -141,4 +146,13 @@ mod tests {
             assert_eq!(sqrt_2, x_2);
         }
     }
+
+    #[test]
+    fn test_secp256k1_decompress_non_residue() {
+        // 5^3 + 7 is not a square, so no point has x = 5.
+        let mut x_bytes_be = [0u8; 32];
+        x_bytes_be[31] = 5;
+        assert!(secp256k1_decompress::<Secp256k1>(&x_bytes_be, 0).is_none());
+        assert!(secp256k1_decompress::<Secp256k1>(&x_bytes_be, 1).is_none());
+    }
 }
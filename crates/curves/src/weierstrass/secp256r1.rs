@@ -0,0 +1,164 @@
+//! Modulo defining the Secp256r1 (NIST P-256) curve and its base field. The constants are all
+//! taken from https://www.secg.org/sec2-v2.pdf.
+
+use generic_array::GenericArray;
+use num::{BigUint, Num};
+use serde::{Deserialize, Serialize};
+use typenum::{U32, U62};
+
+use super::{SwCurve, WeierstrassParameters};
+use crate::{
+    params::{FieldParameters, NumLimbs},
+    AffinePoint, CurveType, EllipticCurve, EllipticCurveParameters,
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+/// Secp256r1 curve parameter
+pub struct Secp256r1Parameters;
+
+pub type Secp256r1 = SwCurve<Secp256r1Parameters>;
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Serialize, Deserialize)]
+/// Secp256r1 base field parameter
+pub struct Secp256r1BaseField;
+
+impl FieldParameters for Secp256r1BaseField {
+    const MODULUS: &'static [u8] = &[
+        0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0x00, 0x00, 0x00,
+        0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x01, 0x00, 0x00, 0x00, 0xff, 0xff,
+        0xff, 0xff,
+    ];
+
+    /// A rough witness-offset estimate given the size of the limbs and the size of the field.
+    const WITNESS_OFFSET: usize = 1usize << 14;
+
+    fn modulus() -> BigUint {
+        BigUint::from_bytes_le(Self::MODULUS)
+    }
+}
+
+impl NumLimbs for Secp256r1BaseField {
+    type Limbs = U32;
+    type Witness = U62;
+}
+
+impl EllipticCurveParameters for Secp256r1Parameters {
+    type BaseField = Secp256r1BaseField;
+    const CURVE_TYPE: CurveType = CurveType::Secp256r1;
+}
+
+impl WeierstrassParameters for Secp256r1Parameters {
+    /// `a = -3` in the base field.
+    const A: GenericArray<u8, U32> = GenericArray::from_array([
+        0xfc, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0x00, 0x00, 0x00,
+        0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x01, 0x00, 0x00, 0x00, 0xff, 0xff,
+        0xff, 0xff,
+    ]);
+
+    const B: GenericArray<u8, U32> = GenericArray::from_array([
+        0x4b, 0x60, 0xd2, 0x27, 0x3e, 0x3c, 0xce, 0x3b, 0xf6, 0xb0, 0x53, 0xcc, 0xb0, 0x06, 0x1d,
+        0x65, 0xbc, 0x86, 0x98, 0x76, 0x55, 0xbd, 0xeb, 0xb3, 0xe7, 0x93, 0x3a, 0xaa, 0xd8, 0x35,
+        0xc6, 0x5a,
+    ]);
+    fn generator() -> (BigUint, BigUint) {
+        let x = BigUint::from_str_radix(
+            "6b17d1f2e12c4247f8bce6e563a440f277037d812deb33a0f4a13945d898c296",
+            16,
+        )
+        .unwrap();
+        let y = BigUint::from_str_radix(
+            "4fe342e2fe1a7f9b8ee7eb4a7c0f9e162bce33576b315ececbb6406837bf51f5",
+            16,
+        )
+        .unwrap();
+        (x, y)
+    }
+
+    fn prime_group_order() -> num::BigUint {
+        BigUint::from_slice(&[
+            0xFC632551, 0xF3B9CAC2, 0xA7179E84, 0xBCE6FAAD, 0xFFFFFFFF, 0xFFFFFFFF, 0x00000000,
+            0xFFFFFFFF,
+        ])
+    }
+}
+
+/// Decompresses a point from its big-endian `x` coordinate, choosing the `y` whose parity is
+/// `sign`, as in the SEC1 encoding.
+///
+/// Returns `None` if `x` is not the coordinate of a point on the curve.
+pub fn secp256r1_decompress<E: EllipticCurve>(
+    bytes_be: &[u8],
+    sign: u32,
+) -> Option<AffinePoint<E>> {
+    let modulus = Secp256r1BaseField::modulus();
+    let x = BigUint::from_bytes_be(bytes_be);
+    if x >= modulus {
+        return None;
+    }
+
+    let y_squared = (x.modpow(&BigUint::from(3u32), &modulus)
+        + Secp256r1Parameters::a_int() * &x
+        + Secp256r1Parameters::b_int())
+        % &modulus;
+    let mut y = secp256r1_try_sqrt(&y_squared)?;
+    if y.bit(0) != (sign == 1) {
+        y = (&modulus - &y) % &modulus;
+    }
+    Some(AffinePoint::<E>::new(x, y))
+}
+
+/// Computes the square root of `n` in the base field, panicking if it is not a square.
+pub fn secp256r1_sqrt(n: &BigUint) -> BigUint {
+    secp256r1_try_sqrt(n).expect("not a quadratic residue")
+}
+
+/// Computes the square root of `n` in the base field, or `None` if it is not a square.
+///
+/// The modulus is `3 mod 4`, so the root is `n^((p + 1) / 4)`.
+fn secp256r1_try_sqrt(n: &BigUint) -> Option<BigUint> {
+    let modulus = Secp256r1BaseField::modulus();
+    let sqrt = n.modpow(&((&modulus + 1u32) >> 2), &modulus);
+    (&sqrt * &sqrt % &modulus == n % &modulus).then_some(sqrt)
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use crate::utils::biguint_from_limbs;
+    use num::bigint::RandBigInt;
+    use rand::thread_rng;
+
+    #[test]
+    fn test_weierstrass_biguint_scalar_mul() {
+        assert_eq!(biguint_from_limbs(Secp256r1BaseField::MODULUS), Secp256r1BaseField::modulus());
+    }
+
+    #[test]
+    fn test_secp256r1_sqrt() {
+        let mut rng = thread_rng();
+        for _ in 0..10 {
+            // Check that sqrt(x^2)^2 == x^2
+            // We use x^2 since not all field elements have a square root
+            let x = rng.gen_biguint(256) % Secp256r1BaseField::modulus();
+            let x_2 = (&x * &x) % Secp256r1BaseField::modulus();
+            let sqrt = secp256r1_sqrt(&x_2);
+
+            let sqrt_2 = (&sqrt * &sqrt) % Secp256r1BaseField::modulus();
+
+            assert_eq!(sqrt_2, x_2);
+        }
+    }
+
+    #[test]
+    fn test_secp256r1_decompress_generator() {
+        let generator = Secp256r1::ec_generator();
+        assert!(generator.sw_is_on_curve());
+
+        let x_bytes_be = generator.x.to_bytes_be();
+        let odd = secp256r1_decompress::<Secp256r1>(&x_bytes_be, 1).unwrap();
+        let even = secp256r1_decompress::<Secp256r1>(&x_bytes_be, 0).unwrap();
+        assert_eq!(odd, generator);
+        assert_eq!(even.y, Secp256r1BaseField::modulus() - &generator.y);
+    }
+}
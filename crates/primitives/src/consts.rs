@@ -4,6 +4,12 @@ pub const MAXIMUM_MEMORY_SIZE: u32 = u32::MAX;
 /// The size of a word in bytes.
 pub const WORD_SIZE: usize = 4;
 
+/// The initial stack pointer of a guest, set by the zkVM entrypoint. The stack grows down from it.
+pub const STACK_TOP: u32 = 0x0020_0400;
+
+/// The address past the end of the guest heap, enforced by the zkVM entrypoint's allocator.
+pub const MAX_MEMORY: u32 = 0x7800_0000;
+
 /// Converts a slice of words to a byte vector in little endian.
 pub fn words_to_bytes_le_vec(words: &[u32]) -> Vec<u8> {
     words.iter().flat_map(|word| word.to_le_bytes().to_vec()).collect::<Vec<_>>()
@@ -1,4 +1,4 @@
-use std::borrow::Borrow;
+use std::{borrow::Borrow, marker::PhantomData};
 
 use itertools::Itertools;
 use p3_baby_bear::BabyBear;
@@ -24,7 +24,7 @@ use sp1_stark::{
 };
 
 use crate::{
-    challenger::{CanObserveVariable, MultiField32ChallengerVariable},
+    challenger::{CanObserveVariable, DomainSeparationTags, MultiField32ChallengerVariable},
     stark::{ShardProofVariable, StarkVerifier},
     utils::{felt_bytes_to_bn254_var, felts_to_bn254_var, words_to_bytes},
     witness::Witnessable,
@@ -102,7 +102,7 @@ where
 
     challenger.observe_slice(&mut builder, proof.clone().public_values);
 
-    let StarkVerifyingKey { commit, pc_start, chip_information, chip_ordering } = wrap_vk;
+    let StarkVerifyingKey { commit, pc_start, chip_information, chip_ordering, .. } = wrap_vk;
 
     let wrap_vk = VerifyingKeyVariable {
         commitment: commit
@@ -114,6 +114,7 @@ where
         pc_start: builder.eval(*pc_start),
         chip_information: chip_information.clone(),
         chip_ordering: chip_ordering.clone(),
+        _phantom: PhantomData,
     };
 
     StarkVerifier::<OuterC, OuterSC, _>::verify_shard(
@@ -122,6 +123,7 @@ where
         &outer_machine,
         &mut challenger.clone(),
         &proof,
+        DomainSeparationTags::default(),
     );
 
     let zero_ext: Ext<_, _> = builder.constant(<OuterConfig as Config>::EF::zero());
@@ -308,9 +310,9 @@ pub mod tests {
 
     use p3_baby_bear::{BabyBear, DiffusionMatrixBabyBear};
     use p3_challenger::{CanObserve, FieldChallenger};
-    use p3_commit::{Pcs, TwoAdicMultiplicativeCoset};
+    use p3_commit::{Pcs, PolynomialSpace, TwoAdicMultiplicativeCoset};
     use p3_field::{extension::BinomialExtensionField, AbstractField};
-    use p3_matrix::dense::RowMajorMatrix;
+    use p3_matrix::{dense::RowMajorMatrix, Dimensions};
     use rand::{rngs::StdRng, SeedableRng};
     use sp1_core_machine::utils::{log2_strict_usize, run_test_machine, setup_logger};
     use sp1_recursion_compiler::{
@@ -332,7 +334,7 @@ pub mod tests {
     use sp1_stark::{BabyBearPoseidon2Inner, StarkMachine};
 
     use crate::{
-        challenger::CanObserveVariable,
+        challenger::{CanObserveVariable, DomainSeparationTags},
         fri::verify_two_adic_pcs,
         hash::BN254_DIGEST_SIZE,
         utils::{babybear_bytes_to_bn254, babybears_to_bn254, words_to_bytes},
@@ -432,7 +434,9 @@ pub mod tests {
                         .collect::<Vec<_>>()
                 })
                 .collect::<Vec<_>>();
-            let domain_points_and_values = TwoAdicPcsMatsVariable { domain, points, values };
+            let dims = Dimensions { width: values[0].len(), height: domain.size() };
+            let domain_points_and_values =
+                TwoAdicPcsMatsVariable::new(domain, points, values, dims).unwrap();
             domains_points_and_opens.push(domain_points_and_values);
         }
 
@@ -508,6 +512,7 @@ pub mod tests {
             &proof,
             &mut challenger,
             rounds,
+            DomainSeparationTags::default(),
         );
         let mut backend = ConstraintCompiler::<OuterConfig>::default();
         let constraints = backend.emit(builder.operations);
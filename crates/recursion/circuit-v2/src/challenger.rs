@@ -9,6 +9,7 @@ use sp1_recursion_core_v2::{
     runtime::{HASH_RATE, PERMUTATION_WIDTH},
     NUM_BITS,
 };
+pub use sp1_stark::{DomainSeparationTags, COMMIT_PHASE_TAG, VK_TAG};
 
 // Constants for the Multifield challenger.
 pub const SPONGE_SIZE: usize = 3;
@@ -31,6 +32,23 @@ pub trait CanObserveVariable<C: Config, V> {
     }
 }
 
+/// Absorbs domain-separation tags into the transcript.
+pub trait CanObserveTagVariable<C: Config>: CanObserveVariable<C, Felt<C::F>> {
+    fn observe_tag(&mut self, builder: &mut Builder<C>, tag: u32) {
+        let tag: Felt<C::F> = builder.eval(C::F::from_canonical_u32(tag));
+        CanObserveVariable::<C, Felt<C::F>>::observe(self, builder, tag);
+    }
+
+    /// Observes the tag if tagging is enabled for this phase.
+    fn observe_optional_tag(&mut self, builder: &mut Builder<C>, tag: Option<u32>) {
+        if let Some(tag) = tag {
+            self.observe_tag(builder, tag);
+        }
+    }
+}
+
+impl<C: Config, T: CanObserveVariable<C, Felt<C::F>>> CanObserveTagVariable<C> for T {}
+
 pub trait CanSampleVariable<C: Config, V> {
     fn sample(&mut self, builder: &mut Builder<C>) -> V;
 }
@@ -459,7 +477,10 @@ pub(crate) mod tests {
     use sp1_stark::{baby_bear_poseidon2::BabyBearPoseidon2, StarkGenericConfig};
 
     use crate::{
-        challenger::{DuplexChallengerVariable, FieldChallengerVariable},
+        challenger::{
+            CanObserveTagVariable, DuplexChallengerVariable, FieldChallengerVariable,
+            COMMIT_PHASE_TAG, VK_TAG,
+        },
         witness::OuterWitness,
     };
 
@@ -508,6 +529,76 @@ pub(crate) mod tests {
         run_test_recursion(builder.operations, None);
     }
 
+    #[test]
+    fn test_observe_tag_separates_phases() {
+        let config = SC::default();
+        let sample_after = |tags: [u32; 2]| -> F {
+            let mut challenger = config.challenger();
+            for tag in tags {
+                challenger.observe(F::from_canonical_u32(tag));
+                challenger.observe(F::one());
+            }
+            challenger.sample()
+        };
+        let in_order = sample_after([VK_TAG, COMMIT_PHASE_TAG]);
+        let reordered = sample_after([COMMIT_PHASE_TAG, VK_TAG]);
+        assert_ne!(in_order, reordered);
+
+        let mut builder = AsmBuilder::<F, EF>::default();
+        let mut challenger = DuplexChallengerVariable::new(&mut builder);
+        let one: Felt<_> = builder.eval(F::one());
+        for tag in [VK_TAG, COMMIT_PHASE_TAG] {
+            challenger.observe_tag(&mut builder, tag);
+            challenger.observe(&mut builder, one);
+        }
+        let element = challenger.sample(&mut builder);
+
+        let expected: Felt<_> = builder.eval(in_order);
+        builder.assert_felt_eq(expected, element);
+
+        run_test_recursion(builder.operations, None);
+    }
+
+    #[test]
+    fn test_challenger_iv_mismatch_rejected() {
+        use crate::BabyBearFriConfigVariable;
+        use sp1_recursion_compiler::config::InnerConfig;
+
+        let config = SC::default();
+        let prover_iv = [F::from_canonical_u32(7), F::from_canonical_u32(11)];
+        let mut prover_challenger = config.challenger_with_iv(&prover_iv);
+        prover_challenger.observe(F::one());
+        let expected: F = prover_challenger.sample();
+
+        let build = |iv: &[F]| {
+            let mut builder = Builder::<InnerConfig>::default();
+            let mut challenger = config.challenger_variable_with_iv(&mut builder, iv);
+            let one: Felt<_> = builder.eval(F::one());
+            challenger.observe(&mut builder, one);
+            let element = challenger.sample(&mut builder);
+            let expected: Felt<_> = builder.eval(expected);
+            builder.assert_felt_eq(expected, element);
+            builder.operations
+        };
+
+        run_test_recursion(build(&prover_iv), None);
+
+        let verifier_iv = [F::from_canonical_u32(7), F::from_canonical_u32(13)];
+        let operations = build(&verifier_iv);
+        let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+            run_test_recursion(operations, None)
+        }));
+        assert!(result.is_err(), "mismatched IV was accepted");
+
+        // An empty IV leaves the default challenger unchanged.
+        let mut default_challenger = config.challenger();
+        let mut empty_iv_challenger = config.challenger_with_iv(&[]);
+        assert_eq!(
+            CanSample::<F>::sample(&mut default_challenger),
+            CanSample::<F>::sample(&mut empty_iv_challenger)
+        );
+    }
+
     #[test]
     fn test_challenger_outer() {
         type SC = BabyBearPoseidon2Outer;
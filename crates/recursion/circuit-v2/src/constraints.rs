@@ -47,9 +47,6 @@ where
     ) {
         let sels = trace_domain.selectors_at_point_variable(builder, zeta);
 
-        // Recompute the quotient at zeta from the chunks.
-        let quotient = Self::recompute_quotient(builder, opening, &qc_domains, zeta);
-
         // Calculate the evaluations of the constraints at zeta.
         let folded_constraints = Self::eval_constraints(
             builder,
@@ -61,8 +58,32 @@ where
             public_values,
         );
 
+        Self::check_quotient_consistency(
+            builder,
+            opening,
+            folded_constraints,
+            &sels,
+            &qc_domains,
+            zeta,
+        );
+    }
+
+    /// Asserts that the quotient recombined from the chunks opened at `zeta` is the folded
+    /// constraint evaluation at `zeta` divided by the vanishing polynomial of the trace domain,
+    /// whose inverse is given by `selectors`.
+    pub fn check_quotient_consistency(
+        builder: &mut Builder<C>,
+        opening: &ChipOpenedValues<Ext<C::F, C::EF>>,
+        constraint_eval: Ext<C::F, C::EF>,
+        selectors: &LagrangeSelectors<Ext<C::F, C::EF>>,
+        qc_domains: &[TwoAdicMultiplicativeCoset<C::F>],
+        zeta: Ext<C::F, C::EF>,
+    ) {
+        // Recompute the quotient at zeta from the chunks.
+        let quotient = Self::recompute_quotient(builder, opening, qc_domains, zeta);
+
         // Assert that the quotient times the zerofier is equal to the folded constraints.
-        builder.assert_ext_eq(folded_constraints * sels.inv_zeroifier, quotient);
+        builder.assert_ext_eq(constraint_eval * selectors.inv_zeroifier, quotient);
     }
 
     pub fn eval_constraints(
@@ -12,11 +12,34 @@ use std::{
 };
 
 use crate::{
-    challenger::{CanSampleBitsVariable, FieldChallengerVariable},
+    challenger::{
+        CanObserveTagVariable, CanSampleBitsVariable, DomainSeparationTags, FieldChallengerVariable,
+    },
     BabyBearFriConfigVariable, CanObserveVariable, CircuitConfig, Ext, FriChallenges, FriMmcs,
     FriProofVariable, FriQueryProofVariable, TwoAdicPcsProofVariable, TwoAdicPcsRoundVariable,
 };
 
+/// The log-degree of the final FRI polynomial, which is folded all the way down to a constant.
+pub const FRI_FINAL_POLY_LOG_DEGREE: usize = 0;
+
+/// The log of the FRI folding factor: every commit-phase round halves the degree.
+pub const FRI_LOG_FOLDING_FACTOR: usize = 1;
+
+/// Derives the expected shape of a FRI proof from the FRI configuration.
+pub trait FriConfigShape {
+    /// The number of commit-phase rounds expected for a polynomial of degree `2^log_max_degree`.
+    fn num_commit_rounds(&self, log_max_degree: usize) -> usize;
+}
+
+impl<M> FriConfigShape for FriConfig<M> {
+    fn num_commit_rounds(&self, log_max_degree: usize) -> usize {
+        log_max_degree
+            .checked_sub(FRI_FINAL_POLY_LOG_DEGREE)
+            .expect("the polynomial must not be of lower degree than the final polynomial")
+            .div_ceil(FRI_LOG_FOLDING_FACTOR)
+    }
+}
+
 pub fn verify_shape_and_sample_challenges<
     C: CircuitConfig<F = BabyBear>,
     SC: BabyBearFriConfigVariable<C>,
@@ -41,10 +64,10 @@ pub fn verify_shape_and_sample_challenges<
         challenger.observe(builder, *felt);
     });
 
-    assert_eq!(proof.query_proofs.len(), config.num_queries);
+    assert_eq!(proof.num_queries(), config.num_queries);
     challenger.check_witness(builder, config.proof_of_work_bits, proof.pow_witness);
 
-    let log_max_height = proof.commit_phase_commits.len() + config.log_blowup;
+    let log_max_height = proof.num_commit_phases() + config.log_blowup;
     let query_indices: Vec<Vec<C::Bit>> =
         repeat_with(|| challenger.sample_bits(builder, log_max_height))
             .take(config.num_queries)
@@ -59,13 +82,20 @@ pub fn verify_two_adic_pcs<C: CircuitConfig<F = SC::Val>, SC: BabyBearFriConfigV
     proof: &TwoAdicPcsProofVariable<C, SC>,
     challenger: &mut SC::FriChallengerVariable,
     rounds: Vec<TwoAdicPcsRoundVariable<C, SC>>,
+    tags: DomainSeparationTags,
 ) {
+    if let Err(err) = proof.validate_shape(config, &rounds) {
+        panic!("the PCS proof does not have the expected shape: {err:?}");
+    }
+
+    challenger.observe_optional_tag(builder, tags.commit_phase);
     let alpha = challenger.sample_ext(builder);
 
     let fri_challenges =
         verify_shape_and_sample_challenges::<C, SC>(builder, config, &proof.fri_proof, challenger);
+    fri_challenges.assert_betas_shape(proof.fri_proof.num_commit_phases());
 
-    let log_global_max_height = proof.fri_proof.commit_phase_commits.len() + config.log_blowup;
+    let log_global_max_height = proof.fri_proof.num_commit_phases() + config.log_blowup;
 
     // The powers of alpha, where the ith element is alpha^i.
     let mut alpha_pows: Vec<Ext<C::F, C::EF>> =
@@ -82,17 +112,18 @@ pub fn verify_two_adic_pcs<C: CircuitConfig<F = SC::Val>, SC: BabyBearFriConfigV
                 [builder.eval(SymbolicExt::from_f(C::EF::zero())); 32];
 
             for (batch_opening, round) in zip(query_opening, rounds.iter().cloned()) {
+                let log_batch_max_height = round.log_max_height(config.log_blowup);
+
                 let batch_commit = round.batch_commit;
                 let mats = round.domains_points_and_opens;
                 let batch_heights =
                     mats.iter().map(|mat| mat.domain.size() << config.log_blowup).collect_vec();
-                let batch_dims = batch_heights
+                let batch_dims = mats
                     .iter()
-                    .map(|&height| Dimensions { width: 0, height })
+                    .zip(&batch_heights)
+                    .map(|(mat, &height)| Dimensions { width: mat.dims.width, height })
                     .collect_vec();
 
-                let batch_max_height = batch_heights.iter().max().expect("Empty batch?");
-                let log_batch_max_height = log2_strict_usize(*batch_max_height);
                 let bits_reduced = log_global_max_height - log_batch_max_height;
 
                 let reduced_index_bits = index_bits[bits_reduced..].to_vec();
@@ -163,21 +194,41 @@ pub fn verify_challenges<C: CircuitConfig<F = SC::Val>, SC: BabyBearFriConfigVar
     challenges: &FriChallenges<C>,
     reduced_openings: Vec<[Ext<C::F, C::EF>; 32]>,
 ) {
-    let log_max_height = proof.commit_phase_commits.len() + config.log_blowup;
-    for ((index_bits, query_proof), ro) in
-        challenges.query_indices.iter().zip(&proof.query_proofs).zip(reduced_openings)
-    {
-        let folded_eval = verify_query::<C, SC>(
-            builder,
-            proof.commit_phase_commits.clone(),
-            index_bits,
-            query_proof.clone(),
-            challenges.betas.clone(),
-            ro,
-            log_max_height,
-        );
+    let log_max_height = proof.num_commit_phases() + config.log_blowup;
+    for query_proof in &proof.query_proofs {
+        query_proof.assert_folds_to_final_poly(proof.num_commit_phases());
+    }
+    let folded_evals = challenges
+        .query_indices
+        .iter()
+        .zip(&proof.query_proofs)
+        .zip(reduced_openings)
+        .map(|((index_bits, query_proof), ro)| {
+            verify_query::<C, SC>(
+                builder,
+                proof.commit_phase_commits.clone(),
+                index_bits,
+                query_proof.clone(),
+                challenges.betas.clone(),
+                ro,
+                log_max_height,
+            )
+        })
+        .collect::<Vec<_>>();
+    check_final_poly(builder, proof.final_poly, &folded_evals);
+}
 
-        builder.assert_ext_eq(folded_eval, proof.final_poly);
+/// Asserts that every query folds to the constant `final_poly`.
+///
+/// Each query folds its opening down to a single value, and the committed polynomial is only of
+/// low degree if all of them agree with the final polynomial sent by the prover.
+pub fn check_final_poly<C: CircuitConfig>(
+    builder: &mut Builder<C>,
+    final_poly: Ext<C::F, C::EF>,
+    folded_evals: &[Ext<C::F, C::EF>],
+) {
+    for folded_eval in folded_evals {
+        builder.assert_ext_eq(*folded_eval, final_poly);
     }
 }
 
@@ -296,13 +347,16 @@ pub fn verify_batch<C: CircuitConfig<F = SC::Val>, SC: BabyBearFriConfigVariable
 mod tests {
     use super::*;
     use crate::{
-        challenger::DuplexChallengerVariable, utils::tests::run_test_recursion,
-        BatchOpeningVariable, FriCommitPhaseProofStepVariable, FriProofVariable,
-        FriQueryProofVariable, TwoAdicPcsMatsVariable, TwoAdicPcsProofVariable,
+        challenger::DuplexChallengerVariable,
+        hash::FieldHasherVariable,
+        utils::{assert_canonical_bits, tests::run_test_recursion},
+        BatchOpeningShapeError, BatchOpeningVariable, FriCommitPhaseProofStepVariable,
+        FriProofShapeError, FriProofVariable, FriQueryProofVariable, PcsProofShapeError,
+        TwoAdicPcsMatsVariable, TwoAdicPcsProofVariable,
     };
     use p3_challenger::{CanObserve, CanSample, FieldChallenger};
     use p3_commit::{Pcs, TwoAdicMultiplicativeCoset};
-    use p3_field::AbstractField;
+    use p3_field::{AbstractField, PrimeField32};
     use p3_fri::{verifier, TwoAdicFriPcsProof};
     use p3_matrix::dense::RowMajorMatrix;
     use rand::{
@@ -430,7 +484,9 @@ mod tests {
                         .collect::<Vec<_>>()
                 })
                 .collect::<Vec<_>>();
-            let domain_points_and_values = TwoAdicPcsMatsVariable { domain, points, values };
+            let dims = Dimensions { width: values[0].len(), height: domain.size() };
+            let domain_points_and_values =
+                TwoAdicPcsMatsVariable::new(domain, points, values, dims).unwrap();
             domains_points_and_opens.push(domain_points_and_values);
         }
 
@@ -484,6 +540,399 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_num_commit_rounds() {
+        let config = inner_fri_config();
+        assert_eq!(config.num_commit_rounds(0), 0);
+        assert_eq!(config.num_commit_rounds(1), 1);
+        assert_eq!(config.num_commit_rounds(22), 22);
+
+        // The prover folds the tallest matrix all the way down to the final constant.
+        let perm = inner_perm();
+        let hash = InnerHash::new(perm.clone());
+        let compress = InnerCompress::new(perm.clone());
+        let val_mmcs = InnerValMmcs::new(hash, compress);
+        for log_degrees in [&[10, 7, 3][..], &[5, 5], &[1]] {
+            let log_max_degree = log_degrees.iter().copied().max().unwrap();
+            let pcs: InnerPcs =
+                InnerPcs::new(log_max_degree, InnerDft {}, val_mmcs.clone(), inner_fri_config());
+            let domains_and_polys = log_degrees
+                .iter()
+                .map(|&d| {
+                    (
+                        <InnerPcs as Pcs<InnerChallenge, InnerChallenger>>::natural_domain_for_degree(
+                            &pcs,
+                            1 << d,
+                        ),
+                        RowMajorMatrix::<InnerVal>::rand(&mut OsRng, 1 << d, 2),
+                    )
+                })
+                .collect::<Vec<_>>();
+            let (commit, data) =
+                <InnerPcs as Pcs<InnerChallenge, InnerChallenger>>::commit(&pcs, domains_and_polys);
+            let mut challenger = InnerChallenger::new(perm.clone());
+            challenger.observe(commit);
+            let zeta = challenger.sample_ext_element::<InnerChallenge>();
+            let points = vec![vec![zeta]; log_degrees.len()];
+            let (_, proof) = pcs.open(vec![(&data, points)], &mut challenger);
+            assert_eq!(
+                proof.fri_proof.commit_phase_commits.len(),
+                config.num_commit_rounds(log_max_degree)
+            );
+        }
+    }
+
+    #[test]
+    fn test_num_commitments() {
+        let mut builder = AsmBuilder::<F, EF>::default();
+        let felt: Felt<_> = builder.eval(F::one());
+        let ext: Ext<_, _> = builder.eval(SymbolicExt::from_f(EF::one()));
+        let digest = [felt; DIGEST_SIZE];
+        let batch_opening = BatchOpeningVariable::<C, SC> {
+            opened_values: vec![vec![vec![felt]]],
+            opening_proof: vec![digest; 4],
+        };
+        let query_proof = FriQueryProofVariable::<C, SC> { commit_phase_openings: vec![] };
+
+        // Four batches (preprocessed, main, permutation, quotient) and three FRI rounds.
+        let proof = TwoAdicPcsProofVariable::<C, SC> {
+            fri_proof: FriProofVariable {
+                commit_phase_commits: vec![digest; 3],
+                query_proofs: vec![query_proof; 2],
+                final_poly: ext,
+                pow_witness: felt,
+            },
+            query_openings: vec![vec![batch_opening; 4]; 2],
+        };
+        assert_eq!(proof.num_commitments(), 7);
+    }
+
+    #[test]
+    fn test_assert_betas_shape() {
+        let mut builder = AsmBuilder::<F, EF>::default();
+        let beta: Ext<_, _> = builder.eval(SymbolicExt::from_f(EF::one()));
+        let challenges = FriChallenges::<C> { query_indices: vec![], betas: vec![beta; 3] };
+        challenges.assert_betas_shape(3);
+    }
+
+    #[test]
+    #[should_panic(expected = "one beta is expected per commit-phase round")]
+    fn test_assert_betas_shape_mismatch() {
+        let mut builder = AsmBuilder::<F, EF>::default();
+        let beta: Ext<_, _> = builder.eval(SymbolicExt::from_f(EF::one()));
+        let challenges = FriChallenges::<C> { query_indices: vec![], betas: vec![beta; 2] };
+        challenges.assert_betas_shape(3);
+    }
+
+    #[test]
+    #[should_panic(expected = "one opening is expected per commit-phase round")]
+    fn test_query_proof_stops_folding_early() {
+        let mut builder = AsmBuilder::<F, EF>::default();
+        let felt = builder.eval(F::one());
+        let sibling_value: Ext<_, _> = builder.eval(SymbolicExt::from_f(EF::one()));
+        let step = FriCommitPhaseProofStepVariable::<C, SC> {
+            sibling_value,
+            opening_proof: vec![[felt; DIGEST_SIZE]; 3],
+        };
+        let query_proof =
+            FriQueryProofVariable::<C, SC> { commit_phase_openings: vec![step.clone(); 3] };
+        query_proof.assert_folds_to_final_poly(3);
+
+        // Dropping the last round leaves a polynomial of degree two unfolded.
+        let truncated = FriQueryProofVariable::<C, SC> { commit_phase_openings: vec![step; 2] };
+        truncated.assert_folds_to_final_poly(3);
+    }
+
+    #[test]
+    fn test_check_final_poly() {
+        let mut builder = AsmBuilder::<F, EF>::default();
+        let final_poly: Ext<F, EF> = builder.eval(SymbolicExt::from_f(EF::from_canonical_u32(7)));
+        let folded_evals: [Ext<F, EF>; 3] =
+            [7, 7, 7].map(|value| builder.eval(SymbolicExt::from_f(EF::from_canonical_u32(value))));
+        check_final_poly(&mut builder, final_poly, &folded_evals);
+
+        run_test_recursion(builder.operations, None);
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_check_final_poly_rejects_diverging_query() {
+        let mut builder = AsmBuilder::<F, EF>::default();
+        let final_poly: Ext<F, EF> = builder.eval(SymbolicExt::from_f(EF::from_canonical_u32(7)));
+        let folded_evals: [Ext<F, EF>; 3] =
+            [7, 8, 7].map(|value| builder.eval(SymbolicExt::from_f(EF::from_canonical_u32(value))));
+        check_final_poly(&mut builder, final_poly, &folded_evals);
+
+        run_test_recursion(builder.operations, None);
+    }
+
+    #[test]
+    fn test_range_check_openings() {
+        let mut builder = AsmBuilder::<F, EF>::default();
+        let values =
+            [0, 1, F::ORDER_U32 - 1].map(|value| builder.eval(F::from_canonical_u32(value)));
+        let opening = BatchOpeningVariable::<C, SC> {
+            opened_values: vec![vec![values.to_vec()]],
+            opening_proof: vec![],
+        };
+        opening.range_check_openings(&mut builder);
+
+        run_test_recursion(builder.operations, None);
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_non_canonical_bits_rejected() {
+        // The bits of `p` itself are a non-canonical encoding of zero.
+        let mut builder = AsmBuilder::<F, EF>::default();
+        let bits = (0..32)
+            .map(|i| builder.eval(F::from_canonical_u32((F::ORDER_U32 >> i) & 1)))
+            .collect::<Vec<Felt<_>>>();
+        assert_canonical_bits::<C>(&mut builder, &bits);
+
+        run_test_recursion(builder.operations, None);
+    }
+
+    #[test]
+    fn test_flatten_opened_values_is_leaf() {
+        let mut builder = Builder::<C>::default();
+        let values =
+            (0..12).map(|i| builder.eval(F::from_canonical_u32(i))).collect::<Vec<Felt<_>>>();
+        // Three matrices of the same height, each opened at two extension-sized chunks.
+        let opened_values = values
+            .chunks(4)
+            .map(|mat| mat.chunks(2).map(<[_]>::to_vec).collect())
+            .collect::<Vec<Vec<_>>>();
+        let opening = BatchOpeningVariable::<C, SC> {
+            opened_values: opened_values.clone(),
+            opening_proof: vec![],
+        };
+        let flattened = opening.flatten_opened_values();
+        assert_eq!(flattened, values);
+
+        // A single leaf is its own Merkle root, so the batch verifies against its hash.
+        let commit = SC::hash(&mut builder, &flattened);
+        let dimensions = vec![Dimensions { width: 4, height: 1 }; 3];
+        verify_batch::<_, SC>(&mut builder, commit, dimensions, vec![], opened_values, vec![]);
+
+        run_test_recursion(builder.operations, None);
+    }
+
+    #[test]
+    fn test_batch_opening_verify_shape() {
+        let mut builder = AsmBuilder::<F, EF>::default();
+        let felt = builder.eval(F::one());
+        let digest = [felt; DIGEST_SIZE];
+        let opening =
+            |opened_values: Vec<Vec<Vec<Felt<F>>>>, depth: usize| BatchOpeningVariable::<C, SC> {
+                opened_values,
+                opening_proof: vec![digest; depth],
+            };
+
+        assert_eq!(opening(vec![vec![vec![felt]]], 3).verify_shape(3), Ok(()));
+        assert_eq!(
+            opening(vec![], 3).verify_shape(3),
+            Err(BatchOpeningShapeError::EmptyOpenedValues)
+        );
+        assert_eq!(
+            opening(vec![vec![vec![felt]]], 0).verify_shape(3),
+            Err(BatchOpeningShapeError::EmptyOpeningProof)
+        );
+        assert_eq!(
+            opening(vec![vec![vec![felt]]], 2).verify_shape(3),
+            Err(BatchOpeningShapeError::OpeningProofLengthMismatch(3, 2))
+        );
+    }
+
+    /// Commits to random matrices of heights `2^log_degrees` and opens them at a random point.
+    fn random_pcs_proof(
+        builder: &mut Builder<C>,
+        seed: u64,
+        log_degrees: &[usize],
+    ) -> TwoAdicPcsProofVariable<C, SC> {
+        let mut rng = StdRng::seed_from_u64(seed);
+        let perm = inner_perm();
+        let hash = InnerHash::new(perm.clone());
+        let compress = InnerCompress::new(perm.clone());
+        let val_mmcs = InnerValMmcs::new(hash, compress);
+        let pcs: InnerPcs = InnerPcs::new(
+            log_degrees.iter().copied().max().unwrap(),
+            InnerDft {},
+            val_mmcs,
+            inner_fri_config(),
+        );
+
+        let domains_and_polys = log_degrees
+            .iter()
+            .map(|&d| {
+                (
+                    <InnerPcs as Pcs<InnerChallenge, InnerChallenger>>::natural_domain_for_degree(
+                        &pcs,
+                        1 << d,
+                    ),
+                    RowMajorMatrix::<InnerVal>::rand(&mut rng, 1 << d, 4),
+                )
+            })
+            .collect::<Vec<_>>();
+        let (commit, data) =
+            <InnerPcs as Pcs<InnerChallenge, InnerChallenger>>::commit(&pcs, domains_and_polys);
+        let mut challenger = InnerChallenger::new(perm);
+        challenger.observe(commit);
+        let zeta = challenger.sample_ext_element::<InnerChallenge>();
+        let points = log_degrees.iter().map(|_| vec![zeta]).collect::<Vec<_>>();
+        let (_, proof) = pcs.open(vec![(&data, points)], &mut challenger);
+        const_two_adic_pcs_proof(builder, proof)
+    }
+
+    #[test]
+    fn test_pcs_proof_same_shape() {
+        let mut builder = Builder::<InnerConfig>::default();
+        let proof = random_pcs_proof(&mut builder, 1, &[10, 8]);
+        let reproof = random_pcs_proof(&mut builder, 2, &[10, 8]);
+        assert!(proof.same_shape(&reproof));
+        assert!(reproof.same_shape(&proof));
+
+        // Taller matrices deepen the Merkle paths and add FRI rounds.
+        let taller = random_pcs_proof(&mut builder, 1, &[11, 8]);
+        assert!(!proof.same_shape(&taller));
+        // An extra matrix only changes the nesting of the opened values.
+        let wider = random_pcs_proof(&mut builder, 1, &[10, 8, 8]);
+        assert!(!proof.same_shape(&wider));
+
+        let mut truncated = reproof.clone();
+        truncated.query_openings[0][0].opened_values[1][0].pop();
+        assert!(!proof.same_shape(&truncated));
+        let mut fewer_queries = reproof;
+        fewer_queries.fri_proof.query_proofs.pop();
+        assert!(!proof.same_shape(&fewer_queries));
+    }
+
+    #[test]
+    fn test_batch_opening_verify_round_shape() {
+        let mut rng = StdRng::seed_from_u64(0xDEADBEEF);
+        let log_degrees = &[10, 8];
+        let perm = inner_perm();
+        let fri_config = inner_fri_config();
+        let log_blowup = fri_config.log_blowup;
+        let hash = InnerHash::new(perm.clone());
+        let compress = InnerCompress::new(perm.clone());
+        let val_mmcs = InnerValMmcs::new(hash, compress);
+        let pcs: InnerPcs = InnerPcs::new(10, InnerDft {}, val_mmcs, fri_config);
+
+        let domains_and_polys = log_degrees
+            .iter()
+            .map(|&d| {
+                (
+                    <InnerPcs as Pcs<InnerChallenge, InnerChallenger>>::natural_domain_for_degree(
+                        &pcs,
+                        1 << d,
+                    ),
+                    RowMajorMatrix::<InnerVal>::rand(&mut rng, 1 << d, 4),
+                )
+            })
+            .collect::<Vec<_>>();
+        let (commit, data) = <InnerPcs as Pcs<InnerChallenge, InnerChallenger>>::commit(
+            &pcs,
+            domains_and_polys.clone(),
+        );
+        let mut challenger = InnerChallenger::new(perm.clone());
+        challenger.observe(commit);
+        let zeta = challenger.sample_ext_element::<InnerChallenge>();
+        let points = domains_and_polys.iter().map(|_| vec![zeta]).collect::<Vec<_>>();
+        let (opening, proof) = pcs.open(vec![(&data, points)], &mut challenger);
+        let os = domains_and_polys
+            .iter()
+            .zip(&opening[0])
+            .map(|((domain, _), mat_openings)| (*domain, vec![(zeta, mat_openings[0].clone())]))
+            .collect::<Vec<_>>();
+
+        let mut builder = Builder::<InnerConfig>::default();
+        let proof = const_two_adic_pcs_proof(&mut builder, proof);
+        let (_, rounds) = const_two_adic_pcs_rounds(&mut builder, commit.into(), os);
+        let round = &rounds[0];
+
+        // The Merkle tree is as deep as the tallest matrix, not the shorter one.
+        assert_eq!(round.log_max_height(log_blowup), 10 + log_blowup);
+        let batch_opening = &proof.query_openings[0][0];
+        assert_eq!(batch_opening.verify_round_shape(round, log_blowup), Ok(()));
+
+        let mut shallow = batch_opening.clone();
+        shallow.opening_proof.pop();
+        assert_eq!(
+            shallow.verify_round_shape(round, log_blowup),
+            Err(BatchOpeningShapeError::OpeningProofLengthMismatch(
+                10 + log_blowup,
+                9 + log_blowup
+            ))
+        );
+
+        let mut deep = batch_opening.clone();
+        deep.opening_proof.push(deep.opening_proof[0]);
+        assert_eq!(
+            deep.verify_round_shape(round, log_blowup),
+            Err(BatchOpeningShapeError::OpeningProofLengthMismatch(
+                10 + log_blowup,
+                11 + log_blowup
+            ))
+        );
+
+        let mut missing_matrix = batch_opening.clone();
+        missing_matrix.opened_values.pop();
+        assert_eq!(
+            missing_matrix.verify_round_shape(round, log_blowup),
+            Err(BatchOpeningShapeError::MatricesMismatch(2, 1))
+        );
+
+        let mut narrow = batch_opening.clone();
+        narrow.opened_values[1].pop();
+        assert_eq!(
+            narrow.verify_round_shape(round, log_blowup),
+            Err(BatchOpeningShapeError::WidthMismatch { matrix: 1, expected: 4, found: 3 })
+        );
+
+        // The whole proof is checked against the configuration it was produced with.
+        let fri_config = inner_fri_config();
+        assert_eq!(proof.validate_shape(&fri_config, &rounds), Ok(()));
+
+        let mut fewer_rounds = proof.clone();
+        fewer_rounds.fri_proof.commit_phase_commits.pop();
+        assert_eq!(
+            fewer_rounds.validate_shape(&fri_config, &rounds),
+            Err(PcsProofShapeError::Fri(FriProofShapeError::CommitPhaseCommitsMismatch(10, 9)))
+        );
+
+        let mut fewer_openings = proof.clone();
+        fewer_openings.query_openings.pop();
+        assert_eq!(
+            fewer_openings.validate_shape(&fri_config, &rounds),
+            Err(PcsProofShapeError::QueryOpeningsMismatch(
+                fri_config.num_queries,
+                fri_config.num_queries - 1
+            ))
+        );
+
+        let mut extra_batch = proof.clone();
+        extra_batch.query_openings[2].push(batch_opening.clone());
+        assert_eq!(
+            extra_batch.validate_shape(&fri_config, &rounds),
+            Err(PcsProofShapeError::BatchesMismatch { query: 2, expected: 1, found: 2 })
+        );
+
+        let mut shallow_query = proof;
+        shallow_query.query_openings[1][0].opening_proof.pop();
+        assert_eq!(
+            shallow_query.validate_shape(&fri_config, &rounds),
+            Err(PcsProofShapeError::BatchOpening {
+                query: 1,
+                round: 0,
+                error: BatchOpeningShapeError::OpeningProofLengthMismatch(
+                    10 + log_blowup,
+                    9 + log_blowup
+                ),
+            })
+        );
+    }
+
     #[test]
     fn test_fri_verify_shape_and_sample_challenges() {
         let mut rng = &mut OsRng;
@@ -630,6 +1079,7 @@ mod tests {
             &proof,
             &mut challenger,
             rounds,
+            DomainSeparationTags::default(),
         );
 
         run_test_recursion(builder.operations, std::iter::empty());
@@ -24,6 +24,8 @@ pub trait FieldHasherVariable<C: CircuitConfig> {
 
     fn compress(builder: &mut Builder<C>, input: [Self::Digest; 2]) -> Self::Digest;
 
+    /// Asserts that `a` and `b` are equal in every element of the digest. Commitments should be
+    /// compared through this rather than element by element at the call site.
     fn assert_digest_eq(builder: &mut Builder<C>, a: Self::Digest, b: Self::Digest);
 
     // Encountered many issues trying to make the following two parametrically polymorphic.
@@ -124,3 +126,44 @@ impl<C: CircuitConfig<F = BabyBear, N = Bn254Fr, Bit = Var<Bn254Fr>>> FieldHashe
 // impl<C: Config<F = BabyBear>> FieldHasherVariable<C> for OuterHash {
 
 // }
+
+#[cfg(test)]
+mod tests {
+    use p3_baby_bear::BabyBear;
+    use p3_field::AbstractField;
+    use sp1_recursion_compiler::{
+        config::InnerConfig,
+        ir::{Builder, Felt},
+    };
+    use sp1_recursion_core_v2::DIGEST_SIZE;
+    use sp1_stark::baby_bear_poseidon2::BabyBearPoseidon2;
+
+    use super::FieldHasherVariable;
+    use crate::utils::tests::run_test_recursion;
+
+    /// Asserts that the digest of `[0, 1, 2]` equals that digest with `delta` added to its last
+    /// element.
+    fn digest_eq_program(delta: u32) {
+        let mut builder = Builder::<InnerConfig>::default();
+        let input =
+            (0..3).map(|i| builder.eval(BabyBear::from_canonical_u32(i))).collect::<Vec<_>>();
+        let digest = BabyBearPoseidon2::hash(&mut builder, &input);
+        let mut other: [Felt<_>; DIGEST_SIZE] = BabyBearPoseidon2::hash(&mut builder, &input);
+        other[DIGEST_SIZE - 1] =
+            builder.eval(other[DIGEST_SIZE - 1] + BabyBear::from_canonical_u32(delta));
+        BabyBearPoseidon2::assert_digest_eq(&mut builder, digest, other);
+
+        run_test_recursion(builder.operations, None);
+    }
+
+    #[test]
+    fn test_assert_digest_eq() {
+        digest_eq_program(0);
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_assert_digest_eq_last_element_differs() {
+        digest_eq_program(1);
+    }
+}
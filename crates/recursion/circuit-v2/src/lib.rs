@@ -97,6 +97,21 @@ pub trait BabyBearFriConfigVariable<C: CircuitConfig<F = BabyBear>>:
 
     /// Get a new challenger corresponding to the given config.
     fn challenger_variable(&self, builder: &mut Builder<C>) -> Self::FriChallengerVariable;
+
+    /// Get a new challenger that has absorbed `iv`, matching
+    /// [StarkGenericConfig::challenger_with_iv].
+    fn challenger_variable_with_iv(
+        &self,
+        builder: &mut Builder<C>,
+        iv: &[BabyBear],
+    ) -> Self::FriChallengerVariable {
+        let mut challenger = self.challenger_variable(builder);
+        for &value in iv {
+            let value: Felt<_> = builder.eval(value);
+            challenger.observe(builder, value);
+        }
+        challenger
+    }
 }
 
 pub trait CircuitConfig: Config {
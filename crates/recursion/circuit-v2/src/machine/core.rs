@@ -2,6 +2,7 @@ use std::{
     array,
     borrow::{Borrow, BorrowMut},
     marker::PhantomData,
+    ops::{Deref, DerefMut},
 };
 
 use itertools::Itertools;
@@ -29,11 +30,11 @@ use sp1_recursion_compiler::{
 
 use sp1_recursion_core_v2::{
     air::{RecursionPublicValues, RECURSIVE_PROOF_NUM_PV_ELTS},
-    DIGEST_SIZE,
+    DIGEST_SIZE, NUM_BITS,
 };
 
 use crate::{
-    challenger::{CanObserveVariable, DuplexChallengerVariable},
+    challenger::{CanObserveVariable, DomainSeparationTags, DuplexChallengerVariable},
     stark::{ShardProofVariable, StarkVerifier},
     VerifyingKeyVariable,
 };
@@ -49,6 +50,252 @@ pub struct SP1RecursionWitnessVariable<
     pub is_complete: Felt<C::F>,
 }
 
+/// The program counter that the `HALT` syscall sets as the next program counter.
+pub const HALT_PC: u32 = 0;
+
+/// The number of low bits of the exit code that may be set, so that exit codes lie in `0..256`.
+pub const EXIT_CODE_NUM_BITS: usize = 8;
+
+/// The public values of an SP1 shard proof, with `N` words in the committed value digest.
+pub struct PublicValuesVariable<C: Config, const N: usize = PV_DIGEST_NUM_WORDS> {
+    /// The public values committed by the proof.
+    pub values: PublicValues<Word<Felt<C::F>>, Felt<C::F>, N>,
+    /// A value the proof is bound to without committing to it, such as a block height or a nonce.
+    ///
+    /// If set, it is observed by the challenger before the verifying key, so the proof is only
+    /// accepted if the prover observed the same value into its challenger before proving.
+    pub context: Option<Felt<C::F>>,
+}
+
+impl<C: Config, const N: usize> PublicValuesVariable<C, N> {
+    /// Reads the public values laid out at the start of `public_values`, without a context.
+    pub fn from_slice(public_values: &[Felt<C::F>]) -> Self {
+        let values: &PublicValues<Word<Felt<C::F>>, Felt<C::F>, N> = public_values.borrow();
+        Self { values: *values, context: None }
+    }
+
+    /// Observes the context into `challenger`, if there is one.
+    pub fn observe_context(
+        &self,
+        builder: &mut Builder<C>,
+        challenger: &mut impl CanObserveVariable<C, Felt<C::F>>,
+    ) {
+        if let Some(context) = self.context {
+            challenger.observe(builder, context);
+        }
+    }
+}
+
+impl<C: Config, const N: usize> Clone for PublicValuesVariable<C, N> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<C: Config, const N: usize> Copy for PublicValuesVariable<C, N> {}
+
+impl<C: Config, const N: usize> Deref for PublicValuesVariable<C, N> {
+    type Target = PublicValues<Word<Felt<C::F>>, Felt<C::F>, N>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.values
+    }
+}
+
+impl<C: Config, const N: usize> DerefMut for PublicValuesVariable<C, N> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.values
+    }
+}
+
+/// Application-level checks on the public values of a shard proof.
+pub trait PublicValuesAssertions<C: CircuitConfig> {
+    /// The public values as constants, with the same committed value digest length.
+    type Constant;
+
+    /// Asserts that every felt of the committed value digest is a byte. This only range-checks the
+    /// digest; it says nothing about how the digest was computed.
+    fn assert_digest_bytes(&self, builder: &mut Builder<C>);
+
+    /// Asserts that the program exited with code zero.
+    fn assert_success(&self, builder: &mut Builder<C>);
+
+    /// Asserts that the exit code lies in `0..2^EXIT_CODE_NUM_BITS` rather than being an
+    /// arbitrary field element, by decomposing it into bits and checking the high bits are zero.
+    fn constrain_exit_code_range(&self, builder: &mut Builder<C>);
+
+    /// Asserts that `other` has the same committed value digest but a different shard number,
+    /// leaving every other field free.
+    ///
+    /// The digest is only finalized at the end of an execution, so consecutive shards of the
+    /// same execution share it while their shard numbers and program counters differ.
+    fn assert_same_digest(&self, builder: &mut Builder<C>, other: &Self);
+
+    /// Asserts that these are the public values of the last shard of an execution that halted,
+    /// rather than one truncated mid-program, by checking that `next_pc` is [`HALT_PC`].
+    fn assert_execution_complete(&self, builder: &mut Builder<C>);
+
+    /// Asserts that every felt of the public values, including each byte of the committed value
+    /// digest, equals the corresponding felt of `value`.
+    fn assert_eq_const(&self, builder: &mut Builder<C>, value: Self::Constant);
+}
+
+impl<C: CircuitConfig, const N: usize> PublicValuesAssertions<C> for PublicValuesVariable<C, N> {
+    type Constant = PublicValues<Word<C::F>, C::F, N>;
+
+    fn assert_digest_bytes(&self, builder: &mut Builder<C>) {
+        for byte in self.committed_value_digest.iter().flat_map(|word| word.0) {
+            assert_fits_in_bits(builder, byte, 8);
+        }
+    }
+
+    fn assert_success(&self, builder: &mut Builder<C>) {
+        builder.assert_felt_eq(self.exit_code, C::F::zero());
+    }
+
+    fn constrain_exit_code_range(&self, builder: &mut Builder<C>) {
+        assert_fits_in_bits(builder, self.exit_code, EXIT_CODE_NUM_BITS);
+    }
+
+    fn assert_same_digest(&self, builder: &mut Builder<C>, other: &Self) {
+        assert_committed_value_digest_eq(builder, self, other);
+        builder.assert_felt_ne(self.shard, other.shard);
+    }
+
+    fn assert_execution_complete(&self, builder: &mut Builder<C>) {
+        builder.assert_felt_eq(self.next_pc, C::F::from_canonical_u32(HALT_PC));
+    }
+
+    fn assert_eq_const(&self, builder: &mut Builder<C>, value: Self::Constant) {
+        for (felt, constant) in
+            public_values_elements(&self.values).into_iter().zip(public_values_elements(&value))
+        {
+            builder.assert_felt_eq(felt, constant);
+        }
+    }
+}
+
+/// Asserts that `value` lies in `0..2^num_bits`, by decomposing it into bits and checking the high
+/// bits are zero.
+fn assert_fits_in_bits<C: CircuitConfig>(
+    builder: &mut Builder<C>,
+    value: Felt<C::F>,
+    num_bits: usize,
+) {
+    let bits = C::num2bits(builder, value, NUM_BITS);
+    // Fewer than 31 high bits recompose to less than the modulus, so this is zero exactly when
+    // every high bit is.
+    let high = C::bits2num(builder, bits.into_iter().skip(num_bits));
+    builder.assert_felt_eq(high, C::F::zero());
+}
+
+/// Asserts that `a` and `b` have the same committed value digest.
+fn assert_committed_value_digest_eq<C: CircuitConfig, const N: usize>(
+    builder: &mut Builder<C>,
+    a: &PublicValuesVariable<C, N>,
+    b: &PublicValuesVariable<C, N>,
+) {
+    for (word, other_word) in a.committed_value_digest.iter().zip(b.committed_value_digest.iter()) {
+        for (byte, other_byte) in word.0.iter().zip(other_word.0.iter()) {
+            builder.assert_felt_eq(*byte, *other_byte);
+        }
+    }
+}
+
+/// The elements of the public values, in the order of their layout.
+fn public_values_elements<T: Copy, const N: usize>(values: &PublicValues<Word<T>, T, N>) -> Vec<T> {
+    let PublicValues {
+        committed_value_digest,
+        deferred_proofs_digest,
+        start_pc,
+        next_pc,
+        exit_code,
+        shard,
+        execution_shard,
+        previous_init_addr_bits,
+        last_init_addr_bits,
+        previous_finalize_addr_bits,
+        last_finalize_addr_bits,
+    } = *values;
+    committed_value_digest
+        .iter()
+        .flat_map(|word| word.0)
+        .chain(deferred_proofs_digest)
+        .chain([start_pc, next_pc, exit_code, shard, execution_shard])
+        .chain(previous_init_addr_bits)
+        .chain(last_init_addr_bits)
+        .chain(previous_finalize_addr_bits)
+        .chain(last_finalize_addr_bits)
+        .collect()
+}
+
+/// Asserts that `node` is a consistent aggregation of the contiguous run of `children`, in order.
+///
+/// Consecutive children must be consecutive shards that continue each other's execution: the
+/// program counter and the memory initialization and finalization addresses where one child stops
+/// are where the next one starts. The node starts where the first child starts and stops where the
+/// last one stops, and every child commits to the node's value and deferred proofs digests. Since
+/// the public values hold a single shard number, the node's shard is the first of its range.
+pub fn assert_tree_node_consistent<C: CircuitConfig, const N: usize>(
+    builder: &mut Builder<C>,
+    children: &[PublicValuesVariable<C, N>],
+    node: &PublicValuesVariable<C, N>,
+) {
+    let (first, last) = match children {
+        [first, .., last] => (first, last),
+        [only] => (only, only),
+        [] => panic!("an aggregation node must have at least one child"),
+    };
+
+    for child in children {
+        assert_committed_value_digest_eq(builder, node, child);
+        for (digest, child_digest) in
+            node.deferred_proofs_digest.iter().zip(child.deferred_proofs_digest.iter())
+        {
+            builder.assert_felt_eq(*digest, *child_digest);
+        }
+    }
+
+    for (previous, next) in children.iter().tuple_windows() {
+        builder.assert_felt_eq(previous.shard + C::F::one(), next.shard);
+        builder.assert_felt_eq(previous.next_pc, next.start_pc);
+        for (last_bit, previous_bit) in
+            previous.last_init_addr_bits.iter().zip(next.previous_init_addr_bits.iter())
+        {
+            builder.assert_felt_eq(*last_bit, *previous_bit);
+        }
+        for (last_bit, previous_bit) in
+            previous.last_finalize_addr_bits.iter().zip(next.previous_finalize_addr_bits.iter())
+        {
+            builder.assert_felt_eq(*last_bit, *previous_bit);
+        }
+    }
+
+    builder.assert_felt_eq(node.shard, first.shard);
+    builder.assert_felt_eq(node.execution_shard, first.execution_shard);
+    builder.assert_felt_eq(node.start_pc, first.start_pc);
+    builder.assert_felt_eq(node.next_pc, last.next_pc);
+    builder.assert_felt_eq(node.exit_code, last.exit_code);
+    for (bit, first_bit) in
+        node.previous_init_addr_bits.iter().zip(first.previous_init_addr_bits.iter())
+    {
+        builder.assert_felt_eq(*bit, *first_bit);
+    }
+    for (bit, first_bit) in
+        node.previous_finalize_addr_bits.iter().zip(first.previous_finalize_addr_bits.iter())
+    {
+        builder.assert_felt_eq(*bit, *first_bit);
+    }
+    for (bit, last_bit) in node.last_init_addr_bits.iter().zip(last.last_init_addr_bits.iter()) {
+        builder.assert_felt_eq(*bit, *last_bit);
+    }
+    for (bit, last_bit) in
+        node.last_finalize_addr_bits.iter().zip(last.last_finalize_addr_bits.iter())
+    {
+        builder.assert_felt_eq(*bit, *last_bit);
+    }
+}
+
 /// A program for recursively verifying a batch of SP1 proofs.
 #[derive(Debug, Clone, Copy)]
 pub struct SP1RecursiveVerifier<C: Config, SC: BabyBearFriConfig> {
@@ -233,6 +480,7 @@ where
                 machine,
                 &mut challenger,
                 &shard_proof,
+                DomainSeparationTags::default(),
             );
 
             // // First shard has a "CPU" constraint.
@@ -552,7 +800,216 @@ where
             //     assert_complete(builder, recursion_public_values, &reconstruct_challenger)
             // });
 
-            commit_recursion_public_values(builder, recursion_public_values);
+            commit_recursion_public_values(builder, recursion_public_values, None);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use p3_field::AbstractField;
+    use sp1_recursion_compiler::config::InnerConfig;
+    use sp1_stark::air::SP1_PROOF_NUM_PV_ELTS;
+
+    use super::*;
+    use crate::utils::tests::run_test_recursion;
+
+    fn digest_program(digest_byte: u32) {
+        let mut builder = Builder::<InnerConfig>::default();
+        let zero: Felt<_> = builder.eval(BabyBear::zero());
+        let mut public_values =
+            PublicValuesVariable::<InnerConfig>::from_slice(&[zero; SP1_PROOF_NUM_PV_ELTS]);
+        public_values.committed_value_digest[3].0[1] =
+            builder.eval(BabyBear::from_canonical_u32(digest_byte));
+        public_values.assert_digest_bytes(&mut builder);
+
+        run_test_recursion(builder.operations, None);
+    }
+
+    #[test]
+    fn test_digest_bytes() {
+        digest_program(255);
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_digest_byte_out_of_range() {
+        digest_program(256);
+    }
+
+    fn exit_code_program(exit_code: u32) {
+        let mut builder = Builder::<InnerConfig>::default();
+        let zero: Felt<_> = builder.eval(BabyBear::zero());
+        let mut public_values =
+            PublicValuesVariable::<InnerConfig>::from_slice(&[zero; SP1_PROOF_NUM_PV_ELTS]);
+        public_values.exit_code = builder.eval(BabyBear::from_canonical_u32(exit_code));
+        public_values.assert_success(&mut builder);
+
+        run_test_recursion(builder.operations, None);
+    }
+
+    #[test]
+    fn test_assert_success() {
+        exit_code_program(0);
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_assert_success_nonzero_exit_code() {
+        exit_code_program(1);
+    }
+
+    fn exit_code_range_program(exit_code: u32) {
+        let mut builder = Builder::<InnerConfig>::default();
+        let zero: Felt<_> = builder.eval(BabyBear::zero());
+        let mut public_values =
+            PublicValuesVariable::<InnerConfig>::from_slice(&[zero; SP1_PROOF_NUM_PV_ELTS]);
+        public_values.exit_code = builder.eval(BabyBear::from_canonical_u32(exit_code));
+        public_values.constrain_exit_code_range(&mut builder);
+
+        run_test_recursion(builder.operations, None);
+    }
+
+    #[test]
+    fn test_exit_code_in_range() {
+        exit_code_range_program(255);
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_exit_code_out_of_range() {
+        exit_code_range_program(256);
+    }
+
+    fn same_digest_program(other_digest_byte: u32, other_shard: u32) {
+        let mut builder = Builder::<InnerConfig>::default();
+        let zero: Felt<_> = builder.eval(BabyBear::zero());
+        let mut streams =
+            [PublicValuesVariable::<InnerConfig>::from_slice(&[zero; SP1_PROOF_NUM_PV_ELTS]); 2];
+        for (i, (public_values, (digest_byte, shard))) in
+            streams.iter_mut().zip([(7, 1), (other_digest_byte, other_shard)]).enumerate()
+        {
+            public_values.committed_value_digest[5].0[2] =
+                builder.eval(BabyBear::from_canonical_u32(digest_byte));
+            public_values.shard = builder.eval(BabyBear::from_canonical_u32(shard));
+            public_values.next_pc = builder.eval(BabyBear::from_canonical_usize(4 * i));
         }
+
+        streams[0].assert_same_digest(&mut builder, &streams[1]);
+
+        run_test_recursion(builder.operations, None);
+    }
+
+    #[test]
+    fn test_assert_same_digest_across_shards() {
+        same_digest_program(7, 2);
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_assert_same_digest_mismatch() {
+        same_digest_program(8, 2);
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_assert_same_digest_same_shard() {
+        same_digest_program(7, 1);
+    }
+
+    fn next_pc_program(next_pc: u32) {
+        let mut builder = Builder::<InnerConfig>::default();
+        let zero: Felt<_> = builder.eval(BabyBear::zero());
+        let mut public_values =
+            PublicValuesVariable::<InnerConfig>::from_slice(&[zero; SP1_PROOF_NUM_PV_ELTS]);
+        public_values.next_pc = builder.eval(BabyBear::from_canonical_u32(next_pc));
+        public_values.assert_execution_complete(&mut builder);
+
+        run_test_recursion(builder.operations, None);
+    }
+
+    #[test]
+    fn test_assert_execution_complete() {
+        next_pc_program(HALT_PC);
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_assert_execution_complete_truncated() {
+        next_pc_program(0x0020_1000);
+    }
+
+    fn eq_const_program(const_exit_code: u32) {
+        let mut builder = Builder::<InnerConfig>::default();
+        let mut value = PublicValues::<u32, u32>::default();
+        value.committed_value_digest[2] = 0x0403_0201;
+        value.shard = 3;
+        value.next_pc = 0x0020_1000;
+        value.exit_code = 1;
+        value.last_init_addr_bits[4] = 1;
+        let public_values_stream = value.to_vec::<BabyBear>()[..SP1_PROOF_NUM_PV_ELTS]
+            .iter()
+            .map(|element| builder.eval(*element))
+            .collect::<Vec<Felt<_>>>();
+        let public_values = PublicValuesVariable::<InnerConfig>::from_slice(&public_values_stream);
+
+        value.exit_code = const_exit_code;
+        public_values.assert_eq_const(&mut builder, PublicValues::from(value));
+
+        run_test_recursion(builder.operations, None);
+    }
+
+    #[test]
+    fn test_assert_eq_const() {
+        eq_const_program(1);
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_assert_eq_const_mismatched_exit_code() {
+        eq_const_program(0);
+    }
+
+    /// Aggregates three consecutive shards into a node, with the third child starting at
+    /// `third_shard` and the node stopping at `node_next_pc`.
+    fn tree_node_program(third_shard: u32, node_next_pc: u32) {
+        let mut builder = Builder::<InnerConfig>::default();
+        let zero: Felt<_> = builder.eval(BabyBear::zero());
+        let mut streams =
+            [PublicValuesVariable::<InnerConfig>::from_slice(&[zero; SP1_PROOF_NUM_PV_ELTS]); 4];
+        let children_shards = [1, 2, third_shard];
+        for (i, public_values) in streams.iter_mut().enumerate() {
+            public_values.committed_value_digest[0].0[0] =
+                builder.eval(BabyBear::from_canonical_u32(42));
+            let (shard, start_pc, next_pc) = match children_shards.get(i) {
+                Some(&shard) => (shard, 0x1000 * (i as u32 + 1), 0x1000 * (i as u32 + 2)),
+                None => (1, 0x1000, node_next_pc),
+            };
+            public_values.shard = builder.eval(BabyBear::from_canonical_u32(shard));
+            public_values.start_pc = builder.eval(BabyBear::from_canonical_u32(start_pc));
+            public_values.next_pc = builder.eval(BabyBear::from_canonical_u32(next_pc));
+        }
+
+        let (children, node) = streams.split_at(3);
+        assert_tree_node_consistent(&mut builder, children, &node[0]);
+
+        run_test_recursion(builder.operations, None);
+    }
+
+    #[test]
+    fn test_assert_tree_node_consistent() {
+        tree_node_program(3, 0x4000);
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_assert_tree_node_shard_gap() {
+        tree_node_program(4, 0x4000);
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_assert_tree_node_wrong_next_pc() {
+        tree_node_program(3, 0x3000);
     }
 }
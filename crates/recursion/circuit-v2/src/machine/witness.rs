@@ -1,4 +1,4 @@
-use std::borrow::Borrow;
+use std::{borrow::Borrow, marker::PhantomData};
 
 use p3_baby_bear::BabyBear;
 use p3_challenger::DuplexChallenger;
@@ -70,7 +70,13 @@ where
         let pc_start = self.pc_start.read(builder);
         let chip_information = self.chip_information.clone();
         let chip_ordering = self.chip_ordering.clone();
-        VerifyingKeyVariable { commitment, pc_start, chip_information, chip_ordering }
+        VerifyingKeyVariable {
+            commitment,
+            pc_start,
+            chip_information,
+            chip_ordering,
+            _phantom: PhantomData,
+        }
     }
 
     fn write(&self, witness: &mut impl WitnessWriter<C>) {
@@ -1,31 +1,34 @@
 use hashbrown::HashMap;
-use itertools::{izip, Itertools};
+use itertools::{chain, Itertools};
 use p3_commit::Mmcs;
 use p3_matrix::dense::RowMajorMatrix;
+use std::iter::once;
 
-use p3_air::Air;
+use p3_air::{Air, BaseAir};
 use p3_baby_bear::BabyBear;
 use p3_commit::{Pcs, TwoAdicMultiplicativeCoset};
-use p3_field::TwoAdicField;
-use sp1_stark::{ShardCommitment, ShardOpenedValues, Val};
+use p3_field::{AbstractExtensionField, AbstractField, TwoAdicField};
+use p3_matrix::Dimensions;
+use sp1_stark::{ChipOpenedValues, ShardCommitment, ShardOpenedValues, Val};
 
 use p3_commit::PolynomialSpace;
 
 use sp1_recursion_compiler::{
     circuit::CircuitV2Builder,
-    ir::{Builder, Config, Ext},
+    ir::{Builder, Config, Ext, SymbolicExt},
     prelude::Felt,
 };
 use sp1_stark::{air::MachineAir, StarkGenericConfig, StarkMachine, StarkVerifyingKey};
 
 use crate::{
-    challenger::CanObserveVariable, CircuitConfig, TwoAdicPcsMatsVariable, TwoAdicPcsProofVariable,
+    challenger::{CanCopyChallenger, CanObserveVariable, DomainSeparationTags},
+    CircuitConfig, TwoAdicPcsMatsVariable, TwoAdicPcsProofVariable,
 };
 
 use crate::{
     challenger::FieldChallengerVariable, constraints::RecursiveVerifierConstraintFolder,
-    domain::PolynomialSpaceVariable, fri::verify_two_adic_pcs, BabyBearFriConfigVariable,
-    TwoAdicPcsRoundVariable, VerifyingKeyVariable,
+    domain::PolynomialSpaceVariable, fri::verify_two_adic_pcs, machine::PublicValuesVariable,
+    BabyBearFriConfigVariable, TwoAdicPcsRoundVariable, VerifyingKeyVariable,
 };
 
 /// Reference: [sp1_core::stark::ShardProof]
@@ -40,6 +43,48 @@ pub struct ShardProofVariable<C: CircuitConfig<F = SC::Val>, SC: BabyBearFriConf
 
 pub const EMPTY: usize = 0x_1111_1111;
 
+/// An error that occurs when the chips of a shard proof are not accounted for by the verifier.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ChipSetError {
+    /// The proof contains a chip that is not part of the machine.
+    UnknownChip(String),
+    /// A preprocessed chip of the verifying key is missing from the proof.
+    MissingChip(String),
+    /// The chip ordering does not index every chip opening exactly once.
+    ChipOrderingMismatch(usize, usize),
+}
+
+/// Checks that the chips of a shard proof are exactly accounted for.
+///
+/// Every preprocessed chip of the verifying key must be present, since the preprocessed commitment
+/// batches all of their traces and so every shard opens each of them. Every chip in the proof must
+/// be a chip of the machine, and the chip ordering must be a permutation of the chip openings.
+pub fn verify_chip_set<'a>(
+    preprocessed_chips: impl IntoIterator<Item = &'a str>,
+    machine_chips: impl IntoIterator<Item = String>,
+    chip_ordering: &HashMap<String, usize>,
+    num_chip_openings: usize,
+) -> Result<(), ChipSetError> {
+    for name in preprocessed_chips {
+        if !chip_ordering.contains_key(name) {
+            return Err(ChipSetError::MissingChip(name.to_string()));
+        }
+    }
+
+    let machine_chips = machine_chips.into_iter().collect::<Vec<_>>();
+    if let Some(name) = chip_ordering.keys().find(|name| !machine_chips.contains(name)) {
+        return Err(ChipSetError::UnknownChip(name.clone()));
+    }
+
+    let mut indices = chip_ordering.values().copied().collect::<Vec<_>>();
+    indices.sort_unstable();
+    if indices.len() != num_chip_openings || indices.iter().enumerate().any(|(i, &j)| i != j) {
+        return Err(ChipSetError::ChipOrderingMismatch(num_chip_openings, chip_ordering.len()));
+    }
+
+    Ok(())
+}
+
 #[derive(Debug, Clone, Copy)]
 pub struct StarkVerifier<C: Config, SC: StarkGenericConfig, A> {
     _phantom: std::marker::PhantomData<(C, SC, A)>,
@@ -74,24 +119,197 @@ where
         )
     }
 
+    /// Asserts that the chips of the proof are exactly those of the verifying key and machine.
+    ///
+    /// The chips of a proof fix the shape of the circuit, so they are checked when it is built. A
+    /// mismatch is emitted as an unsatisfiable constraint rather than a panic, so a malformed proof
+    /// is rejected by the circuit like any other invalid proof. Returns whether the chips match,
+    /// since the rest of the proof cannot be laid out when they do not.
+    pub fn verify_chip_set(
+        builder: &mut Builder<C>,
+        vk: &VerifyingKeyVariable<C, SC>,
+        machine: &StarkMachine<SC, A>,
+        proof: &ShardProofVariable<C, SC>,
+    ) -> bool {
+        let accounted = verify_chip_set(
+            vk.chip_information.iter().map(|(name, _, _)| name.as_str()),
+            machine.chips().iter().map(|chip| chip.name()),
+            &proof.chip_ordering,
+            proof.opened_values.chips.len(),
+        )
+        .is_ok();
+        let accounted_felt: Felt<_> = builder.eval(C::F::from_bool(accounted));
+        builder.assert_felt_eq(accounted_felt, C::F::one());
+        accounted
+    }
+
     pub fn verify_shard(
         builder: &mut Builder<C>,
         vk: &VerifyingKeyVariable<C, SC>,
         machine: &StarkMachine<SC, A>,
         challenger: &mut SC::FriChallengerVariable,
         proof: &ShardProofVariable<C, SC>,
+        tags: DomainSeparationTags,
+    ) where
+        A: for<'a> Air<RecursiveVerifierConstraintFolder<'a, C>>,
+    {
+        let Some(challenges) =
+            Self::verify_shard_openings(builder, vk, machine, challenger, proof, tags)
+        else {
+            return;
+        };
+
+        // Verify the constrtaint evaluations.
+        builder.cycle_tracker_v2_enter("stage-e-verify-constraints".to_string());
+        for sub_proof in proof.split_by_chip() {
+            Self::verify_chip_sub_proof(builder, machine, &challenges, &sub_proof);
+        }
+        builder.cycle_tracker_v2_exit();
+    }
+
+    /// Verifies the shard proofs of a machine proof on a fresh transcript, as
+    /// [`StarkMachine::verify_with_seed`] does on the host.
+    ///
+    /// If `seed` is set, it is observed before anything else, so the prover must have observed the
+    /// same values into its challenger before proving.
+    pub fn verify_shards(
+        builder: &mut Builder<C>,
+        vk: &VerifyingKeyVariable<C, SC>,
+        machine: &StarkMachine<SC, A>,
+        proofs: &[ShardProofVariable<C, SC>],
+        seed: Option<&[Felt<C::F>]>,
+    ) where
+        A: for<'a> Air<RecursiveVerifierConstraintFolder<'a, C>>,
+    {
+        // Assert that the number of proofs is not zero.
+        assert!(!proofs.is_empty());
+
+        let mut challenger = machine.config().challenger_variable(builder);
+        if let Some(seed) = seed {
+            challenger.observe_slice(builder, seed.iter().copied());
+        }
+        vk.observe_into(builder, &mut challenger);
+        for proof in proofs {
+            challenger.observe(builder, proof.commitment.main_commit);
+            let pv_slice = &proof.public_values[..machine.num_pv_elts()];
+            challenger.observe_slice(builder, pv_slice.iter().copied());
+        }
+
+        let zero: Ext<_, _> = builder.eval(SymbolicExt::from_f(C::EF::zero()));
+        let mut cumulative_sum = zero;
+        for proof in proofs {
+            let mut challenger = challenger.copy(builder);
+            Self::verify_shard(
+                builder,
+                vk,
+                machine,
+                &mut challenger,
+                proof,
+                DomainSeparationTags::default(),
+            );
+            for chip in &proof.opened_values.chips {
+                cumulative_sum = builder.eval(cumulative_sum + chip.cumulative_sum);
+            }
+        }
+        builder.assert_ext_eq(cumulative_sum, zero);
+    }
+
+    /// Verifies a shard proof whose chips were verified as separate sub-proofs.
+    ///
+    /// The sub-proofs must be those of [`ShardProofVariable::split_by_chip`], in order. Their
+    /// opened values are constrained to equal those of `proof`, which the PCS opening binds to the
+    /// commitments, so the combined check is equivalent to [`Self::verify_shard`].
+    pub fn combine(
+        builder: &mut Builder<C>,
+        vk: &VerifyingKeyVariable<C, SC>,
+        machine: &StarkMachine<SC, A>,
+        challenger: &mut SC::FriChallengerVariable,
+        proof: &ShardProofVariable<C, SC>,
+        sub_proofs: &[ChipSubProof<C>],
+        tags: DomainSeparationTags,
     ) where
         A: for<'a> Air<RecursiveVerifierConstraintFolder<'a, C>>,
     {
+        let Some(challenges) =
+            Self::verify_shard_openings(builder, vk, machine, challenger, proof, tags)
+        else {
+            return;
+        };
+
+        assert_eq!(sub_proofs.len(), proof.opened_values.chips.len(), "missing chip sub-proofs");
+        for (i, sub_proof) in sub_proofs.iter().enumerate() {
+            assert_eq!(
+                proof.chip_ordering[&sub_proof.chip_name], i,
+                "chip sub-proofs out of order"
+            );
+            sub_proof.assert_opened_values_eq(builder, &proof.opened_values.chips[i]);
+            for (a, b) in sub_proof.public_values.iter().zip_eq(proof.public_values.iter()) {
+                builder.assert_felt_eq(*a, *b);
+            }
+            Self::verify_chip_sub_proof(builder, machine, &challenges, sub_proof);
+        }
+    }
+
+    /// Verifies the constraints of a single chip against the challenges of its shard.
+    pub fn verify_chip_sub_proof(
+        builder: &mut Builder<C>,
+        machine: &StarkMachine<SC, A>,
+        challenges: &ShardChallengesVariable<C>,
+        sub_proof: &ChipSubProof<C>,
+    ) where
+        A: for<'a> Air<RecursiveVerifierConstraintFolder<'a, C>>,
+    {
+        let chip = machine
+            .chips()
+            .iter()
+            .find(|chip| chip.name() == sub_proof.chip_name)
+            .expect("chip is not part of the machine");
+        let values = &sub_proof.opened_values;
+
+        let log_quotient_degree = chip.log_quotient_degree();
+        let trace_domain =
+            Self::natural_domain_for_degree(machine.config(), 1 << values.log_degree);
+        let qc_domains = trace_domain
+            .create_disjoint_domain(1 << (values.log_degree + log_quotient_degree))
+            .split_domains(1 << log_quotient_degree);
+
+        // Verify the shape of the opening arguments matches the expected values.
+        Self::verify_opening_shape(chip, values).unwrap();
+        // Verify the constraint evaluation.
+        Self::verify_constraints(
+            builder,
+            chip,
+            values,
+            trace_domain,
+            qc_domains,
+            challenges.zeta,
+            challenges.alpha,
+            &challenges.permutation_challenges,
+            &sub_proof.public_values,
+        );
+    }
+
+    /// Verifies the PCS opening of a shard proof and returns the challenges shared by its chips.
+    ///
+    /// Returns `None` if the chips of the proof are not accounted for, in which case an
+    /// unsatisfiable constraint has been emitted instead.
+    pub fn verify_shard_openings(
+        builder: &mut Builder<C>,
+        vk: &VerifyingKeyVariable<C, SC>,
+        machine: &StarkMachine<SC, A>,
+        challenger: &mut SC::FriChallengerVariable,
+        proof: &ShardProofVariable<C, SC>,
+        tags: DomainSeparationTags,
+    ) -> Option<ShardChallengesVariable<C>> {
+        // Verify that the chips of the proof are accounted for by the verifying key.
+        if !Self::verify_chip_set(builder, vk, machine, proof) {
+            return None;
+        }
+
         let chips = machine.shard_chips_ordered(&proof.chip_ordering).collect::<Vec<_>>();
 
-        let ShardProofVariable {
-            commitment,
-            opened_values,
-            opening_proof,
-            chip_ordering,
-            public_values,
-        } = proof;
+        let ShardProofVariable { commitment, opened_values, opening_proof, chip_ordering, .. } =
+            proof;
 
         let log_degrees = opened_values.chips.iter().map(|val| val.log_degree).collect::<Vec<_>>();
 
@@ -119,34 +337,50 @@ where
         let preprocessed_domains_points_and_opens = vk
             .chip_information
             .iter()
-            .map(|(name, domain, _)| {
+            .map(|(name, domain, dims)| {
                 let i = chip_ordering[name];
                 let values = opened_values.chips[i].preprocessed.clone();
-                TwoAdicPcsMatsVariable::<C> {
-                    domain: *domain,
-                    points: vec![zeta, domain.next_point_variable(builder, zeta)],
-                    values: vec![values.local, values.next],
-                }
+                TwoAdicPcsMatsVariable::<C>::new(
+                    *domain,
+                    vec![zeta, domain.next_point_variable(builder, zeta)],
+                    vec![values.local, values.next],
+                    *dims,
+                )
+                .unwrap()
             })
             .collect::<Vec<_>>();
 
+        let ext_degree = <C::EF as AbstractExtensionField<C::F>>::D;
         let main_domains_points_and_opens = trace_domains
             .iter()
             .zip_eq(opened_values.chips.iter())
-            .map(|(domain, values)| TwoAdicPcsMatsVariable::<C> {
-                domain: *domain,
-                points: vec![zeta, domain.next_point_variable(builder, zeta)],
-                values: vec![values.main.local.clone(), values.main.next.clone()],
+            .zip_eq(chips.iter())
+            .map(|((domain, values), chip)| {
+                TwoAdicPcsMatsVariable::<C>::new(
+                    *domain,
+                    vec![zeta, domain.next_point_variable(builder, zeta)],
+                    vec![values.main.local.clone(), values.main.next.clone()],
+                    Dimensions { width: chip.width(), height: domain.size() },
+                )
+                .unwrap()
             })
             .collect::<Vec<_>>();
 
         let perm_domains_points_and_opens = trace_domains
             .iter()
             .zip_eq(opened_values.chips.iter())
-            .map(|(domain, values)| TwoAdicPcsMatsVariable::<C> {
-                domain: *domain,
-                points: vec![zeta, domain.next_point_variable(builder, zeta)],
-                values: vec![values.permutation.local.clone(), values.permutation.next.clone()],
+            .zip_eq(chips.iter())
+            .map(|((domain, values), chip)| {
+                TwoAdicPcsMatsVariable::<C>::new(
+                    *domain,
+                    vec![zeta, domain.next_point_variable(builder, zeta)],
+                    vec![values.permutation.local.clone(), values.permutation.next.clone()],
+                    Dimensions {
+                        width: chip.permutation_width() * ext_degree,
+                        height: domain.size(),
+                    },
+                )
+                .unwrap()
             })
             .collect::<Vec<_>>();
 
@@ -169,11 +403,13 @@ where
             .zip_eq(quotient_chunk_domains.iter())
             .flat_map(|(values, qc_domains)| {
                 values.quotient.iter().zip_eq(qc_domains).map(move |(values, q_domain)| {
-                    TwoAdicPcsMatsVariable::<C> {
-                        domain: *q_domain,
-                        points: vec![zeta],
-                        values: vec![values.clone()],
-                    }
+                    TwoAdicPcsMatsVariable::<C>::new(
+                        *q_domain,
+                        vec![zeta],
+                        vec![values.clone()],
+                        Dimensions { width: ext_degree, height: q_domain.size() },
+                    )
+                    .unwrap()
                 })
             })
             .collect::<Vec<_>>();
@@ -201,30 +437,46 @@ where
         // Verify the pcs proof
         builder.cycle_tracker_v2_enter("stage-d-verify-pcs".to_string());
         let config = machine.config().fri_config();
-        verify_two_adic_pcs::<C, SC>(builder, config, opening_proof, challenger, rounds);
+        verify_two_adic_pcs::<C, SC>(builder, config, opening_proof, challenger, rounds, tags);
         builder.cycle_tracker_v2_exit();
 
-        // Verify the constrtaint evaluations.
-        builder.cycle_tracker_v2_enter("stage-e-verify-constraints".to_string());
-        for (chip, trace_domain, qc_domains, values) in
-            izip!(chips.iter(), trace_domains, quotient_chunk_domains, opened_values.chips.iter(),)
-        {
-            // Verify the shape of the opening arguments matches the expected values.
-            Self::verify_opening_shape(chip, values).unwrap();
-            // Verify the constraint evaluation.
-            Self::verify_constraints(
-                builder,
-                chip,
-                values,
-                trace_domain,
-                qc_domains,
-                zeta,
-                alpha,
-                &permutation_challenges,
-                public_values,
-            );
-        }
-        builder.cycle_tracker_v2_exit();
+        Some(ShardChallengesVariable { permutation_challenges, alpha, zeta })
+    }
+
+    /// Verifies a single-shard proof on a fresh transcript and returns its public values.
+    ///
+    /// If `context` is set, the proof must be bound to it, and the returned public values carry
+    /// it. The public values are only usable once verification has been constrained, so
+    /// application circuits can build further constraints on the returned variable directly.
+    ///
+    /// The `tags`, and whether the chip ordering is observed with the verifying key, must match
+    /// what the prover absorbed.
+    pub fn verify_and_extract(
+        builder: &mut Builder<C>,
+        vk: &VerifyingKeyVariable<C, SC>,
+        proof: &ShardProofVariable<C, SC>,
+        machine: &StarkMachine<SC, A>,
+        context: Option<Felt<C::F>>,
+        tags: DomainSeparationTags,
+        chip_ordering: bool,
+    ) -> PublicValuesVariable<C>
+    where
+        A: for<'a> Air<RecursiveVerifierConstraintFolder<'a, C>>,
+    {
+        let public_values = PublicValuesVariable {
+            context,
+            ..PublicValuesVariable::from_slice(&proof.public_values)
+        };
+
+        let mut challenger = machine.config().challenger_variable(builder);
+        public_values.observe_context(builder, &mut challenger);
+        vk.observe_into_with_tags(builder, &mut challenger, tags, chip_ordering);
+        challenger.observe(builder, proof.commitment.main_commit);
+        let pv_slice = &proof.public_values[..machine.num_pv_elts()];
+        challenger.observe_slice(builder, pv_slice.iter().cloned());
+        Self::verify_shard(builder, vk, machine, &mut challenger, proof, tags);
+
+        public_values
     }
 }
 
@@ -240,6 +492,68 @@ impl<C: CircuitConfig<F = SC::Val>, SC: BabyBearFriConfigVariable<C>> ShardProof
     pub fn contains_memory_finalize(&self) -> bool {
         self.chip_ordering.contains_key("MemoryFinalize")
     }
+
+    /// Splits the proof into the sub-proofs of its chips, in the order of the chip openings.
+    pub fn split_by_chip(&self) -> Vec<ChipSubProof<C>> {
+        self.chip_ordering
+            .iter()
+            .sorted_by_key(|(_, i)| **i)
+            .map(|(name, i)| ChipSubProof {
+                chip_name: name.clone(),
+                opened_values: self.opened_values.chips[*i].clone(),
+                public_values: self.public_values.clone(),
+            })
+            .collect()
+    }
+}
+
+/// The challenges shared by all the chips of a shard proof.
+#[derive(Clone)]
+pub struct ShardChallengesVariable<C: Config> {
+    pub permutation_challenges: Vec<Ext<C::F, C::EF>>,
+    pub alpha: Ext<C::F, C::EF>,
+    pub zeta: Ext<C::F, C::EF>,
+}
+
+/// The opening of a single chip of a shard proof, whose constraints can be verified independently
+/// of the other chips.
+///
+/// The PCS opening proof batches every chip, so it is verified once per shard, which also fixes
+/// the [`ShardChallengesVariable`] that each sub-proof is checked against.
+#[derive(Clone)]
+pub struct ChipSubProof<C: Config> {
+    pub chip_name: String,
+    pub opened_values: ChipOpenedValues<Ext<C::F, C::EF>>,
+    pub public_values: Vec<Felt<C::F>>,
+}
+
+impl<C: Config> ChipSubProof<C> {
+    /// Asserts that the opened values of the sub-proof are `other`.
+    fn assert_opened_values_eq(
+        &self,
+        builder: &mut Builder<C>,
+        other: &ChipOpenedValues<Ext<C::F, C::EF>>,
+    ) {
+        let values = &self.opened_values;
+        assert_eq!(values.log_degree, other.log_degree, "chip sub-proof degree mismatch");
+        let flatten = |values: &ChipOpenedValues<Ext<C::F, C::EF>>| {
+            chain!(
+                values.preprocessed.local.iter(),
+                values.preprocessed.next.iter(),
+                values.main.local.iter(),
+                values.main.next.iter(),
+                values.permutation.local.iter(),
+                values.permutation.next.iter(),
+                values.quotient.iter().flatten(),
+                once(&values.cumulative_sum),
+            )
+            .copied()
+            .collect::<Vec<_>>()
+        };
+        for (a, b) in flatten(values).into_iter().zip_eq(flatten(other)) {
+            builder.assert_ext_eq(a, b);
+        }
+    }
 }
 
 #[allow(unused_imports)]
@@ -249,6 +563,7 @@ pub mod tests {
 
     use crate::{
         challenger::{CanCopyChallenger, CanObserveVariable, DuplexChallengerVariable},
+        hash::FieldHasherVariable,
         utils::tests::run_test_recursion_with_prover,
         BabyBearFriConfig,
     };
@@ -342,11 +657,603 @@ pub mod tests {
         let num_shards = num_shards_in_batch.unwrap_or(proofs.len());
         for proof in proofs.into_iter().take(num_shards) {
             let mut challenger = challenger.copy(&mut builder);
-            StarkVerifier::verify_shard(&mut builder, &vk, &machine, &mut challenger, &proof);
+            StarkVerifier::verify_shard(
+                &mut builder,
+                &vk,
+                &machine,
+                &mut challenger,
+                &proof,
+                DomainSeparationTags::default(),
+            );
         }
         (builder.operations, witness_stream)
     }
 
+    #[test]
+    fn test_verify_chip_set() {
+        let machine_chips = || ["Program", "Byte", "CPU", "MemoryInit"].map(String::from);
+        let ordering = |names: &[&str]| {
+            names
+                .iter()
+                .enumerate()
+                .map(|(i, name)| (name.to_string(), i))
+                .collect::<HashMap<_, _>>()
+        };
+        let preprocessed = ["Program", "Byte"];
+
+        let valid = ordering(&["CPU", "Program", "Byte"]);
+        assert_eq!(verify_chip_set(preprocessed, machine_chips(), &valid, 3), Ok(()));
+
+        // A chip that is not part of the machine.
+        let extra = ordering(&["CPU", "Program", "Byte", "Backdoor"]);
+        assert_eq!(
+            verify_chip_set(preprocessed, machine_chips(), &extra, 4),
+            Err(ChipSetError::UnknownChip("Backdoor".to_string()))
+        );
+
+        // A preprocessed chip that is missing from the proof.
+        let missing = ordering(&["CPU", "Program"]);
+        assert_eq!(
+            verify_chip_set(preprocessed, machine_chips(), &missing, 2),
+            Err(ChipSetError::MissingChip("Byte".to_string()))
+        );
+
+        // An opening that is not indexed by the chip ordering.
+        assert_eq!(
+            verify_chip_set(preprocessed, machine_chips(), &valid, 4),
+            Err(ChipSetError::ChipOrderingMismatch(4, 3))
+        );
+    }
+
+    #[test]
+    fn test_verify_shard_rejects_unaccounted_chips() {
+        type SC = BabyBearPoseidon2;
+        type C = InnerConfig;
+
+        setup_logger();
+        let machine = RiscvAir::<F>::machine(SC::default());
+        let (_, vk) = machine.setup(&Program::from(FIBONACCI_ELF).unwrap());
+        let (mut proof, _, _) = prove::<_, CpuProver<_, _>>(
+            Program::from(FIBONACCI_ELF).unwrap(),
+            &SP1Stdin::new(),
+            SC::default(),
+            SP1CoreOpts::default(),
+        )
+        .unwrap();
+        let proof = proof.shard_proofs.pop().unwrap();
+
+        let build = |tamper: fn(&mut HashMap<String, usize>)| {
+            let mut builder = Builder::<C>::default();
+            let mut witness_stream = Vec::<WitnessBlock<C>>::new();
+            Witnessable::<C>::write(&vk, &mut witness_stream);
+            Witnessable::<C>::write(&proof, &mut witness_stream);
+            let vk_variable = vk.read(&mut builder);
+            let mut proof_variable = proof.read(&mut builder);
+            tamper(&mut proof_variable.chip_ordering);
+
+            let mut challenger = machine.config().challenger_variable(&mut builder);
+            StarkVerifier::verify_shard(
+                &mut builder,
+                &vk_variable,
+                &machine,
+                &mut challenger,
+                &proof_variable,
+                DomainSeparationTags::default(),
+            );
+            (builder.operations, witness_stream)
+        };
+
+        // The circuit is built for an extra chip and a missing preprocessed chip, but the proofs
+        // are rejected when it runs.
+        let extra = |ordering: &mut HashMap<String, usize>| {
+            ordering.insert("Backdoor".to_string(), ordering.len());
+        };
+        let missing = |ordering: &mut HashMap<String, usize>| {
+            ordering.remove("Byte");
+        };
+        for tamper in [extra as fn(&mut _), missing] {
+            let (operations, stream) = build(tamper);
+            let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+                run_test_recursion_with_prover::<CpuProver<_, _>>(operations, stream)
+            }));
+            assert!(result.is_err(), "a proof with unaccounted chips was accepted");
+        }
+    }
+
+    /// Proves the first shard of the Fibonacci program on its own, so that a fresh transcript
+    /// that observes this shard alone is the prover's transcript.
+    fn prove_first_shard() -> (StarkVerifyingKey<BabyBearPoseidon2>, ShardProof<BabyBearPoseidon2>)
+    {
+        prove_first_shard_with(None, DomainSeparationTags::default(), false)
+    }
+
+    /// Like [`prove_first_shard`], with the transcript starting from `context` if it is set, and
+    /// absorbing the given tags and chip ordering.
+    fn prove_first_shard_with(
+        context: Option<F>,
+        tags: DomainSeparationTags,
+        chip_ordering: bool,
+    ) -> (StarkVerifyingKey<BabyBearPoseidon2>, ShardProof<BabyBearPoseidon2>) {
+        use p3_challenger::CanObserve;
+
+        let program = Program::from(FIBONACCI_ELF).unwrap();
+        let mut runtime = sp1_core_executor::Executor::new(program.clone(), SP1CoreOpts::default());
+        runtime.run().unwrap();
+        runtime.records.truncate(1);
+        runtime.records[0].public_values.shard = 1;
+
+        let prover = CpuProver::new(RiscvAir::machine(BabyBearPoseidon2::default()));
+        let (pk, vk) = prover.setup(&program);
+        let mut challenger = prover.config().challenger();
+        if let Some(context) = context {
+            challenger.observe(context);
+        }
+        let opts = SP1CoreOpts::default();
+        let mut proof = prover
+            .prove_with_tags(&pk, runtime.records, &mut challenger, opts, tags, chip_ordering)
+            .unwrap();
+        assert_eq!(proof.shard_proofs.len(), 1);
+        (vk, proof.shard_proofs.pop().unwrap())
+    }
+
+    #[test]
+    fn test_verify_and_extract() {
+        use p3_field::AbstractField;
+        use sp1_stark::InnerChallenge;
+
+        use crate::machine::PublicValuesAssertions;
+
+        type SC = BabyBearPoseidon2;
+        type C = InnerConfig;
+
+        setup_logger();
+        let machine = RiscvAir::<F>::machine(SC::default());
+        let (vk, proof) = prove_first_shard();
+
+        let build = |proof: &ShardProof<SC>| {
+            let mut builder = Builder::<C>::default();
+            let mut witness_stream = Vec::<WitnessBlock<C>>::new();
+            Witnessable::<C>::write(&vk, &mut witness_stream);
+            Witnessable::<C>::write(proof, &mut witness_stream);
+            let vk_variable = vk.read(&mut builder);
+            let proof_variable = proof.read(&mut builder);
+            let public_values = StarkVerifier::verify_and_extract(
+                &mut builder,
+                &vk_variable,
+                &proof_variable,
+                &machine,
+                None,
+                DomainSeparationTags::default(),
+                false,
+            );
+            public_values.assert_success(&mut builder);
+            builder.assert_felt_eq(public_values.shard, F::one());
+            (builder.operations, witness_stream)
+        };
+
+        let (operations, stream) = build(&proof);
+        run_test_recursion_with_prover::<CpuProver<_, _>>(operations, stream);
+
+        let mut tampered = proof.clone();
+        tampered.opened_values.chips[0].main.local[0] += InnerChallenge::one();
+        let (operations, stream) = build(&tampered);
+        let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+            run_test_recursion_with_prover::<CpuProver<_, _>>(operations, stream)
+        }));
+        assert!(result.is_err(), "tampered proof was accepted");
+    }
+
+    #[test]
+    fn test_verify_and_extract_with_context() {
+        use p3_field::AbstractField;
+
+        type SC = BabyBearPoseidon2;
+        type C = InnerConfig;
+
+        setup_logger();
+        let machine = RiscvAir::<F>::machine(SC::default());
+        let context = F::from_canonical_u32(20_000_000);
+        let (vk, proof) =
+            prove_first_shard_with(Some(context), DomainSeparationTags::default(), false);
+
+        let build = |expected: Option<F>| {
+            let mut builder = Builder::<C>::default();
+            let mut witness_stream = Vec::<WitnessBlock<C>>::new();
+            Witnessable::<C>::write(&vk, &mut witness_stream);
+            Witnessable::<C>::write(&proof, &mut witness_stream);
+            let vk_variable = vk.read(&mut builder);
+            let proof_variable = proof.read(&mut builder);
+            let context = expected.map(|expected| builder.eval(expected));
+            let public_values = StarkVerifier::verify_and_extract(
+                &mut builder,
+                &vk_variable,
+                &proof_variable,
+                &machine,
+                context,
+                DomainSeparationTags::default(),
+                false,
+            );
+            assert_eq!(public_values.context.is_some(), expected.is_some());
+            builder.assert_felt_eq(public_values.shard, F::one());
+            (builder.operations, witness_stream)
+        };
+
+        let (operations, stream) = build(Some(context));
+        run_test_recursion_with_prover::<CpuProver<_, _>>(operations, stream);
+
+        for expected in [Some(context + F::one()), None] {
+            let (operations, stream) = build(expected);
+            let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+                run_test_recursion_with_prover::<CpuProver<_, _>>(operations, stream)
+            }));
+            assert!(result.is_err(), "proof was accepted with a mismatched context");
+        }
+    }
+
+    #[test]
+    fn test_verify_and_extract_with_transcript_options() {
+        type SC = BabyBearPoseidon2;
+        type C = InnerConfig;
+
+        setup_logger();
+        let machine = RiscvAir::<F>::machine(SC::default());
+        let tags = DomainSeparationTags::standard();
+        let (vk, proof) = prove_first_shard_with(None, tags, true);
+
+        let build = |tags: DomainSeparationTags, chip_ordering: bool| {
+            let mut builder = Builder::<C>::default();
+            let mut witness_stream = Vec::<WitnessBlock<C>>::new();
+            Witnessable::<C>::write(&vk, &mut witness_stream);
+            Witnessable::<C>::write(&proof, &mut witness_stream);
+            let vk_variable = vk.read(&mut builder);
+            let proof_variable = proof.read(&mut builder);
+            StarkVerifier::verify_and_extract(
+                &mut builder,
+                &vk_variable,
+                &proof_variable,
+                &machine,
+                None,
+                tags,
+                chip_ordering,
+            );
+            (builder.operations, witness_stream)
+        };
+
+        let (operations, stream) = build(tags, true);
+        run_test_recursion_with_prover::<CpuProver<_, _>>(operations, stream);
+
+        let untagged_vk = DomainSeparationTags { vk: None, ..tags };
+        let untagged_opening = DomainSeparationTags { commit_phase: None, ..tags };
+        for (tags, chip_ordering) in [(untagged_vk, true), (untagged_opening, true), (tags, false)]
+        {
+            let (operations, stream) = build(tags, chip_ordering);
+            let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+                run_test_recursion_with_prover::<CpuProver<_, _>>(operations, stream)
+            }));
+            assert!(result.is_err(), "proof was accepted with a mismatched transcript");
+        }
+    }
+
+    #[test]
+    fn test_verify_shards_with_seed() {
+        use p3_challenger::CanObserve;
+        use sp1_core_executor::{programs::tests::simple_program, ExecutionRecord, Executor};
+        use sp1_stark::MachineRecord;
+
+        type SC = BabyBearPoseidon2;
+        type C = InnerConfig;
+
+        setup_logger();
+        let program = simple_program();
+        let opts = SP1CoreOpts::default();
+        let mut runtime = Executor::new(program.clone(), opts);
+        runtime.run().unwrap();
+
+        // Move the memory events into their own shards, as the core prover does.
+        let mut records = runtime.records;
+        let mut deferred = ExecutionRecord::default();
+        for record in records.iter_mut() {
+            deferred.append(&mut record.defer());
+        }
+        records.extend(deferred.split(true, opts.split_opts));
+        records.iter_mut().enumerate().for_each(|(i, shard)| {
+            shard.public_values.shard = (i + 1) as u32;
+        });
+
+        let prover = CpuProver::new(RiscvAir::machine(SC::default()));
+        let (pk, vk) = prover.setup(&program);
+        let seed = [F::from_canonical_u32(0xBEAC0); 8];
+        let mut challenger = prover.config().challenger();
+        challenger.observe_slice(&seed);
+        let proof = prover.prove(&pk, records, &mut challenger, opts).unwrap();
+
+        let build = |seed: Option<&[F]>| {
+            let mut builder = Builder::<C>::default();
+            let mut witness_stream = Vec::<WitnessBlock<C>>::new();
+            Witnessable::<C>::write(&vk, &mut witness_stream);
+            let vk_variable = vk.read(&mut builder);
+            let proofs = proof
+                .shard_proofs
+                .iter()
+                .map(|proof| {
+                    Witnessable::<C>::write(proof, &mut witness_stream);
+                    proof.read(&mut builder)
+                })
+                .collect::<Vec<_>>();
+            let seed = seed.map(|seed| {
+                seed.iter().map(|value| builder.eval(*value)).collect::<Vec<Felt<_>>>()
+            });
+            StarkVerifier::verify_shards(
+                &mut builder,
+                &vk_variable,
+                prover.machine(),
+                &proofs,
+                seed.as_deref(),
+            );
+            (builder.operations, witness_stream)
+        };
+
+        let (operations, stream) = build(Some(&seed));
+        run_test_recursion_with_prover::<CpuProver<_, _>>(operations, stream);
+
+        for other in [Some(&[F::from_canonical_u32(0xBEAC1); 8][..]), None] {
+            let (operations, stream) = build(other);
+            let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+                run_test_recursion_with_prover::<CpuProver<_, _>>(operations, stream)
+            }));
+            assert!(result.is_err(), "proof was accepted with a different seed");
+        }
+    }
+
+    #[test]
+    fn test_split_by_chip_and_combine() {
+        use p3_field::AbstractField;
+        use sp1_recursion_compiler::ir::SymbolicExt;
+        use sp1_stark::InnerChallenge;
+
+        type SC = BabyBearPoseidon2;
+        type C = InnerConfig;
+
+        setup_logger();
+        let machine = RiscvAir::<F>::machine(SC::default());
+        let (vk, proof) = prove_first_shard();
+
+        // `None` verifies the proof monolithically, `Some(tamper)` through its chip sub-proofs.
+        let build = |split: Option<bool>| {
+            let mut builder = Builder::<C>::default();
+            let mut witness_stream = Vec::<WitnessBlock<C>>::new();
+            Witnessable::<C>::write(&vk, &mut witness_stream);
+            Witnessable::<C>::write(&proof, &mut witness_stream);
+            let vk_variable = vk.read(&mut builder);
+            let proof_variable = proof.read(&mut builder);
+
+            let mut challenger = machine.config().challenger_variable(&mut builder);
+            vk_variable.observe_into(&mut builder, &mut challenger);
+            challenger.observe(&mut builder, proof_variable.commitment.main_commit);
+            let pv_slice = &proof_variable.public_values[..machine.num_pv_elts()];
+            challenger.observe_slice(&mut builder, pv_slice.iter().cloned());
+            match split {
+                None => StarkVerifier::verify_shard(
+                    &mut builder,
+                    &vk_variable,
+                    &machine,
+                    &mut challenger,
+                    &proof_variable,
+                    DomainSeparationTags::default(),
+                ),
+                Some(tamper) => {
+                    let mut sub_proofs = proof_variable.split_by_chip();
+                    assert_eq!(sub_proofs.len(), proof.opened_values.chips.len());
+                    if tamper {
+                        let one: Ext<_, _> =
+                            builder.eval(SymbolicExt::from_f(InnerChallenge::one()));
+                        let values = &mut sub_proofs[0].opened_values;
+                        values.main.local[0] = builder.eval(values.main.local[0] + one);
+                    }
+                    StarkVerifier::combine(
+                        &mut builder,
+                        &vk_variable,
+                        &machine,
+                        &mut challenger,
+                        &proof_variable,
+                        &sub_proofs,
+                        DomainSeparationTags::default(),
+                    );
+                }
+            }
+            (builder.operations, witness_stream)
+        };
+
+        for split in [None, Some(false)] {
+            let (operations, stream) = build(split);
+            run_test_recursion_with_prover::<CpuProver<_, _>>(operations, stream);
+        }
+
+        let (operations, stream) = build(Some(true));
+        let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+            run_test_recursion_with_prover::<CpuProver<_, _>>(operations, stream)
+        }));
+        assert!(result.is_err(), "tampered chip sub-proof was accepted");
+    }
+
+    #[test]
+    fn test_check_quotient_consistency() {
+        use p3_field::{AbstractExtensionField, AbstractField};
+        use sp1_recursion_compiler::ir::SymbolicExt;
+        use sp1_stark::{AirOpenedValues, ChipOpenedValues, InnerChallenge};
+
+        use crate::{domain::PolynomialSpaceVariable, utils::tests::run_test_recursion};
+
+        type SC = BabyBearPoseidon2;
+        type C = InnerConfig;
+
+        let build = |offset: u32| {
+            let mut builder = Builder::<C>::default();
+            let (log_degree, log_quotient_degree) = (3, 1);
+            let trace_domain = TwoAdicMultiplicativeCoset { log_n: log_degree, shift: F::one() };
+            let qc_domains = trace_domain
+                .create_disjoint_domain(1 << (log_degree + log_quotient_degree))
+                .split_domains(1 << log_quotient_degree);
+            let zeta: Ext<_, _> =
+                builder.eval(SymbolicExt::from_f(InnerChallenge::from_base_fn(|i| {
+                    F::from_canonical_usize(i + 7)
+                })));
+
+            let mut quotient_value = |i: usize| -> Ext<_, _> {
+                builder.eval(SymbolicExt::from_f(InnerChallenge::from_canonical_usize(i + 1)))
+            };
+            let quotient =
+                (0..2).map(|i| (0..4).map(|j| quotient_value(4 * i + j)).collect()).collect();
+            let empty = || AirOpenedValues { local: vec![], next: vec![] };
+            let opening = ChipOpenedValues {
+                preprocessed: empty(),
+                main: empty(),
+                permutation: empty(),
+                quotient,
+                cumulative_sum: builder.eval(SymbolicExt::from_f(InnerChallenge::zero())),
+                log_degree,
+            };
+
+            // The constraints vanish on the trace domain, so they are the quotient times
+            // zeta^8 - 1.
+            let quotient = StarkVerifier::<C, SC, A>::recompute_quotient(
+                &mut builder,
+                &opening,
+                &qc_domains,
+                zeta,
+            );
+            let mut zeta_pow = SymbolicExt::from(zeta);
+            for _ in 0..log_degree {
+                zeta_pow = zeta_pow.clone() * zeta_pow;
+            }
+            let constraint_eval = builder.eval(
+                quotient * (zeta_pow - InnerChallenge::one())
+                    + InnerChallenge::from_canonical_u32(offset),
+            );
+
+            let selectors = trace_domain.selectors_at_point_variable(&mut builder, zeta);
+            StarkVerifier::<C, SC, A>::check_quotient_consistency(
+                &mut builder,
+                &opening,
+                constraint_eval,
+                &selectors,
+                &qc_domains,
+                zeta,
+            );
+            builder.operations
+        };
+
+        run_test_recursion(build(0), None);
+
+        let operations = build(1);
+        let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+            run_test_recursion(operations, None)
+        }));
+        assert!(result.is_err(), "an inconsistent quotient was accepted");
+    }
+
+    #[test]
+    fn test_vk_hash_digest_size() {
+        use std::marker::PhantomData;
+
+        use p3_challenger::{CanObserve, CanSample};
+        use p3_field::AbstractField;
+        use p3_symmetric::CryptographicHasher;
+        use sp1_recursion_core_v2::DIGEST_SIZE;
+        use sp1_stark::{inner_perm, InnerHash};
+
+        use crate::challenger::CanSampleVariable;
+
+        type SC = BabyBearPoseidon2;
+        type C = InnerConfig;
+
+        let machine = RiscvAir::<F>::machine(SC::default());
+        let (_, vk) = machine.setup(&Program::from(FIBONACCI_ELF).unwrap());
+        let commit: [F; DIGEST_SIZE] = vk.commit.into();
+
+        let hasher = InnerHash::new(inner_perm());
+        let expected_hash = |commitment: &[F]| {
+            let mut inputs = commitment.to_vec();
+            inputs.push(vk.pc_start);
+            for (_, domain, _) in vk.chip_information.iter() {
+                inputs.push(F::from_canonical_usize(domain.log_n));
+                inputs.push(F::from_canonical_usize(1 << domain.log_n));
+                inputs.push(domain.shift);
+                inputs.push(F::two_adic_generator(domain.log_n));
+            }
+            hasher.hash_iter(inputs)
+        };
+
+        let mut builder = Builder::<C>::default();
+        let mut witness_stream = Vec::<WitnessBlock<C>>::new();
+        Witnessable::<C>::write(&vk, &mut witness_stream);
+        let vk_variable = vk.read(&mut builder);
+
+        // A key whose commitment has half the default digest size.
+        let short_vk = VerifyingKeyVariable::<C, SC, [Felt<F>; DIGEST_SIZE / 2]> {
+            commitment: vk_variable.commitment[..DIGEST_SIZE / 2].try_into().unwrap(),
+            pc_start: vk_variable.pc_start,
+            chip_information: vk_variable.chip_information.clone(),
+            chip_ordering: vk_variable.chip_ordering.clone(),
+            _phantom: PhantomData,
+        };
+        let short_digest = short_vk.hash(&mut builder);
+        for (digest, expected) in
+            short_digest.into_iter().zip(expected_hash(&commit[..DIGEST_SIZE / 2]))
+        {
+            builder.assert_felt_eq(digest, expected);
+        }
+
+        // Observing the key absorbs its commitment at its own size.
+        let mut challenger = DuplexChallengerVariable::new(&mut builder);
+        short_vk.observe_into(&mut builder, &mut challenger);
+        let sample = challenger.sample(&mut builder);
+        let mut host_challenger = SC::default().challenger();
+        host_challenger.observe_slice(&commit[..DIGEST_SIZE / 2]);
+        host_challenger.observe(vk.pc_start);
+        let expected: F = host_challenger.sample();
+        builder.assert_felt_eq(sample, expected);
+
+        let digest = vk_variable.hash(&mut builder);
+        for (digest, expected) in digest.into_iter().zip(expected_hash(&commit)) {
+            builder.assert_felt_eq(digest, expected);
+        }
+
+        run_test_recursion_with_prover::<CpuProver<_, _>>(builder.operations, witness_stream);
+    }
+
+    #[test]
+    fn test_vk_assert_eq_const() {
+        use p3_field::AbstractField;
+
+        type SC = BabyBearPoseidon2;
+        type C = InnerConfig;
+
+        setup_logger();
+        let machine = RiscvAir::<F>::machine(SC::default());
+        let (_, vk) = machine.setup(&Program::from(FIBONACCI_ELF).unwrap());
+
+        let build = |expected: &StarkVerifyingKey<SC>| {
+            let mut builder = Builder::<C>::default();
+            let mut witness_stream = Vec::<WitnessBlock<C>>::new();
+            Witnessable::<C>::write(&vk, &mut witness_stream);
+            let vk_variable = vk.read(&mut builder);
+            vk_variable.assert_eq_const(&mut builder, expected);
+            (builder.operations, witness_stream)
+        };
+
+        let (operations, stream) = build(&vk);
+        run_test_recursion_with_prover::<CpuProver<_, _>>(operations, stream);
+
+        let mut different = vk.clone();
+        different.pc_start += F::one();
+        let (operations, stream) = build(&different);
+        let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+            run_test_recursion_with_prover::<CpuProver<_, _>>(operations, stream)
+        }));
+        assert!(result.is_err(), "a differing verifying key was accepted");
+    }
+
     #[test]
     fn test_verify_shard_inner() {
         let (operations, stream) =
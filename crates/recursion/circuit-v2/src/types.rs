@@ -1,24 +1,65 @@
+use std::marker::PhantomData;
+
 use hashbrown::HashMap;
+use p3_baby_bear::BabyBear;
 use p3_commit::TwoAdicMultiplicativeCoset;
 use p3_field::{AbstractField, TwoAdicField};
+use p3_fri::FriConfig;
 use p3_matrix::Dimensions;
 
 use sp1_recursion_compiler::ir::{Builder, Ext, Felt};
 
 use sp1_recursion_core_v2::DIGEST_SIZE;
+use sp1_stark::{baby_bear_poseidon2::BabyBearPoseidon2, StarkVerifyingKey};
 
 use crate::{
-    challenger::CanObserveVariable, hash::FieldHasherVariable, BabyBearFriConfigVariable,
-    CircuitConfig,
+    challenger::{CanObserveTagVariable, CanObserveVariable, DomainSeparationTags},
+    fri::FriConfigShape,
+    hash::FieldHasherVariable,
+    utils::assert_canonical_bits,
+    BabyBearFriConfigVariable, CircuitConfig,
 };
 
 /// Reference: [sp1_core::stark::StarkVerifyingKey]
+///
+/// The commitment is a digest of `SC` by default. A key whose commitment comes from a layer with
+/// another digest size sets `Com` to that layer's commitment, such as an array of another length.
 #[derive(Clone)]
-pub struct VerifyingKeyVariable<C: CircuitConfig<F = SC::Val>, SC: BabyBearFriConfigVariable<C>> {
-    pub commitment: SC::Digest,
+pub struct VerifyingKeyVariable<
+    C: CircuitConfig<F = SC::Val>,
+    SC: BabyBearFriConfigVariable<C>,
+    Com = <SC as FieldHasherVariable<C>>::Digest,
+> {
+    pub commitment: Com,
     pub pc_start: Felt<C::F>,
     pub chip_information: Vec<(String, TwoAdicMultiplicativeCoset<C::F>, Dimensions)>,
     pub chip_ordering: HashMap<String, usize>,
+    pub _phantom: PhantomData<SC>,
+}
+
+/// Memoizes the digests of a verifying key, so that hashing it several times within a builder
+/// runs Poseidon2 only once per layout.
+///
+/// The memo borrows the key, which therefore cannot be mutated while the memo is alive. It must
+/// only be used with the builder the digests were first computed in.
+pub struct VkDigestMemo<'a, C: CircuitConfig<F = SC::Val>, SC: BabyBearFriConfigVariable<C>> {
+    vk: &'a VerifyingKeyVariable<C, SC>,
+    digests: HashMap<HashLayout, SC::Digest>,
+}
+
+/// The domain-separation constant prepended to the inputs of a [`HashLayout::V2`] digest.
+pub const VK_HASH_V2_TAG: u32 = 2;
+
+/// The layout of the inputs hashed into a verifying key digest.
+///
+/// Verifiers pin a layout so that circuits hashing with different versions of the spec can coexist
+/// while migrating from one to the other.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum HashLayout {
+    /// commit[..] || pc_start || prep_domains[N].{log_n, .size, .shift, .g}
+    V1,
+    /// [`VK_HASH_V2_TAG`] || commit[..] || pc_start || prep_domains[N].{log_n, .size, .shift, .g}
+    V2,
 }
 
 #[derive(Clone)]
@@ -61,6 +102,45 @@ pub struct BatchOpeningVariable<C: CircuitConfig, H: FieldHasherVariable<C>> {
     pub opening_proof: Vec<H::Digest>,
 }
 
+/// An error that occurs when a batch opening has a degenerate shape.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum BatchOpeningShapeError {
+    /// The batch opening does not open any matrix.
+    EmptyOpenedValues,
+    /// The batch opening has no Merkle authentication path.
+    EmptyOpeningProof,
+    /// The Merkle authentication path does not match the depth of the committed matrices.
+    OpeningProofLengthMismatch(usize, usize),
+    /// The batch opening does not open one matrix per matrix committed in the round.
+    MatricesMismatch(usize, usize),
+    /// The opening of the matrix at the given index does not have one value per column.
+    WidthMismatch { matrix: usize, expected: usize, found: usize },
+}
+
+/// An error that occurs when a FRI proof does not have the shape the verifier expects.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum FriProofShapeError {
+    /// The number of commit-phase commitments does not match the number of folding rounds.
+    CommitPhaseCommitsMismatch(usize, usize),
+    /// The number of query proofs does not match the number of queries.
+    QueryProofsMismatch(usize, usize),
+    /// The query proof at the given index does not have one opening per folding round.
+    QueryOpeningsMismatch { query: usize, expected: usize, found: usize },
+}
+
+/// An error that occurs when a PCS proof does not have the shape of the rounds it opens.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PcsProofShapeError {
+    /// The FRI proof does not have the shape the FRI configuration expects.
+    Fri(FriProofShapeError),
+    /// The number of query openings does not match the number of queries.
+    QueryOpeningsMismatch(usize, usize),
+    /// The query at the given index does not open one batch per round.
+    BatchesMismatch { query: usize, expected: usize, found: usize },
+    /// The opening of the given round in the query at the given index has the wrong shape.
+    BatchOpening { query: usize, round: usize, error: BatchOpeningShapeError },
+}
+
 #[derive(Clone)]
 pub struct TwoAdicPcsRoundVariable<C: CircuitConfig, H: FieldHasherVariable<C>> {
     pub batch_commit: H::Digest,
@@ -72,40 +152,699 @@ pub struct TwoAdicPcsMatsVariable<C: CircuitConfig> {
     pub domain: TwoAdicMultiplicativeCoset<C::F>,
     pub points: Vec<Ext<C::F, C::EF>>,
     pub values: Vec<Vec<Ext<C::F, C::EF>>>,
+    /// The dimensions of the committed matrix, with one opened value per column at each point.
+    pub dims: Dimensions,
+}
+
+/// An error that occurs when the opened values of a matrix do not match its committed shape.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum MatsShapeError {
+    /// The number of points does not match the number of openings.
+    PointsMismatch(usize, usize),
+    /// The height of the matrix does not match the size of its domain.
+    HeightMismatch(usize, usize),
+    /// The opening at the given point does not have one value per column of the matrix.
+    WidthMismatch { point: usize, expected: usize, found: usize },
 }
 
-impl<C: CircuitConfig<F = SC::Val>, SC: BabyBearFriConfigVariable<C>> VerifyingKeyVariable<C, SC> {
+impl<C: CircuitConfig<F = SC::Val>, SC: BabyBearFriConfigVariable<C>, Com: Copy>
+    VerifyingKeyVariable<C, SC, Com>
+{
     pub fn observe_into<Challenger>(&self, builder: &mut Builder<C>, challenger: &mut Challenger)
     where
-        Challenger: CanObserveVariable<C, Felt<C::F>> + CanObserveVariable<C, SC::Digest>,
+        Challenger: CanObserveVariable<C, Felt<C::F>> + CanObserveVariable<C, Com>,
     {
+        self.observe_into_with_tags(builder, challenger, DomainSeparationTags::default(), false);
+    }
+
+    /// Observes the verifying key, preceded by the verifying key tag if it is enabled.
+    ///
+    /// If `chip_ordering` is set, the chip ordering is observed after the key, as the host
+    /// [`StarkVerifyingKey::observe_into_with_tags`] does.
+    pub fn observe_into_with_tags<Challenger>(
+        &self,
+        builder: &mut Builder<C>,
+        challenger: &mut Challenger,
+        tags: DomainSeparationTags,
+        chip_ordering: bool,
+    ) where
+        Challenger: CanObserveVariable<C, Felt<C::F>> + CanObserveVariable<C, Com>,
+    {
+        challenger.observe_optional_tag(builder, tags.vk);
         // Observe the commitment.
         challenger.observe(builder, self.commitment);
         // Observe the pc_start.
         challenger.observe(builder, self.pc_start);
+        // Observe the chip ordering if it is enabled.
+        if chip_ordering {
+            let inputs = chip_ordering_inputs(builder, &self.chip_ordering);
+            challenger.observe_slice(builder, inputs);
+        }
+    }
+
+    /// Observes the verifying key as [`Self::observe_into`] does, followed by a hash of its chip
+    /// information, so that keys that only differ in their preprocessed chip domains lead to
+    /// different challenger states.
+    pub fn observe_full<Challenger>(&self, builder: &mut Builder<C>, challenger: &mut Challenger)
+    where
+        C::F: TwoAdicField,
+        Challenger: CanObserveVariable<C, Felt<C::F>>
+            + CanObserveVariable<C, Com>
+            + CanObserveVariable<C, SC::Digest>,
+    {
+        self.observe_into(builder, challenger);
+        let inputs = chip_information_inputs(builder, &self.chip_information);
+        let chip_information_digest = SC::hash(builder, &inputs);
+        challenger.observe(builder, chip_information_digest);
     }
 
     /// Hash the verifying key + prep domains into a single digest.
-    /// poseidon2( commit[0..8] || pc_start || prep_domains[N].{log_n, .size, .shift, .g})
+    /// poseidon2( commit[..] || pc_start || prep_domains[N].{log_n, .size, .shift, .g})
     pub fn hash(&self, builder: &mut Builder<C>) -> SC::Digest
+    where
+        C::F: TwoAdicField,
+        Com: IntoIterator<Item = Felt<C::F>>,
+    {
+        self.hash_with_layout(builder, HashLayout::V1)
+    }
+
+    /// Hash the verifying key + prep domains into a single digest, laid out as `layout` states.
+    pub fn hash_with_layout(&self, builder: &mut Builder<C>, layout: HashLayout) -> SC::Digest
+    where
+        C::F: TwoAdicField,
+        Com: IntoIterator<Item = Felt<C::F>>,
+    {
+        let commitment = self.commitment.into_iter().collect::<Vec<_>>();
+        let mut inputs =
+            vk_hash_inputs(builder, &commitment, self.pc_start, &self.chip_information);
+        if layout == HashLayout::V2 {
+            let tag: Felt<_> = builder.eval(C::F::from_canonical_u32(VK_HASH_V2_TAG));
+            inputs.insert(0, tag);
+        }
+        SC::hash(builder, &inputs)
+    }
+}
+
+impl<'a, C: CircuitConfig<F = SC::Val>, SC: BabyBearFriConfigVariable<C>> VkDigestMemo<'a, C, SC> {
+    /// Creates a memo of the digests of `vk`, none of which has been computed yet.
+    pub fn new(vk: &'a VerifyingKeyVariable<C, SC>) -> Self {
+        Self { vk, digests: HashMap::new() }
+    }
+
+    /// Returns the digest of the key in `layout`, hashing it only if no earlier call did.
+    pub fn hash(&mut self, builder: &mut Builder<C>, layout: HashLayout) -> SC::Digest
     where
         C::F: TwoAdicField,
         SC::Digest: IntoIterator<Item = Felt<C::F>>,
     {
-        let prep_domains = self.chip_information.iter().map(|(_, domain, _)| domain);
-        let num_inputs = DIGEST_SIZE + 1 + (4 * prep_domains.len());
-        let mut inputs = Vec::with_capacity(num_inputs);
-        inputs.extend(self.commitment);
-        inputs.push(self.pc_start);
-        for domain in prep_domains {
-            inputs.push(builder.eval(C::F::from_canonical_usize(domain.log_n)));
-            let size = 1 << domain.log_n;
-            inputs.push(builder.eval(C::F::from_canonical_usize(size)));
-            let g = C::F::two_adic_generator(domain.log_n);
-            inputs.push(builder.eval(domain.shift));
-            inputs.push(builder.eval(g));
+        if let Some(digest) = self.digests.get(&layout) {
+            return *digest;
         }
+        let digest = self.vk.hash_with_layout(builder, layout);
+        self.digests.insert(layout, digest);
+        digest
+    }
+}
 
-        SC::hash(builder, &inputs)
+/// The inputs hashed into a verifying key digest:
+/// commit[..] || pc_start || prep_domains[N].{log_n, .size, .shift, .g}
+///
+/// The commitment may have any digest size, so that keys whose commitments come from a layer with
+/// another digest size hash the same way.
+pub fn vk_hash_inputs<C: CircuitConfig>(
+    builder: &mut Builder<C>,
+    commitment: &[Felt<C::F>],
+    pc_start: Felt<C::F>,
+    chip_information: &[(String, TwoAdicMultiplicativeCoset<C::F>, Dimensions)],
+) -> Vec<Felt<C::F>>
+where
+    C::F: TwoAdicField,
+{
+    let num_inputs = commitment.len() + 1 + (4 * chip_information.len());
+    let mut inputs = Vec::with_capacity(num_inputs);
+    inputs.extend_from_slice(commitment);
+    inputs.push(pc_start);
+    inputs.extend(chip_information_inputs(builder, chip_information));
+    inputs
+}
+
+/// The inputs describing a chip ordering, as [`sp1_stark::chip_ordering_inputs`] lays them out.
+pub fn chip_ordering_inputs<C: CircuitConfig>(
+    builder: &mut Builder<C>,
+    chip_ordering: &HashMap<String, usize>,
+) -> Vec<Felt<C::F>> {
+    sp1_stark::chip_ordering_inputs::<C::F>(chip_ordering)
+        .into_iter()
+        .map(|input| builder.eval(input))
+        .collect()
+}
+
+/// The inputs describing the preprocessed chip domains: prep_domains[N].{log_n, .size, .shift, .g}
+pub fn chip_information_inputs<C: CircuitConfig>(
+    builder: &mut Builder<C>,
+    chip_information: &[(String, TwoAdicMultiplicativeCoset<C::F>, Dimensions)],
+) -> Vec<Felt<C::F>>
+where
+    C::F: TwoAdicField,
+{
+    let mut inputs = Vec::with_capacity(4 * chip_information.len());
+    for (_, domain, _) in chip_information {
+        inputs.push(builder.eval(C::F::from_canonical_usize(domain.log_n)));
+        let size = 1 << domain.log_n;
+        inputs.push(builder.eval(C::F::from_canonical_usize(size)));
+        let g = C::F::two_adic_generator(domain.log_n);
+        inputs.push(builder.eval(domain.shift));
+        inputs.push(builder.eval(g));
+    }
+    inputs
+}
+
+impl<C: CircuitConfig<F = BabyBear, Bit = Felt<BabyBear>>>
+    VerifyingKeyVariable<C, BabyBearPoseidon2>
+{
+    /// Constrains the verifying key to be the constant host verifying key `vk`.
+    ///
+    /// The commitment and the start pc are constrained in the circuit. The chip domains and the
+    /// chip ordering are fixed when the circuit is built, so they are checked while building it.
+    pub fn assert_eq_const(
+        &self,
+        builder: &mut Builder<C>,
+        vk: &StarkVerifyingKey<BabyBearPoseidon2>,
+    ) {
+        let commitment: [BabyBear; DIGEST_SIZE] = vk.commit.into();
+        for (felt, value) in self.commitment.iter().zip(commitment) {
+            builder.assert_felt_eq(*felt, value);
+        }
+        builder.assert_felt_eq(self.pc_start, vk.pc_start);
+
+        assert_eq!(
+            self.chip_information.len(),
+            vk.chip_information.len(),
+            "the number of preprocessed chips does not match"
+        );
+        for ((name, domain, dims), (vk_name, vk_domain, vk_dims)) in
+            self.chip_information.iter().zip(vk.chip_information.iter())
+        {
+            assert_eq!(name, vk_name, "preprocessed chip name mismatch");
+            assert_eq!(
+                (domain.log_n, domain.shift),
+                (vk_domain.log_n, vk_domain.shift),
+                "preprocessed domain mismatch for chip {name}"
+            );
+            assert_eq!(
+                (dims.width, dims.height),
+                (vk_dims.width, vk_dims.height),
+                "preprocessed dimensions mismatch for chip {name}"
+            );
+        }
+        assert_eq!(self.chip_ordering, vk.chip_ordering, "chip ordering mismatch");
+    }
+}
+
+impl<C: CircuitConfig, H: FieldHasherVariable<C>> TwoAdicPcsProofVariable<C, H> {
+    /// The number of commitments the proof refers to: one per batch opened by every query, plus
+    /// one per FRI commit-phase round.
+    pub fn num_commitments(&self) -> usize {
+        let num_batches = self.query_openings.first().map_or(0, Vec::len);
+        assert!(
+            self.query_openings.iter().all(|openings| openings.len() == num_batches),
+            "every query must open the same batches"
+        );
+        num_batches + self.fri_proof.num_commit_phases()
+    }
+
+    /// Checks that the proof has the shape `config` expects for opening `rounds`: a FRI proof with
+    /// one commit-phase round per halving of the tallest domain, and for every query one batch
+    /// opening per round, each matching the matrices committed in that round.
+    ///
+    /// This is the only shape check of the PCS verifier, which runs it before emitting any
+    /// constraint for the proof.
+    pub fn validate_shape<M>(
+        &self,
+        config: &FriConfig<M>,
+        rounds: &[TwoAdicPcsRoundVariable<C, H>],
+    ) -> Result<(), PcsProofShapeError> {
+        let log_max_degree = rounds
+            .iter()
+            .flat_map(|round| &round.domains_points_and_opens)
+            .map(|mat| mat.domain.log_n)
+            .max()
+            .expect("Empty rounds?");
+        self.fri_proof
+            .validate_shape(config.num_commit_rounds(log_max_degree), config.num_queries)
+            .map_err(PcsProofShapeError::Fri)?;
+        if self.query_openings.len() != config.num_queries {
+            return Err(PcsProofShapeError::QueryOpeningsMismatch(
+                config.num_queries,
+                self.query_openings.len(),
+            ));
+        }
+        for (query, batch_openings) in self.query_openings.iter().enumerate() {
+            if batch_openings.len() != rounds.len() {
+                return Err(PcsProofShapeError::BatchesMismatch {
+                    query,
+                    expected: rounds.len(),
+                    found: batch_openings.len(),
+                });
+            }
+            for (index, (batch_opening, round)) in batch_openings.iter().zip(rounds).enumerate() {
+                batch_opening.verify_round_shape(round, config.log_blowup).map_err(|error| {
+                    PcsProofShapeError::BatchOpening { query, round: index, error }
+                })?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Whether `other` has the same shape as this proof, ignoring the values it holds.
+    ///
+    /// Two proofs have the same shape if their FRI proofs have as many commit-phase commitments and
+    /// queries, each query with as many openings and Merkle paths of the same lengths, and if their
+    /// query openings nest the same number of batches, matrices and values.
+    pub fn same_shape(&self, other: &Self) -> bool {
+        let (fri, other_fri) = (&self.fri_proof, &other.fri_proof);
+        let same_fri_shape = fri.commit_phase_commits.len() == other_fri.commit_phase_commits.len()
+            && same_shape_by(&fri.query_proofs, &other_fri.query_proofs, |a, b| {
+                same_shape_by(&a.commit_phase_openings, &b.commit_phase_openings, |a, b| {
+                    a.opening_proof.len() == b.opening_proof.len()
+                })
+            });
+        same_fri_shape
+            && same_shape_by(&self.query_openings, &other.query_openings, |a, b| {
+                same_shape_by(a, b, |a, b| {
+                    a.opening_proof.len() == b.opening_proof.len()
+                        && same_shape_by(&a.opened_values, &b.opened_values, |a, b| {
+                            same_shape_by(a, b, |a, b| a.len() == b.len())
+                        })
+                })
+            })
+    }
+}
+
+/// Whether `a` and `b` have the same length and `same` holds for each pair of their elements.
+fn same_shape_by<T>(a: &[T], b: &[T], same: impl Fn(&T, &T) -> bool) -> bool {
+    a.len() == b.len() && a.iter().zip(b).all(|(a, b)| same(a, b))
+}
+
+impl<C: CircuitConfig, H: FieldHasherVariable<C>> FriProofVariable<C, H> {
+    /// The number of queries, which is the number of query proofs.
+    pub fn num_queries(&self) -> usize {
+        self.query_proofs.len()
+    }
+
+    /// The number of commit-phase rounds, which is the number of commit-phase commitments.
+    pub fn num_commit_phases(&self) -> usize {
+        self.commit_phase_commits.len()
+    }
+
+    /// Checks that the proof has one commit-phase commitment per folding round, `expected_queries`
+    /// query proofs, and one commit-phase opening per folding round in each query proof.
+    ///
+    /// This is meant to be called before the verifier emits any constraints for the proof, so that
+    /// a malformed proof is rejected with a clear error rather than in the middle of verification.
+    pub fn validate_shape(
+        &self,
+        expected_rounds: usize,
+        expected_queries: usize,
+    ) -> Result<(), FriProofShapeError> {
+        if self.num_commit_phases() != expected_rounds {
+            return Err(FriProofShapeError::CommitPhaseCommitsMismatch(
+                expected_rounds,
+                self.num_commit_phases(),
+            ));
+        }
+        if self.num_queries() != expected_queries {
+            return Err(FriProofShapeError::QueryProofsMismatch(
+                expected_queries,
+                self.num_queries(),
+            ));
+        }
+        for (query, query_proof) in self.query_proofs.iter().enumerate() {
+            let found = query_proof.commit_phase_openings.len();
+            if found != expected_rounds {
+                return Err(FriProofShapeError::QueryOpeningsMismatch {
+                    query,
+                    expected: expected_rounds,
+                    found,
+                });
+            }
+        }
+        Ok(())
+    }
+}
+
+impl<C: CircuitConfig> FriChallenges<C> {
+    /// Asserts that there is exactly one folding challenge per commit-phase round.
+    ///
+    /// This FRI variant folds every round with a commitment, so there are no normalize rounds.
+    /// [`TwoAdicPcsProofVariable::validate_shape`] checks the proof, not the challenges sampled
+    /// from it.
+    pub fn assert_betas_shape(&self, commit_rounds: usize) {
+        assert_eq!(self.betas.len(), commit_rounds, "one beta is expected per commit-phase round");
+    }
+}
+
+impl<C: CircuitConfig, H: FieldHasherVariable<C>> FriQueryProofVariable<C, H> {
+    /// Asserts that the query folds through every commit-phase round, so that the folded
+    /// evaluation has the degree of the final polynomial.
+    ///
+    /// A query with fewer openings would stop folding early and compare an evaluation of a
+    /// higher-degree polynomial against the final polynomial.
+    pub fn assert_folds_to_final_poly(&self, commit_rounds: usize) {
+        assert_eq!(
+            self.commit_phase_openings.len(),
+            commit_rounds,
+            "one opening is expected per commit-phase round"
+        );
+    }
+}
+
+impl<C: CircuitConfig<F = BabyBear>, H: FieldHasherVariable<C>> BatchOpeningVariable<C, H> {
+    /// Constrains every opened value to be a canonical field element.
+    pub fn range_check_openings(&self, builder: &mut Builder<C>) {
+        for value in self.flatten_opened_values() {
+            let bits = C::num2bits(builder, value, 32);
+            assert_canonical_bits(builder, &bits);
+        }
+    }
+}
+
+impl<C: CircuitConfig, H: FieldHasherVariable<C>> BatchOpeningVariable<C, H> {
+    /// The opened values of every matrix in the batch, one matrix after the other.
+    ///
+    /// When the matrices of the batch share a height, this is exactly the leaf that the opening
+    /// proof authenticates. Otherwise, `verify_batch` hashes the values of each group of matrices
+    /// of the same padded height separately, in this order within each group.
+    pub fn flatten_opened_values(&self) -> Vec<Felt<C::F>> {
+        self.opened_values.iter().flatten().flatten().copied().collect()
+    }
+
+    /// Checks that the opening is non-empty and that its Merkle authentication path matches the
+    /// depth of a commitment whose tallest matrix has height `2^log_max_height`.
+    pub fn verify_shape(&self, log_max_height: usize) -> Result<(), BatchOpeningShapeError> {
+        if self.opened_values.is_empty() {
+            return Err(BatchOpeningShapeError::EmptyOpenedValues);
+        }
+        if self.opening_proof.is_empty() {
+            return Err(BatchOpeningShapeError::EmptyOpeningProof);
+        }
+        if self.opening_proof.len() != log_max_height {
+            return Err(BatchOpeningShapeError::OpeningProofLengthMismatch(
+                log_max_height,
+                self.opening_proof.len(),
+            ));
+        }
+        Ok(())
+    }
+
+    /// Checks the shape of the opening against the matrices committed in `round`: one opening per
+    /// matrix, each with one value per column, and a Merkle path as deep as the tallest matrix.
+    pub fn verify_round_shape(
+        &self,
+        round: &TwoAdicPcsRoundVariable<C, H>,
+        log_blowup: usize,
+    ) -> Result<(), BatchOpeningShapeError> {
+        self.verify_shape(round.log_max_height(log_blowup))?;
+        let mats = &round.domains_points_and_opens;
+        if self.opened_values.len() != mats.len() {
+            return Err(BatchOpeningShapeError::MatricesMismatch(
+                mats.len(),
+                self.opened_values.len(),
+            ));
+        }
+        for (matrix, (opening, mat)) in self.opened_values.iter().zip(mats).enumerate() {
+            if opening.len() != mat.dims.width {
+                return Err(BatchOpeningShapeError::WidthMismatch {
+                    matrix,
+                    expected: mat.dims.width,
+                    found: opening.len(),
+                });
+            }
+        }
+        Ok(())
+    }
+}
+
+impl<C: CircuitConfig> TwoAdicPcsMatsVariable<C> {
+    /// Creates the openings of a matrix of dimensions `dims` at `points`, checking that the matrix
+    /// fills `domain` and that `values` holds one value per column at each point.
+    pub fn new(
+        domain: TwoAdicMultiplicativeCoset<C::F>,
+        points: Vec<Ext<C::F, C::EF>>,
+        values: Vec<Vec<Ext<C::F, C::EF>>>,
+        dims: Dimensions,
+    ) -> Result<Self, MatsShapeError> {
+        if values.len() != points.len() {
+            return Err(MatsShapeError::PointsMismatch(points.len(), values.len()));
+        }
+        if dims.height != 1 << domain.log_n {
+            return Err(MatsShapeError::HeightMismatch(1 << domain.log_n, dims.height));
+        }
+        for (point, opening) in values.iter().enumerate() {
+            if opening.len() != dims.width {
+                return Err(MatsShapeError::WidthMismatch {
+                    point,
+                    expected: dims.width,
+                    found: opening.len(),
+                });
+            }
+        }
+        Ok(Self { domain, points, values, dims })
+    }
+}
+
+impl<C: CircuitConfig, H: FieldHasherVariable<C>> TwoAdicPcsRoundVariable<C, H> {
+    /// The log-height of the tallest matrix committed in this round, after the LDE blowup.
+    ///
+    /// This is the depth of the Merkle tree, and so the length of every opening proof.
+    pub fn log_max_height(&self, log_blowup: usize) -> usize {
+        self.domains_points_and_opens
+            .iter()
+            .map(|mat| mat.domain.log_n + log_blowup)
+            .max()
+            .expect("Empty batch?")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::marker::PhantomData;
+
+    use hashbrown::HashMap;
+    use p3_baby_bear::BabyBear;
+    use p3_commit::TwoAdicMultiplicativeCoset;
+    use p3_field::AbstractField;
+    use p3_matrix::Dimensions;
+    use sp1_recursion_compiler::{
+        config::InnerConfig,
+        ir::{Builder, DslIr, Ext, Felt},
+    };
+    use sp1_recursion_core_v2::DIGEST_SIZE;
+    use sp1_stark::baby_bear_poseidon2::BabyBearPoseidon2;
+
+    use super::{
+        DomainSeparationTags, FriCommitPhaseProofStepVariable, FriProofShapeError,
+        FriProofVariable, FriQueryProofVariable, HashLayout, MatsShapeError,
+        TwoAdicPcsMatsVariable, VerifyingKeyVariable, VkDigestMemo,
+    };
+    use crate::{
+        challenger::CanSampleVariable, hash::FieldHasherVariable, utils::tests::run_test_recursion,
+        BabyBearFriConfigVariable,
+    };
+
+    /// A verifying key whose single preprocessed chip has a domain of size `2^log_n`.
+    fn vk_variable(
+        builder: &mut Builder<InnerConfig>,
+        log_n: usize,
+    ) -> VerifyingKeyVariable<InnerConfig, BabyBearPoseidon2> {
+        let zero: Felt<_> = builder.eval(BabyBear::zero());
+        let domain = TwoAdicMultiplicativeCoset { log_n, shift: BabyBear::generator() };
+        let dimensions = Dimensions { width: 1, height: 1 << log_n };
+        VerifyingKeyVariable {
+            commitment: [zero; DIGEST_SIZE],
+            pc_start: zero,
+            chip_information: vec![("Program".to_string(), domain, dimensions)],
+            chip_ordering: HashMap::from([("Program".to_string(), 0)]),
+            _phantom: PhantomData,
+        }
+    }
+
+    /// Samples from a challenger that has observed a verifying key whose single preprocessed
+    /// chip has a domain of size `2^log_n`, observing the chip information only if `full`.
+    fn sample_after_vk(
+        builder: &mut Builder<InnerConfig>,
+        log_n: usize,
+        full: bool,
+    ) -> Felt<BabyBear> {
+        let vk = vk_variable(builder, log_n);
+        let mut challenger = BabyBearPoseidon2::default().challenger_variable(builder);
+        if full {
+            vk.observe_full(builder, &mut challenger);
+        } else {
+            vk.observe_into(builder, &mut challenger);
+        }
+        challenger.sample(builder)
+    }
+
+    #[test]
+    fn test_observe_full_separates_chip_information() {
+        let mut builder = Builder::<InnerConfig>::default();
+        let partial = sample_after_vk(&mut builder, 3, false);
+        let other_partial = sample_after_vk(&mut builder, 4, false);
+        builder.assert_felt_eq(partial, other_partial);
+
+        let full = sample_after_vk(&mut builder, 3, true);
+        let other_full = sample_after_vk(&mut builder, 4, true);
+        builder.assert_felt_ne(full, other_full);
+
+        run_test_recursion(builder.operations, None);
+    }
+
+    #[test]
+    fn test_vk_hash_cached() {
+        let num_permutations = |builder: &Builder<InnerConfig>| {
+            builder
+                .operations
+                .vec
+                .iter()
+                .filter(|op| matches!(op, DslIr::CircuitV2Poseidon2PermuteBabyBear(_)))
+                .count()
+        };
+
+        let mut builder = Builder::<InnerConfig>::default();
+        let vk = vk_variable(&mut builder, 3);
+        let mut memo = VkDigestMemo::new(&vk);
+        let before = num_permutations(&builder);
+        let digest = memo.hash(&mut builder, HashLayout::V1);
+        let after_first = num_permutations(&builder);
+        assert!(after_first > before);
+        let cached = memo.hash(&mut builder, HashLayout::V1);
+        assert_eq!(num_permutations(&builder), after_first);
+        BabyBearPoseidon2::assert_digest_eq(&mut builder, digest, cached);
+
+        // A fresh memo hashes the key again.
+        VkDigestMemo::new(&vk).hash(&mut builder, HashLayout::V1);
+        assert_eq!(num_permutations(&builder), 2 * after_first - before);
+
+        run_test_recursion(builder.operations, None);
+    }
+
+    #[test]
+    fn test_vk_hash_layouts_differ() {
+        let mut builder = Builder::<InnerConfig>::default();
+        let vk = vk_variable(&mut builder, 3);
+        let mut memo = VkDigestMemo::new(&vk);
+        let v1 = memo.hash(&mut builder, HashLayout::V1);
+        let v2 = memo.hash(&mut builder, HashLayout::V2);
+        assert_eq!(memo.digests.len(), 2);
+        // The digests are equal only if every element is, so their difference at the first
+        // element is enough to tell the layouts apart.
+        builder.assert_felt_ne(v1[0], v2[0]);
+
+        run_test_recursion(builder.operations, None);
+    }
+
+    /// A FRI proof with `rounds` commit-phase commitments and a query proof with the given number
+    /// of openings for each element of `openings`.
+    fn fri_proof(
+        builder: &mut Builder<InnerConfig>,
+        rounds: usize,
+        openings: &[usize],
+    ) -> FriProofVariable<InnerConfig, BabyBearPoseidon2> {
+        let zero: Felt<_> = builder.eval(BabyBear::zero());
+        let zero_ext: Ext<_, _> = builder.eval(zero);
+        let step =
+            FriCommitPhaseProofStepVariable { sibling_value: zero_ext, opening_proof: vec![] };
+        FriProofVariable {
+            commit_phase_commits: vec![[zero; DIGEST_SIZE]; rounds],
+            query_proofs: openings
+                .iter()
+                .map(|&len| FriQueryProofVariable {
+                    commit_phase_openings: vec![step.clone(); len],
+                })
+                .collect(),
+            final_poly: zero_ext,
+            pow_witness: zero,
+        }
+    }
+
+    #[test]
+    fn test_fri_proof_counts() {
+        let mut builder = Builder::<InnerConfig>::default();
+        let proof = fri_proof(&mut builder, 3, &[3, 3, 3, 3]);
+        assert_eq!(proof.num_commit_phases(), 3);
+        assert_eq!(proof.num_queries(), 4);
+    }
+
+    #[test]
+    fn test_fri_proof_validate_shape() {
+        let mut builder = Builder::<InnerConfig>::default();
+        assert_eq!(fri_proof(&mut builder, 3, &[3, 3]).validate_shape(3, 2), Ok(()));
+        assert_eq!(
+            fri_proof(&mut builder, 2, &[3, 3]).validate_shape(3, 2),
+            Err(FriProofShapeError::CommitPhaseCommitsMismatch(3, 2))
+        );
+        assert_eq!(
+            fri_proof(&mut builder, 3, &[3]).validate_shape(3, 2),
+            Err(FriProofShapeError::QueryProofsMismatch(2, 1))
+        );
+        assert_eq!(
+            fri_proof(&mut builder, 3, &[3, 2]).validate_shape(3, 2),
+            Err(FriProofShapeError::QueryOpeningsMismatch { query: 1, expected: 3, found: 2 })
+        );
+    }
+
+    #[test]
+    fn test_mats_shape() {
+        let mut builder = Builder::<InnerConfig>::default();
+        let zero: Felt<_> = builder.eval(BabyBear::zero());
+        let zero_ext: Ext<_, _> = builder.eval(zero);
+        let domain = TwoAdicMultiplicativeCoset { log_n: 3, shift: BabyBear::one() };
+        let dims = Dimensions { width: 2, height: 8 };
+        let mats = |points: usize, values: Vec<usize>, dims: Dimensions| {
+            TwoAdicPcsMatsVariable::<InnerConfig>::new(
+                domain,
+                vec![zero_ext; points],
+                values.into_iter().map(|len| vec![zero_ext; len]).collect(),
+                dims,
+            )
+            .map(|mats| (mats.dims.width, mats.dims.height))
+        };
+
+        assert_eq!(mats(2, vec![2, 2], dims), Ok((2, 8)));
+        assert_eq!(mats(1, vec![2, 2], dims), Err(MatsShapeError::PointsMismatch(1, 2)));
+        assert_eq!(
+            mats(2, vec![2, 2], Dimensions { width: 2, height: 4 }),
+            Err(MatsShapeError::HeightMismatch(8, 4))
+        );
+        assert_eq!(
+            mats(2, vec![2, 3], dims),
+            Err(MatsShapeError::WidthMismatch { point: 1, expected: 2, found: 3 })
+        );
+    }
+
+    #[test]
+    fn test_observe_chip_ordering() {
+        let sample_after = |builder: &mut Builder<InnerConfig>, cpu_first: bool, observe: bool| {
+            let mut vk = vk_variable(builder, 3);
+            let (cpu, program) = if cpu_first { (0, 1) } else { (1, 0) };
+            vk.chip_ordering =
+                HashMap::from([("Cpu".to_string(), cpu), ("Program".to_string(), program)]);
+            let mut challenger = BabyBearPoseidon2::default().challenger_variable(builder);
+            let tags = DomainSeparationTags::default();
+            vk.observe_into_with_tags(builder, &mut challenger, tags, observe);
+            challenger.sample(builder)
+        };
+
+        let mut builder = Builder::<InnerConfig>::default();
+        let unobserved = sample_after(&mut builder, true, false);
+        let other_unobserved = sample_after(&mut builder, false, false);
+        builder.assert_felt_eq(unobserved, other_unobserved);
+
+        let observed = sample_after(&mut builder, true, true);
+        let other_observed = sample_after(&mut builder, false, true);
+        builder.assert_felt_ne(observed, other_observed);
+
+        run_test_recursion(builder.operations, None);
     }
 }
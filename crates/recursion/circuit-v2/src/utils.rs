@@ -15,22 +15,29 @@ use sp1_recursion_core_v2::{
 };
 use sp1_stark::Word;
 
+use crate::CircuitConfig;
+
 /// Register and commits the recursion public values.
+///
+/// If `context` is set, it is registered after the public values and hashed with them, so the
+/// committed digest binds the proof to it.
 pub fn commit_recursion_public_values<C: Config>(
     builder: &mut Builder<C>,
     public_values: &RecursionPublicValues<Felt<C::F>>,
+    context: Option<Felt<C::F>>,
 ) {
     let mut pv_elements: [Felt<_>; RECURSIVE_PROOF_NUM_PV_ELTS] =
         core::array::from_fn(|_| builder.uninit());
     *pv_elements.as_mut_slice().borrow_mut() = *public_values;
-    let pv_elms_no_digest = &pv_elements[0..NUM_PV_ELMS_TO_HASH];
+    let mut pv_elms_no_digest = pv_elements[0..NUM_PV_ELMS_TO_HASH].to_vec();
+    pv_elms_no_digest.extend(context);
 
     for value in pv_elms_no_digest.iter() {
         builder.register_public_value(*value);
     }
 
     // Hash the public values.
-    let pv_digest = builder.poseidon2_hash_v2(&pv_elements[0..NUM_PV_ELMS_TO_HASH]);
+    let pv_digest = builder.poseidon2_hash_v2(&pv_elms_no_digest);
     for element in pv_digest {
         builder.commit_public_value(element);
     }
@@ -115,6 +122,28 @@ pub fn words_to_bytes<T: Copy>(words: &[Word<T>]) -> Vec<T> {
     words.iter().flat_map(|w| w.0).collect::<Vec<_>>()
 }
 
+/// Asserts that the 32 little-endian `bits` encode a canonical BabyBear element, that is, an
+/// integer less than `p = 2^31 - 2^27 + 1`.
+///
+/// The integer is below `p` exactly when the top bit is zero and, if bits 27 to 30 are all set,
+/// the low 27 bits are all zero.
+pub fn assert_canonical_bits<C: CircuitConfig<F = BabyBear>>(
+    builder: &mut Builder<C>,
+    bits: &[C::Bit],
+) {
+    assert_eq!(bits.len(), 32);
+    let top_bit = C::bits2num(builder, [bits[31].clone()]);
+    builder.assert_felt_eq(top_bit, BabyBear::zero());
+
+    let mut high_bits_set: Felt<_> = builder.eval(BabyBear::one());
+    for bit in &bits[27..31] {
+        let bit = C::bits2num(builder, [bit.clone()]);
+        high_bits_set = builder.eval(high_bits_set * bit);
+    }
+    let low_bits = C::bits2num(builder, bits[..27].iter().cloned());
+    builder.assert_felt_eq(high_bits_set * low_bits, BabyBear::zero());
+}
+
 #[cfg(any(test, feature = "export-tests"))]
 pub(crate) mod tests {
     use std::sync::Arc;
@@ -122,7 +151,7 @@ pub(crate) mod tests {
     use sp1_core_machine::utils::{run_test_machine_with_prover, setup_logger};
     use sp1_recursion_compiler::{asm::AsmConfig, circuit::AsmCompiler, ir::DslIr};
 
-    use sp1_recursion_compiler::ir::TracedVec;
+    use sp1_recursion_compiler::ir::{Builder, Felt, TracedVec};
     use sp1_recursion_core_v2::{machine::RecursionAir, Runtime};
     use sp1_stark::{
         baby_bear_poseidon2::BabyBearPoseidon2, CpuProver, InnerChallenge, InnerVal, MachineProver,
@@ -178,4 +207,45 @@ pub(crate) mod tests {
     ) {
         run_test_recursion_with_prover::<CpuProver<_, _>>(operations, witness_stream)
     }
+
+    #[test]
+    fn test_commit_recursion_public_values_with_context() {
+        use std::borrow::Borrow;
+
+        use p3_field::AbstractField;
+        use sp1_recursion_compiler::config::InnerConfig;
+        use sp1_recursion_core_v2::air::{
+            RecursionPublicValues, NUM_PV_ELMS_TO_HASH, RECURSIVE_PROOF_NUM_PV_ELTS,
+        };
+
+        use super::commit_recursion_public_values;
+
+        // Returns the registered public values and the context, if one was committed.
+        let registered = |with_context: bool| {
+            let mut builder = Builder::<InnerConfig>::default();
+            let zero: Felt<_> = builder.eval(F::zero());
+            let elements = [zero; RECURSIVE_PROOF_NUM_PV_ELTS];
+            let public_values: &RecursionPublicValues<Felt<_>> = elements.as_slice().borrow();
+            let context = with_context.then(|| builder.eval(F::from_canonical_u32(7)));
+            commit_recursion_public_values(&mut builder, public_values, context);
+            let values = builder
+                .operations
+                .vec
+                .iter()
+                .filter_map(|op| match op {
+                    DslIr::RegisterPublicValue(value) => Some(*value),
+                    _ => None,
+                })
+                .collect::<Vec<_>>();
+            (values, context)
+        };
+
+        let (values, context) = registered(false);
+        assert_eq!(values.len(), NUM_PV_ELMS_TO_HASH);
+        assert!(context.is_none());
+
+        let (values, context) = registered(true);
+        assert_eq!(values.len(), NUM_PV_ELMS_TO_HASH + 1);
+        assert_eq!(values.last().copied(), context);
+    }
 }
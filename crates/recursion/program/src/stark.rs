@@ -38,6 +38,11 @@ pub trait StarkRecursiveVerifier<C: Config> {
         is_complete: impl Into<SymbolicVar<C::N>>,
     );
 
+    /// Verifies each shard proof with the same challenger.
+    ///
+    /// If `seed` is set, it is observed before the shards are verified, so the prover must have
+    /// observed the same values into its challenger first.
+    #[allow(clippy::too_many_arguments)]
     fn verify_shards(
         &self,
         builder: &mut Builder<C>,
@@ -46,10 +51,18 @@ pub trait StarkRecursiveVerifier<C: Config> {
         challenger: &mut DuplexChallengerVariable<C>,
         proofs: &Array<C, ShardProofVariable<C>>,
         is_complete: impl Into<SymbolicVar<C::N>> + Clone,
+        seed: Option<&[Felt<C::F>]>,
     ) {
         // Assert that the number of shards is not zero.
         builder.assert_usize_ne(proofs.len(), 0);
 
+        // Observe the seed.
+        if let Some(seed) = seed {
+            for value in seed {
+                challenger.observe(builder, *value);
+            }
+        }
+
         // Verify each shard.
         builder.range(0, proofs.len()).for_each(|i, builder| {
             let proof = builder.get(proofs, i);
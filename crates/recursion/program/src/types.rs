@@ -2,10 +2,7 @@ use p3_air::BaseAir;
 use p3_field::{AbstractExtensionField, AbstractField};
 use sp1_primitives::consts::WORD_SIZE;
 use sp1_recursion_compiler::prelude::*;
-use sp1_stark::{
-    air::{MachineAir, PV_DIGEST_NUM_WORDS},
-    AirOpenedValues, Chip, ChipOpenedValues, Word,
-};
+use sp1_stark::{air::MachineAir, AirOpenedValues, Chip, ChipOpenedValues, Word};
 
 use crate::fri::{
     types::{DigestVariable, FriConfigVariable, TwoAdicPcsProofVariable},
@@ -69,6 +66,15 @@ pub struct ChipOpening<C: Config> {
     pub log_degree: Var<C::N>,
 }
 
+/// The dimensions of a chip's openings, as [`ChipOpening::from_variable`] expects them.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ChipOpeningWidths {
+    pub preprocessed_width: usize,
+    pub main_width: usize,
+    pub permutation_width: usize,
+    pub num_quotient_chunks: usize,
+}
+
 /// Reference: [sp1_core_machine::stark::ChipOpenedValues]
 #[derive(DslVariable, Debug, Clone)]
 pub struct ChipOpenedValuesVariable<C: Config> {
@@ -93,8 +99,10 @@ pub struct Sha256DigestVariable<C: Config> {
 }
 
 impl<C: Config> Sha256DigestVariable<C> {
+    /// Flattens `words` into their bytes. The length follows `words`, so that a digest of another
+    /// width than [`sp1_stark::air::PV_DIGEST_NUM_WORDS`] is neither truncated nor padded.
     pub fn from_words(builder: &mut Builder<C>, words: &[Word<Felt<C::F>>]) -> Self {
-        let mut bytes = builder.array(PV_DIGEST_NUM_WORDS * WORD_SIZE);
+        let mut bytes = builder.array(words.len() * WORD_SIZE);
         for (i, word) in words.iter().enumerate() {
             for j in 0..WORD_SIZE {
                 let byte = word[j];
@@ -106,6 +114,20 @@ impl<C: Config> Sha256DigestVariable<C> {
 }
 
 impl<C: Config> ChipOpening<C> {
+    /// The widths of the openings of `chip`, which a caller can compare against the lengths of a
+    /// `ChipOpenedValuesVariable` before extracting it.
+    pub fn widths<A>(chip: &Chip<C::F, A>) -> ChipOpeningWidths
+    where
+        A: MachineAir<C::F>,
+    {
+        ChipOpeningWidths {
+            preprocessed_width: chip.preprocessed_width(),
+            main_width: chip.width(),
+            permutation_width: C::EF::D * chip.permutation_width(),
+            num_quotient_chunks: 1 << chip.log_quotient_degree(),
+        }
+    }
+
     /// Collect opening values from a dynamic array into vectors.
     ///
     /// This method is used to convert a `ChipOpenedValuesVariable` into a `ChipOpenedValues`, which
@@ -121,8 +143,21 @@ impl<C: Config> ChipOpening<C> {
     where
         A: MachineAir<C::F>,
     {
+        let ChipOpeningWidths {
+            preprocessed_width,
+            main_width,
+            permutation_width,
+            num_quotient_chunks,
+        } = Self::widths(chip);
+        debug_assert_len(&opening.preprocessed.local, preprocessed_width, "preprocessed.local");
+        debug_assert_len(&opening.preprocessed.next, preprocessed_width, "preprocessed.next");
+        debug_assert_len(&opening.main.local, main_width, "main.local");
+        debug_assert_len(&opening.main.next, main_width, "main.next");
+        debug_assert_len(&opening.permutation.local, permutation_width, "permutation.local");
+        debug_assert_len(&opening.permutation.next, permutation_width, "permutation.next");
+        debug_assert_len(&opening.quotient, num_quotient_chunks, "quotient");
+
         let mut preprocessed = AirOpenedValues { local: vec![], next: vec![] };
-        let preprocessed_width = chip.preprocessed_width();
         // Assert that the length of the dynamic arrays match the expected length of the vectors.
         builder.assert_usize_eq(preprocessed_width, opening.preprocessed.local.len());
         builder.assert_usize_eq(preprocessed_width, opening.preprocessed.next.len());
@@ -133,7 +168,6 @@ impl<C: Config> ChipOpening<C> {
         }
 
         let mut main = AirOpenedValues { local: vec![], next: vec![] };
-        let main_width = chip.width();
         // Assert that the length of the dynamic arrays match the expected length of the vectors.
         builder.assert_usize_eq(main_width, opening.main.local.len());
         builder.assert_usize_eq(main_width, opening.main.next.len());
@@ -144,7 +178,6 @@ impl<C: Config> ChipOpening<C> {
         }
 
         let mut permutation = AirOpenedValues { local: vec![], next: vec![] };
-        let permutation_width = C::EF::D * chip.permutation_width();
         // Assert that the length of the dynamic arrays match the expected length of the vectors.
         builder.assert_usize_eq(permutation_width, opening.permutation.local.len());
         builder.assert_usize_eq(permutation_width, opening.permutation.next.len());
@@ -154,7 +187,6 @@ impl<C: Config> ChipOpening<C> {
             permutation.next.push(builder.get(&opening.permutation.next, i));
         }
 
-        let num_quotient_chunks = 1 << chip.log_quotient_degree();
         let mut quotient = vec![];
         // Assert that the length of the quotient chunk arrays match the expected length.
         builder.assert_usize_eq(num_quotient_chunks, opening.quotient.len());
@@ -183,6 +215,18 @@ impl<C: Config> ChipOpening<C> {
     }
 }
 
+/// Checks in debug builds that `array` has `expected` elements whenever its length is known while
+/// building, so that a misshapen opening fails here instead of being read out of range.
+fn debug_assert_len<C: Config, V: MemVariable<C>>(
+    array: &Array<C, V>,
+    expected: usize,
+    name: &str,
+) {
+    if let Usize::Const(len) = array.len() {
+        debug_assert_eq!(len, expected, "opening {name} has {len} elements, expected {expected}");
+    }
+}
+
 impl<C: Config> FromConstant<C> for AirOpenedValuesVariable<C> {
     type Constant = AirOpenedValues<C::EF>;
 
@@ -226,3 +270,73 @@ impl<C: Config> FriConfigVariable<C> {
         builder.get(&self.generators, bits)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::array;
+
+    use p3_field::AbstractField;
+    use sp1_core_machine::riscv::RiscvAir;
+    use sp1_recursion_compiler::{
+        asm::{AsmBuilder, AsmConfig},
+        ir::{Ext, Felt},
+        prelude::ExtConst,
+    };
+    use sp1_recursion_core::stark::utils::{run_test_recursion, TestConfig};
+    use sp1_stark::{baby_bear_poseidon2::BabyBearPoseidon2, StarkGenericConfig, Word};
+
+    use super::{
+        AirOpenedValuesVariable, ChipOpenedValuesVariable, ChipOpening, Sha256DigestVariable,
+    };
+
+    type SC = BabyBearPoseidon2;
+    type F = <SC as StarkGenericConfig>::Val;
+    type EF = <SC as StarkGenericConfig>::Challenge;
+
+    #[test]
+    fn test_sha256_digest_from_wider_words() {
+        // Two more words than the standard digest, which used to overflow the array.
+        let mut builder = AsmBuilder::<F, EF>::default();
+        let words = (0..10)
+            .map(|i| Word(array::from_fn(|j| builder.eval(F::from_canonical_usize(4 * i + j)))))
+            .collect::<Vec<Word<Felt<F>>>>();
+        let digest = Sha256DigestVariable::from_words(&mut builder, &words);
+        builder.assert_usize_eq(digest.bytes.len(), 40);
+        let last: Felt<_> = builder.get(&digest.bytes, 39);
+        builder.assert_felt_eq(last, F::from_canonical_u32(39));
+        builder.halt();
+
+        let program = builder.compile_program();
+        run_test_recursion(program, None, TestConfig::All);
+    }
+
+    #[test]
+    #[should_panic(expected = "opening permutation.local has")]
+    fn test_chip_opening_short_permutation() {
+        let machine = RiscvAir::<F>::machine(SC::default());
+        let chip = machine.chips().iter().find(|chip| chip.permutation_width() > 0).unwrap();
+        let widths = ChipOpening::<AsmConfig<F, EF>>::widths(chip);
+
+        let mut builder = AsmBuilder::<F, EF>::default();
+        let zero: Ext<_, _> = builder.eval(EF::zero().cons());
+        let mut opened = |len: usize| AirOpenedValuesVariable {
+            local: builder.vec(vec![zero; len]),
+            next: builder.vec(vec![zero; len]),
+        };
+        let preprocessed = opened(widths.preprocessed_width);
+        let main = opened(widths.main_width);
+        // One element short of what the chip's permutation trace needs.
+        let permutation = opened(widths.permutation_width - 1);
+        let chunk = builder.vec(vec![zero; 4]);
+        let opening = ChipOpenedValuesVariable {
+            preprocessed,
+            main,
+            permutation,
+            quotient: builder.vec(vec![chunk; widths.num_quotient_chunks]),
+            cumulative_sum: zero,
+            log_degree: builder.eval(F::zero()),
+        };
+
+        ChipOpening::from_variable(&mut builder, chip, &opening);
+    }
+}
@@ -4,6 +4,7 @@ use std::borrow::{Borrow, BorrowMut};
 use itertools::Itertools;
 use p3_field::{AbstractField, PrimeField32};
 use serde::{Deserialize, Serialize};
+use serde_with::serde_as;
 
 use crate::{Word, PROOF_MAX_NUM_PVS};
 
@@ -17,11 +18,20 @@ pub const PV_DIGEST_NUM_WORDS: usize = 8;
 pub const POSEIDON_NUM_WORDS: usize = 8;
 
 /// Stores all of a shard proof's public values.
-#[derive(Serialize, Deserialize, Clone, Copy, Default, Debug)]
+///
+/// The committed value digest has `N` words, so a program committing a shorter output, such as a
+/// 20-byte address, does not have to pad it to [`PV_DIGEST_NUM_WORDS`].
+#[serde_as]
+#[derive(Serialize, Deserialize, Clone, Copy, Debug)]
+#[serde(bound(
+    serialize = "W: Serialize, T: Serialize",
+    deserialize = "W: Deserialize<'de>, T: Deserialize<'de>"
+))]
 #[repr(C)]
-pub struct PublicValues<W, T> {
+pub struct PublicValues<W, T, const N: usize = PV_DIGEST_NUM_WORDS> {
     /// The hash of all the bytes that the guest program has written to public values.
-    pub committed_value_digest: [W; PV_DIGEST_NUM_WORDS],
+    #[serde_as(as = "[_; N]")]
+    pub committed_value_digest: [W; N],
 
     /// The hash of all deferred proofs that have been witnessed in the VM. It will be rebuilt in
     /// recursive verification as the proofs get verified. The hash itself is a rolling poseidon2
@@ -56,15 +66,33 @@ pub struct PublicValues<W, T> {
     pub last_finalize_addr_bits: [T; 32],
 }
 
-impl PublicValues<u32, u32> {
+impl<W: Default, T: Default, const N: usize> Default for PublicValues<W, T, N> {
+    fn default() -> Self {
+        Self {
+            committed_value_digest: core::array::from_fn(|_| W::default()),
+            deferred_proofs_digest: Default::default(),
+            start_pc: T::default(),
+            next_pc: T::default(),
+            exit_code: T::default(),
+            shard: T::default(),
+            execution_shard: T::default(),
+            previous_init_addr_bits: Default::default(),
+            last_init_addr_bits: Default::default(),
+            previous_finalize_addr_bits: Default::default(),
+            last_finalize_addr_bits: Default::default(),
+        }
+    }
+}
+
+impl<const N: usize> PublicValues<u32, u32, N> {
     /// Convert the public values into a vector of field elements.  This function will pad the
     /// vector to the maximum number of public values.
     #[must_use]
     pub fn to_vec<F: AbstractField>(&self) -> Vec<F> {
         let mut ret = vec![F::zero(); PROOF_MAX_NUM_PVS];
 
-        let field_values = PublicValues::<Word<F>, F>::from(*self);
-        let ret_ref_mut: &mut PublicValues<Word<F>, F> = ret.as_mut_slice().borrow_mut();
+        let field_values = PublicValues::<Word<F>, F, N>::from(*self);
+        let ret_ref_mut: &mut PublicValues<Word<F>, F, N> = ret.as_mut_slice().borrow_mut();
         *ret_ref_mut = field_values;
         ret
     }
@@ -85,7 +113,23 @@ impl PublicValues<u32, u32> {
     }
 }
 
-impl<F: PrimeField32> PublicValues<Word<F>, F> {
+impl<F: Clone, const N: usize> PublicValues<Word<F>, F, N> {
+    /// Reads the public values from the front of a vector of field elements, as laid out by
+    /// [`PublicValues::to_vec`]. Panics, naming both lengths, if `values` is too short.
+    #[must_use]
+    pub fn from_vec(values: &[F]) -> Self {
+        let expected = size_of::<PublicValues<Word<u8>, u8, N>>();
+        assert!(
+            values.len() >= expected,
+            "public values vec has {} elements, expected at least {expected}",
+            values.len()
+        );
+        let public_values: &Self = values.borrow();
+        public_values.clone()
+    }
+}
+
+impl<F: PrimeField32, const N: usize> PublicValues<Word<F>, F, N> {
     /// Returns the commit digest as a vector of little-endian bytes.
     pub fn commit_digest_bytes(&self) -> Vec<u8> {
         self.committed_value_digest
@@ -95,32 +139,35 @@ impl<F: PrimeField32> PublicValues<Word<F>, F> {
     }
 }
 
-impl<T: Clone> Borrow<PublicValues<Word<T>, T>> for [T] {
-    fn borrow(&self) -> &PublicValues<Word<T>, T> {
-        let size = std::mem::size_of::<PublicValues<Word<u8>, u8>>();
+impl<T: Clone, const N: usize> Borrow<PublicValues<Word<T>, T, N>> for [T] {
+    fn borrow(&self) -> &PublicValues<Word<T>, T, N> {
+        let size = std::mem::size_of::<PublicValues<Word<u8>, u8, N>>();
         debug_assert!(self.len() >= size);
         let slice = &self[0..size];
-        let (prefix, shorts, _suffix) = unsafe { slice.align_to::<PublicValues<Word<T>, T>>() };
+        let (prefix, shorts, _suffix) = unsafe { slice.align_to::<PublicValues<Word<T>, T, N>>() };
         debug_assert!(prefix.is_empty(), "Alignment should match");
         debug_assert_eq!(shorts.len(), 1);
         &shorts[0]
     }
 }
 
-impl<T: Clone> BorrowMut<PublicValues<Word<T>, T>> for [T] {
-    fn borrow_mut(&mut self) -> &mut PublicValues<Word<T>, T> {
-        let size = std::mem::size_of::<PublicValues<Word<u8>, u8>>();
+impl<T: Clone, const N: usize> BorrowMut<PublicValues<Word<T>, T, N>> for [T] {
+    fn borrow_mut(&mut self) -> &mut PublicValues<Word<T>, T, N> {
+        let size = std::mem::size_of::<PublicValues<Word<u8>, u8, N>>();
         debug_assert!(self.len() >= size);
         let slice = &mut self[0..size];
-        let (prefix, shorts, _suffix) = unsafe { slice.align_to_mut::<PublicValues<Word<T>, T>>() };
+        let (prefix, shorts, _suffix) =
+            unsafe { slice.align_to_mut::<PublicValues<Word<T>, T, N>>() };
         debug_assert!(prefix.is_empty(), "Alignment should match");
         debug_assert_eq!(shorts.len(), 1);
         &mut shorts[0]
     }
 }
 
-impl<F: AbstractField> From<PublicValues<u32, u32>> for PublicValues<Word<F>, F> {
-    fn from(value: PublicValues<u32, u32>) -> Self {
+impl<F: AbstractField, const N: usize> From<PublicValues<u32, u32, N>>
+    for PublicValues<Word<F>, F, N>
+{
+    fn from(value: PublicValues<u32, u32, N>) -> Self {
         let PublicValues {
             committed_value_digest,
             deferred_proofs_digest,
@@ -135,7 +182,7 @@ impl<F: AbstractField> From<PublicValues<u32, u32>> for PublicValues<Word<F>, F>
             last_finalize_addr_bits,
         } = value;
 
-        let committed_value_digest: [_; PV_DIGEST_NUM_WORDS] =
+        let committed_value_digest: [_; N] =
             core::array::from_fn(|i| Word::from(committed_value_digest[i]));
 
         let deferred_proofs_digest: [_; POSEIDON_NUM_WORDS] =
@@ -169,11 +216,46 @@ impl<F: AbstractField> From<PublicValues<u32, u32>> for PublicValues<Word<F>, F>
 
 #[cfg(test)]
 mod tests {
-    use crate::air::public_values;
+    use p3_baby_bear::BabyBear;
+    use p3_field::AbstractField;
+
+    use super::PublicValues;
+    use crate::{air::public_values, Word};
 
     /// Check that the [`PI_DIGEST_NUM_WORDS`] number match the zkVM crate's.
     #[test]
     fn test_public_values_digest_num_words_consistency_zkvm() {
         assert_eq!(public_values::PV_DIGEST_NUM_WORDS, sp1_zkvm::PV_DIGEST_NUM_WORDS);
     }
+
+    #[test]
+    fn test_shorter_digest_round_trip() {
+        const ADDRESS_NUM_WORDS: usize = 5;
+        let mut public_values = PublicValues::<u32, u32, ADDRESS_NUM_WORDS>::default();
+        public_values.committed_value_digest = [0x0403_0201, 0x0807_0605, 0, u32::MAX, 0x7f];
+        public_values.exit_code = 1;
+        public_values.shard = 3;
+
+        let values = public_values.to_vec::<BabyBear>();
+        let read = PublicValues::<Word<BabyBear>, BabyBear, ADDRESS_NUM_WORDS>::from_vec(&values);
+        assert_eq!(read.commit_digest_bytes().len(), ADDRESS_NUM_WORDS * 4);
+        assert_eq!(read.commit_digest_bytes()[..8], [1, 2, 3, 4, 5, 6, 7, 8]);
+        assert_eq!(read.exit_code, BabyBear::one());
+        assert_eq!(read.shard, BabyBear::from_canonical_u32(3));
+
+        // The shorter digest shifts every later field down by the missing words.
+        let full_size = super::SP1_PROOF_NUM_PV_ELTS;
+        let short_size = core::mem::size_of::<PublicValues<Word<u8>, u8, ADDRESS_NUM_WORDS>>();
+        assert_eq!(
+            full_size - short_size,
+            (public_values::PV_DIGEST_NUM_WORDS - ADDRESS_NUM_WORDS) * 4
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "public values vec has 40 elements, expected at least")]
+    fn test_from_vec_truncated() {
+        let values = PublicValues::<u32, u32>::default().to_vec::<BabyBear>();
+        let _ = PublicValues::<Word<BabyBear>, BabyBear>::from_vec(&values[..40]);
+    }
 }
@@ -69,6 +69,15 @@ pub trait StarkGenericConfig: 'static + Send + Sync + Serialize + DeserializeOwn
 
     /// Initialize a new challenger.
     fn challenger(&self) -> Self::Challenger;
+
+    /// Initialize a new challenger whose transcript starts by absorbing `iv`.
+    ///
+    /// The prover and verifier must use the same IV. An empty IV gives [`Self::challenger`].
+    fn challenger_with_iv(&self, iv: &[Self::Val]) -> Self::Challenger {
+        let mut challenger = self.challenger();
+        challenger.observe_slice(iv);
+        challenger
+    }
 }
 
 pub struct UniConfig<SC>(pub SC);
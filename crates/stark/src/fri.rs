@@ -0,0 +1,52 @@
+use p3_fri::verifier::FriChallenges;
+use serde::{Deserialize, Serialize};
+
+/// The concrete values of the FRI challenges, in a serializable form.
+///
+/// Dumping these from the host verifier and from a run of the recursive verifier lets the two be
+/// diffed when they disagree on a proof.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FriChallengeValues<F> {
+    /// The sampled query indices.
+    pub query_indices: Vec<usize>,
+    /// The folding challenges, one per commit-phase round.
+    pub betas: Vec<F>,
+}
+
+impl<F: Clone> From<&FriChallenges<F>> for FriChallengeValues<F> {
+    fn from(challenges: &FriChallenges<F>) -> Self {
+        Self { query_indices: challenges.query_indices.clone(), betas: challenges.betas.clone() }
+    }
+}
+
+impl<F> From<FriChallengeValues<F>> for FriChallenges<F> {
+    fn from(values: FriChallengeValues<F>) -> Self {
+        Self { query_indices: values.query_indices, betas: values.betas }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::{rngs::StdRng, Rng, SeedableRng};
+
+    use super::*;
+    use crate::InnerChallenge;
+
+    #[test]
+    fn test_fri_challenge_values_round_trip() {
+        let mut rng = StdRng::seed_from_u64(0xC0FFEE);
+        let challenges = FriChallenges {
+            query_indices: (0..4).map(|_| rng.gen_range(0..1 << 10)).collect(),
+            betas: (0..10).map(|_| rng.gen::<InnerChallenge>()).collect(),
+        };
+
+        let values = FriChallengeValues::from(&challenges);
+        let bytes = bincode::serialize(&values).unwrap();
+        let decoded: FriChallengeValues<InnerChallenge> = bincode::deserialize(&bytes).unwrap();
+        assert_eq!(decoded, values);
+
+        let decoded = FriChallenges::from(decoded);
+        assert_eq!(decoded.query_indices, challenges.query_indices);
+        assert_eq!(decoded.betas, challenges.betas);
+    }
+}
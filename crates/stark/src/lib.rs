@@ -26,6 +26,7 @@ mod chip;
 mod config;
 mod debug;
 mod folder;
+mod fri;
 mod lookup;
 mod machine;
 mod opts;
@@ -33,6 +34,7 @@ mod permutation;
 mod prover;
 mod quotient;
 mod record;
+mod tags;
 mod types;
 mod util;
 mod verifier;
@@ -43,6 +45,7 @@ pub use chip::*;
 pub use config::*;
 pub use debug::*;
 pub use folder::*;
+pub use fri::*;
 pub use lookup::*;
 pub use machine::*;
 pub use opts::*;
@@ -50,6 +53,7 @@ pub use permutation::*;
 pub use prover::*;
 pub use quotient::*;
 pub use record::*;
+pub use tags::*;
 pub use types::*;
 pub use verifier::*;
 pub use word::*;
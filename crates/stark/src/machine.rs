@@ -1,7 +1,7 @@
 use hashbrown::HashMap;
 use itertools::Itertools;
 use p3_air::Air;
-use p3_challenger::{CanObserve, FieldChallenger};
+use p3_challenger::{CanObserve, CanSample, FieldChallenger};
 use p3_commit::Pcs;
 use p3_field::{AbstractExtensionField, AbstractField, Field, PrimeField32};
 use p3_matrix::{dense::RowMajorMatrix, Dimensions, Matrix};
@@ -14,12 +14,15 @@ use super::{debug_constraints, Dom};
 use crate::{
     air::{MachineAir, MachineProgram},
     lookup::{debug_interactions_with_all_chips, InteractionKind},
+    observe_optional_tag,
     record::MachineRecord,
-    DebugConstraintBuilder, ShardProof, VerifierConstraintFolder,
+    DebugConstraintBuilder, DomainSeparationTags, ShardProof, VerifierConstraintFolder,
+    NUM_PERMUTATION_CHALLENGES,
 };
 
 use super::{
-    Chip, Com, MachineProof, PcsProverData, StarkGenericConfig, Val, VerificationError, Verifier,
+    Chip, ChipReproduction, Com, MachineProof, PcsProverData, ReportedVerification,
+    StarkGenericConfig, Val, VerificationError, Verifier,
 };
 
 /// A chip in a machine.
@@ -63,8 +66,25 @@ pub struct StarkProvingKey<SC: StarkGenericConfig> {
 impl<SC: StarkGenericConfig> StarkProvingKey<SC> {
     /// Observes the values of the proving key into the challenger.
     pub fn observe_into(&self, challenger: &mut SC::Challenger) {
+        self.observe_into_with_tags(challenger, DomainSeparationTags::default(), false);
+    }
+
+    /// Observes the values of the proving key, preceded by the verifying key tag if it is enabled.
+    ///
+    /// If `chip_ordering` is set, the chip ordering is observed after the key, so that keys for
+    /// different chip subsets lead to different transcripts.
+    pub fn observe_into_with_tags(
+        &self,
+        challenger: &mut SC::Challenger,
+        tags: DomainSeparationTags,
+        chip_ordering: bool,
+    ) {
+        observe_optional_tag::<Val<SC>, _>(challenger, tags.vk);
         challenger.observe(self.commit.clone());
         challenger.observe(self.pc_start);
+        if chip_ordering {
+            challenger.observe_slice(&chip_ordering_inputs::<Val<SC>>(&self.chip_ordering));
+        }
     }
 }
 
@@ -81,16 +101,74 @@ pub struct StarkVerifyingKey<SC: StarkGenericConfig> {
     pub chip_information: Vec<(String, Dom<SC>, Dimensions)>,
     /// The chip ordering.
     pub chip_ordering: HashMap<String, usize>,
+    /// The quotient degree of every chip of the machine, in the order of [`StarkMachine::chips`].
+    pub chip_quotient_degrees: Vec<usize>,
 }
 
 impl<SC: StarkGenericConfig> StarkVerifyingKey<SC> {
+    /// Returns the quotient degree bound of every chip of the machine, in the order of
+    /// [`StarkMachine::chips`], so that proofs can be validated without the AIRs.
+    #[must_use]
+    pub fn chip_quotient_degrees(&self) -> Vec<usize> {
+        self.chip_quotient_degrees.clone()
+    }
+
     /// Observes the values of the verifying key into the challenger.
     pub fn observe_into(&self, challenger: &mut SC::Challenger) {
+        self.observe_into_with_tags(challenger, DomainSeparationTags::default(), false);
+    }
+
+    /// Observes the values of the verifying key, preceded by its tag if it is enabled.
+    ///
+    /// If `chip_ordering` is set, the chip ordering is observed after the key, so that keys for
+    /// different chip subsets lead to different transcripts.
+    pub fn observe_into_with_tags(
+        &self,
+        challenger: &mut SC::Challenger,
+        tags: DomainSeparationTags,
+        chip_ordering: bool,
+    ) {
+        observe_optional_tag::<Val<SC>, _>(challenger, tags.vk);
         challenger.observe(self.commit.clone());
         challenger.observe(self.pc_start);
+        if chip_ordering {
+            challenger.observe_slice(&chip_ordering_inputs::<Val<SC>>(&self.chip_ordering));
+        }
     }
 }
 
+/// The values describing a chip ordering: for each chip, sorted by name, the length of its name,
+/// the bytes of its name, and its index.
+#[must_use]
+pub fn chip_ordering_inputs<F: AbstractField>(chip_ordering: &HashMap<String, usize>) -> Vec<F> {
+    let mut inputs = Vec::new();
+    for (name, &index) in chip_ordering.iter().sorted() {
+        inputs.push(F::from_canonical_usize(name.len()));
+        inputs.extend(name.bytes().map(F::from_canonical_u8));
+        inputs.push(F::from_canonical_usize(index));
+    }
+    inputs
+}
+
+/// Derives a short fingerprint of a verifying key from its commitment and start pc.
+///
+/// The fingerprint is meant for identifying verifying keys in logs and telemetry, and is not a
+/// substitute for the verifying key hash. It is the first two elements sampled from a fresh
+/// challenger after observing the verifying key, so it only depends on `vk` and the config type.
+pub fn vk_fingerprint<SC: StarkGenericConfig + Default>(vk: &StarkVerifyingKey<SC>) -> [u8; 8]
+where
+    Val<SC>: PrimeField32,
+{
+    let mut challenger = SC::default().challenger();
+    vk.observe_into(&mut challenger);
+    let mut fingerprint = [0u8; 8];
+    for chunk in fingerprint.chunks_mut(4) {
+        let element: Val<SC> = challenger.sample();
+        chunk.copy_from_slice(&element.as_canonical_u32().to_le_bytes());
+    }
+    fingerprint
+}
+
 impl<SC: StarkGenericConfig> Debug for StarkVerifyingKey<SC> {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         f.debug_struct("VerifyingKey").finish()
@@ -103,6 +181,11 @@ impl<SC: StarkGenericConfig, A: MachineAir<Val<SC>>> StarkMachine<SC, A> {
         &self.chips
     }
 
+    /// Returns the quotient degree bound of every chip, in the order of [`Self::chips`].
+    pub fn chip_quotient_degrees(&self) -> impl Iterator<Item = usize> + '_ {
+        self.chips.iter().map(|chip| 1 << chip.log_quotient_degree())
+    }
+
     /// Returns the number of public values elements.
     pub const fn num_pv_elts(&self) -> usize {
         self.num_pv_elts
@@ -212,6 +295,8 @@ impl<SC: StarkGenericConfig, A: MachineAir<Val<SC>>> StarkMachine<SC, A> {
 
         let pc_start = program.pc_start();
 
+        let chip_quotient_degrees = self.chip_quotient_degrees().collect();
+
         (
             StarkProvingKey {
                 commit: commit.clone(),
@@ -220,7 +305,13 @@ impl<SC: StarkGenericConfig, A: MachineAir<Val<SC>>> StarkMachine<SC, A> {
                 data,
                 chip_ordering: chip_ordering.clone(),
             },
-            StarkVerifyingKey { commit, pc_start, chip_information, chip_ordering },
+            StarkVerifyingKey {
+                commit,
+                pc_start,
+                chip_information,
+                chip_ordering,
+                chip_quotient_degrees,
+            },
         )
     }
 
@@ -249,6 +340,24 @@ impl<SC: StarkGenericConfig, A: MachineAir<Val<SC>>> StarkMachine<SC, A> {
         &self.config
     }
 
+    /// Observes the verifying key, then the main commitment and public values of every shard.
+    ///
+    /// This is the part of the transcript shared by all shards, which the verifier replays before
+    /// checking any single shard.
+    pub fn observe_shards(
+        &self,
+        challenger: &mut SC::Challenger,
+        vk: &StarkVerifyingKey<SC>,
+        proofs: &[ShardProof<SC>],
+    ) {
+        // Observe the preprocessed commitment.
+        vk.observe_into(challenger);
+        for proof in proofs {
+            challenger.observe(proof.commitment.main_commit.clone());
+            challenger.observe_slice(&proof.public_values[0..self.num_pv_elts()]);
+        }
+    }
+
     /// Verify that a proof is complete and valid given a verifying key and a claimed digest.
     #[instrument("verify", level = "info", skip_all)]
     #[allow(clippy::match_bool)]
@@ -262,14 +371,8 @@ impl<SC: StarkGenericConfig, A: MachineAir<Val<SC>>> StarkMachine<SC, A> {
         SC::Challenger: Clone,
         A: for<'a> Air<VerifierConstraintFolder<'a, SC>>,
     {
-        // Observe the preprocessed commitment.
-        vk.observe_into(challenger);
-        tracing::debug_span!("observe challenges for all shards").in_scope(|| {
-            proof.shard_proofs.iter().for_each(|proof| {
-                challenger.observe(proof.commitment.main_commit.clone());
-                challenger.observe_slice(&proof.public_values[0..self.num_pv_elts()]);
-            });
-        });
+        tracing::debug_span!("observe challenges for all shards")
+            .in_scope(|| self.observe_shards(challenger, vk, &proof.shard_proofs));
 
         // Verify the shard proofs.
         if proof.shard_proofs.is_empty() {
@@ -308,6 +411,92 @@ impl<SC: StarkGenericConfig, A: MachineAir<Val<SC>>> StarkMachine<SC, A> {
         })
     }
 
+    /// Verify a proof whose Fiat-Shamir transcript starts from an external seed.
+    ///
+    /// If `seed` is set, it is observed by the challenger before anything else, so the prover must
+    /// have observed the same values into its challenger before proving. This binds the proof to
+    /// external randomness, such as the output of a beacon.
+    pub fn verify_with_seed(
+        &self,
+        vk: &StarkVerifyingKey<SC>,
+        proof: &MachineProof<SC>,
+        challenger: &mut SC::Challenger,
+        seed: Option<&[Val<SC>]>,
+    ) -> Result<(), MachineVerificationError<SC>>
+    where
+        SC::Challenger: Clone,
+        A: for<'a> Air<VerifierConstraintFolder<'a, SC>>,
+    {
+        if let Some(seed) = seed {
+            challenger.observe_slice(seed);
+        }
+        self.verify(vk, proof, challenger)
+    }
+
+    /// Verifies the shard at index `shard` of a proof and reports which checks passed, or which
+    /// check failed.
+    ///
+    /// This only checks the one shard, not the cumulative sum of the proof, so [`Self::verify`]
+    /// is still needed to accept a proof. Returns `None` if the shard does not exist.
+    pub fn verify_shard_with_report(
+        &self,
+        vk: &StarkVerifyingKey<SC>,
+        proof: &MachineProof<SC>,
+        shard: usize,
+    ) -> Option<ReportedVerification<SC>>
+    where
+        A: for<'a> Air<VerifierConstraintFolder<'a, SC>>,
+    {
+        let mut challenger = self.config.challenger();
+        self.observe_shards(&mut challenger, vk, &proof.shard_proofs);
+        let shard_proof = proof.shard_proofs.get(shard)?;
+        let chips = self.shard_chips_ordered(&shard_proof.chip_ordering).collect::<Vec<_>>();
+        Some(Verifier::verify_shard_with_report(
+            &self.config,
+            vk,
+            &chips,
+            &mut challenger,
+            shard_proof,
+        ))
+    }
+
+    /// Extracts a standalone reproduction of the constraint check of the chip named `chip_name`
+    /// in the shard at index `shard` of a proof.
+    ///
+    /// Returns `None` if the shard does not exist or does not contain the chip.
+    pub fn extract_chip_reproduction(
+        &self,
+        vk: &StarkVerifyingKey<SC>,
+        proof: &MachineProof<SC>,
+        shard: usize,
+        chip_name: &str,
+    ) -> Option<ChipReproduction<SC>> {
+        let mut challenger = self.config.challenger();
+        self.observe_shards(&mut challenger, vk, &proof.shard_proofs);
+        Verifier::<SC, A>::extract_chip_reproduction(
+            vk,
+            &mut challenger,
+            proof.shard_proofs.get(shard)?,
+            chip_name,
+        )
+    }
+
+    /// Reruns the constraint check captured by a [`ChipReproduction`] against the machine's chip.
+    pub fn reproduce_chip(
+        &self,
+        reproduction: &ChipReproduction<SC>,
+    ) -> Result<(), VerificationError<SC>>
+    where
+        A: for<'a> Air<VerifierConstraintFolder<'a, SC>>,
+    {
+        let chip = self
+            .chips
+            .iter()
+            .find(|chip| chip.name() == reproduction.chip_name)
+            .expect("the reproduction is for a chip of another machine");
+        Verifier::reproduce_chip(&self.config, chip, reproduction)
+    }
+
     /// Debugs the constraints of the given records.
     #[instrument("debug constraints", level = "debug", skip_all)]
     pub fn debug_constraints(
@@ -323,7 +512,7 @@ impl<SC: StarkGenericConfig, A: MachineAir<Val<SC>>> StarkMachine<SC, A> {
 
         // Obtain the challenges used for the permutation argument.
         let mut permutation_challenges: Vec<SC::Challenge> = Vec::new();
-        for _ in 0..2 {
+        for _ in 0..NUM_PERMUTATION_CHALLENGES {
             permutation_challenges.push(challenger.sample_ext_element());
         }
 
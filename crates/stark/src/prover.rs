@@ -17,9 +17,10 @@ use super::{
     VerifierConstraintFolder,
 };
 use crate::{
-    air::MachineAir, lookup::InteractionBuilder, opts::SP1CoreOpts, record::MachineRecord,
-    DebugConstraintBuilder, MachineChip, MachineProof, PackedChallenge, PcsProverData,
-    ProverConstraintFolder, ShardCommitment, ShardMainData, ShardProof, StarkVerifyingKey,
+    air::MachineAir, lookup::InteractionBuilder, observe_optional_tag, opts::SP1CoreOpts,
+    record::MachineRecord, DebugConstraintBuilder, DomainSeparationTags, MachineChip, MachineProof,
+    PackedChallenge, PcsProverData, ProverConstraintFolder, ShardCommitment, ShardMainData,
+    ShardProof, StarkVerifyingKey, NUM_PERMUTATION_CHALLENGES,
 };
 
 /// An algorithmic & hardware independent prover implementation for any [`MachineAir`].
@@ -99,6 +100,17 @@ pub trait MachineProver<SC: StarkGenericConfig, A: MachineAir<SC::Val>>:
         pk: &StarkProvingKey<SC>,
         data: ShardMainData<SC, Self::DeviceMatrix, Self::DeviceProverData>,
         challenger: &mut SC::Challenger,
+    ) -> Result<ShardProof<SC>, Self::Error> {
+        self.open_with_tags(pk, data, challenger, DomainSeparationTags::default())
+    }
+
+    /// Compute the openings of the traces, absorbing the commit phase tag if it is enabled.
+    fn open_with_tags(
+        &self,
+        pk: &StarkProvingKey<SC>,
+        data: ShardMainData<SC, Self::DeviceMatrix, Self::DeviceProverData>,
+        challenger: &mut SC::Challenger,
+        tags: DomainSeparationTags,
     ) -> Result<ShardProof<SC>, Self::Error>;
 
     /// Generate a proof for the given records.
@@ -109,6 +121,25 @@ pub trait MachineProver<SC: StarkGenericConfig, A: MachineAir<SC::Val>>:
         challenger: &mut SC::Challenger,
         opts: <A::Record as MachineRecord>::Config,
     ) -> Result<MachineProof<SC>, Self::Error>
+    where
+        A: for<'a> Air<DebugConstraintBuilder<'a, Val<SC>, SC::Challenge>>,
+    {
+        self.prove_with_tags(pk, records, challenger, opts, DomainSeparationTags::default(), false)
+    }
+
+    /// Generate a proof for the given records, absorbing the enabled domain-separation tags.
+    ///
+    /// If `chip_ordering` is set, the chip ordering is observed with the proving key, as
+    /// [`StarkProvingKey::observe_into_with_tags`] does.
+    fn prove_with_tags(
+        &self,
+        pk: &StarkProvingKey<SC>,
+        records: Vec<A::Record>,
+        challenger: &mut SC::Challenger,
+        opts: <A::Record as MachineRecord>::Config,
+        tags: DomainSeparationTags,
+        chip_ordering: bool,
+    ) -> Result<MachineProof<SC>, Self::Error>
     where
         A: for<'a> Air<DebugConstraintBuilder<'a, Val<SC>, SC::Challenge>>;
 
@@ -223,11 +254,12 @@ where
     #[allow(clippy::too_many_lines)]
     #[allow(clippy::redundant_closure_for_method_calls)]
     #[allow(clippy::map_unwrap_or)]
-    fn open(
+    fn open_with_tags(
         &self,
         pk: &StarkProvingKey<SC>,
         mut data: ShardMainData<SC, Self::DeviceMatrix, Self::DeviceProverData>,
         challenger: &mut <SC as StarkGenericConfig>::Challenger,
+        tags: DomainSeparationTags,
     ) -> Result<ShardProof<SC>, Self::Error> {
         let chips = self.machine().shard_chips_ordered(&data.chip_ordering).collect::<Vec<_>>();
         let config = self.machine().config();
@@ -248,7 +280,7 @@ where
 
         // Obtain the challenges used for the permutation argument.
         let mut permutation_challenges: Vec<SC::Challenge> = Vec::new();
-        for _ in 0..2 {
+        for _ in 0..NUM_PERMUTATION_CHALLENGES {
             permutation_challenges.push(challenger.sample_ext_element());
         }
         let packed_perm_challenges = permutation_challenges
@@ -411,6 +443,7 @@ where
         let quotient_opening_points =
             (0..num_quotient_chunks).map(|_| vec![zeta]).collect::<Vec<_>>();
 
+        observe_optional_tag::<Val<SC>, _>(challenger, tags.commit_phase);
         let (openings, opening_proof) = tracing::debug_span!("open multi batches").in_scope(|| {
             pcs.open(
                 vec![
@@ -498,12 +531,14 @@ where
     /// Given a proving key `pk` and a matching execution record `record`, this function generates
     /// a STARK proof that the execution record is valid.
     #[allow(clippy::needless_for_each)]
-    fn prove(
+    fn prove_with_tags(
         &self,
         pk: &StarkProvingKey<SC>,
         mut records: Vec<A::Record>,
         challenger: &mut SC::Challenger,
         opts: <A::Record as MachineRecord>::Config,
+        tags: DomainSeparationTags,
+        chip_ordering: bool,
     ) -> Result<MachineProof<SC>, Self::Error>
     where
         A: for<'a> Air<DebugConstraintBuilder<'a, Val<SC>, SC::Challenge>>,
@@ -512,7 +547,7 @@ where
         self.machine().generate_dependencies(&mut records, &opts);
 
         // Observe the preprocessed commitment.
-        pk.observe_into(challenger);
+        pk.observe_into_with_tags(challenger, tags, chip_ordering);
 
         // Generate and commit the traces for each shard.
         let shard_data = records
@@ -534,7 +569,7 @@ where
         let shard_proofs = tracing::info_span!("prove_shards").in_scope(|| {
             shard_data
                 .into_par_iter()
-                .map(|data| self.open(pk, data, &mut challenger.clone()))
+                .map(|data| self.open_with_tags(pk, data, &mut challenger.clone(), tags))
                 .collect::<Result<Vec<_>, _>>()
         })?;
 
@@ -0,0 +1,36 @@
+use p3_challenger::CanObserve;
+use p3_field::AbstractField;
+
+/// The tag absorbed before observing the verifying key.
+pub const VK_TAG: u32 = 1;
+/// The tag absorbed before the opening proof, whose FRI commit phase follows.
+pub const COMMIT_PHASE_TAG: u32 = 2;
+
+/// Domain-separation tags absorbed at the phase boundaries of a shard proof's transcript.
+///
+/// The prover and the verifier must absorb the same tags, so tagging is disabled by default.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct DomainSeparationTags {
+    /// The tag absorbed before the verifying key.
+    pub vk: Option<u32>,
+    /// The tag absorbed before the opening proof.
+    pub commit_phase: Option<u32>,
+}
+
+impl DomainSeparationTags {
+    /// Tags every phase with its standard tag.
+    #[must_use]
+    pub const fn standard() -> Self {
+        Self { vk: Some(VK_TAG), commit_phase: Some(COMMIT_PHASE_TAG) }
+    }
+}
+
+/// Observes `tag` into the challenger if tagging is enabled for its phase.
+pub fn observe_optional_tag<F: AbstractField, Challenger: CanObserve<F>>(
+    challenger: &mut Challenger,
+    tag: Option<u32>,
+) {
+    if let Some(tag) = tag {
+        challenger.observe(F::from_canonical_u32(tag));
+    }
+}
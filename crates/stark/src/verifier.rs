@@ -9,20 +9,48 @@ use p3_air::{Air, BaseAir};
 use p3_challenger::{CanObserve, FieldChallenger};
 use p3_commit::{LagrangeSelectors, Pcs, PolynomialSpace};
 use p3_field::{AbstractExtensionField, AbstractField};
+use p3_matrix::Dimensions;
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
 
 use super::{
     folder::VerifierConstraintFolder,
     types::{AirOpenedValues, ChipOpenedValues, ShardCommitment, ShardProof},
-    Domain, OpeningError, StarkGenericConfig, StarkVerifyingKey, Val,
+    Com, Dom, Domain, OpeningError, StarkGenericConfig, StarkVerifyingKey, Val,
 };
 use crate::{air::MachineAir, MachineChip};
 
+/// The number of challenges sampled for the permutation argument of a shard.
+pub const NUM_PERMUTATION_CHALLENGES: usize = 2;
+
+/// Everything needed to rerun the constraint check of a single chip of a shard proof.
+///
+/// The challenges are replayed from the shard's transcript, so the constraint check can be
+/// reproduced without the other chips, the commitments, or the PCS opening proof.
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(bound(serialize = "Dom<SC>: Serialize"))]
+#[serde(bound(deserialize = "Dom<SC>: DeserializeOwned"))]
+pub struct ChipReproduction<SC: StarkGenericConfig> {
+    /// The name of the chip.
+    pub chip_name: String,
+    /// The verifying key entry of the chip, if it has a preprocessed trace.
+    pub chip_information: Option<(String, Dom<SC>, Dimensions)>,
+    /// The opened values of the chip.
+    pub opened_values: ChipOpenedValues<SC::Challenge>,
+    /// The out-of-domain evaluation point.
+    pub zeta: SC::Challenge,
+    /// The constraint folding challenge.
+    pub alpha: SC::Challenge,
+    /// The permutation challenges.
+    pub permutation_challenges: Vec<SC::Challenge>,
+    /// The public values of the shard.
+    pub public_values: Vec<Val<SC>>,
+}
+
 /// A verifier for a collection of air chips.
 pub struct Verifier<SC, A>(PhantomData<SC>, PhantomData<A>);
 
 impl<SC: StarkGenericConfig, A: MachineAir<Val<SC>>> Verifier<SC, A> {
     /// Verify a proof for a collection of air chips.
-    #[allow(clippy::too_many_lines)]
     pub fn verify_shard(
         config: &SC,
         vk: &StarkVerifyingKey<SC>,
@@ -30,6 +58,42 @@ impl<SC: StarkGenericConfig, A: MachineAir<Val<SC>>> Verifier<SC, A> {
         challenger: &mut SC::Challenger,
         proof: &ShardProof<SC>,
     ) -> Result<(), VerificationError<SC>>
+    where
+        A: for<'a> Air<VerifierConstraintFolder<'a, SC>>,
+    {
+        Self::verify_shard_inner(config, vk, chips, challenger, proof, None)
+    }
+
+    /// Verify a proof for a collection of air chips, recording each check in a
+    /// [`VerificationReport`].
+    ///
+    /// Building the report costs an extra hash of the challenges, so [`Self::verify_shard`] should
+    /// be used when the report is not needed.
+    pub fn verify_shard_with_report(
+        config: &SC,
+        vk: &StarkVerifyingKey<SC>,
+        chips: &[&MachineChip<SC, A>],
+        challenger: &mut SC::Challenger,
+        proof: &ShardProof<SC>,
+    ) -> ReportedVerification<SC>
+    where
+        A: for<'a> Air<VerifierConstraintFolder<'a, SC>>,
+    {
+        let mut report = VerificationReport::new(proof.opened_values.chips.len());
+        let result =
+            Self::verify_shard_inner(config, vk, chips, challenger, proof, Some(&mut report));
+        (result, report)
+    }
+
+    #[allow(clippy::too_many_lines)]
+    fn verify_shard_inner(
+        config: &SC,
+        vk: &StarkVerifyingKey<SC>,
+        chips: &[&MachineChip<SC, A>],
+        challenger: &mut SC::Challenger,
+        proof: &ShardProof<SC>,
+        mut report: Option<&mut VerificationReport<SC>>,
+    ) -> Result<(), VerificationError<SC>>
     where
         A: for<'a> Air<VerifierConstraintFolder<'a, SC>>,
     {
@@ -46,9 +110,12 @@ impl<SC: StarkGenericConfig, A: MachineAir<Val<SC>>> Verifier<SC, A> {
 
         let pcs = config.pcs();
 
-        if chips.len() != opened_values.chips.len() {
-            return Err(VerificationError::ChipOpeningLengthMismatch);
-        }
+        let chip_count_result = if chips.len() == opened_values.chips.len() {
+            Ok(())
+        } else {
+            Err(VerificationError::ChipOpeningLengthMismatch)
+        };
+        record_check(report.as_deref_mut(), VerificationCheck::ChipCount, chip_count_result)?;
 
         let log_degrees = opened_values.chips.iter().map(|val| val.log_degree).collect::<Vec<_>>();
 
@@ -62,17 +129,16 @@ impl<SC: StarkGenericConfig, A: MachineAir<Val<SC>>> Verifier<SC, A> {
 
         let ShardCommitment { main_commit, permutation_commit, quotient_commit } = commitment;
 
-        let permutation_challenges =
-            (0..2).map(|_| challenger.sample_ext_element::<SC::Challenge>()).collect::<Vec<_>>();
-
-        challenger.observe(permutation_commit.clone());
-
-        let alpha = challenger.sample_ext_element::<SC::Challenge>();
-
-        // Observe the quotient commitments.
-        challenger.observe(quotient_commit.clone());
+        let (permutation_challenges, alpha, zeta) =
+            Self::sample_challenges(challenger, permutation_commit, quotient_commit);
 
-        let zeta = challenger.sample_ext_element::<SC::Challenge>();
+        if let Some(report) = report.as_deref_mut() {
+            let mut digest_challenger = config.challenger();
+            for challenge in permutation_challenges.iter().chain([&alpha, &zeta]) {
+                digest_challenger.observe_ext_element(*challenge);
+            }
+            report.challenges_digest = Some(digest_challenger.sample_ext_element());
+        }
 
         let preprocessed_domains_points_and_opens = vk
             .chip_information
@@ -141,7 +207,7 @@ impl<SC: StarkGenericConfig, A: MachineAir<Val<SC>>> Verifier<SC, A> {
             })
             .collect::<Vec<_>>();
 
-        config
+        let opening_result = config
             .pcs()
             .verify(
                 vec![
@@ -153,17 +219,23 @@ impl<SC: StarkGenericConfig, A: MachineAir<Val<SC>>> Verifier<SC, A> {
                 opening_proof,
                 challenger,
             )
-            .map_err(|e| VerificationError::InvalidopeningArgument(e))?;
+            .map_err(|e| VerificationError::InvalidopeningArgument(e));
+        record_check(report.as_deref_mut(), VerificationCheck::OpeningProof, opening_result)?;
 
         // Verify the constrtaint evaluations.
         for (chip, trace_domain, qc_domains, values) in
             izip!(chips.iter(), trace_domains, quotient_chunk_domains, opened_values.chips.iter(),)
         {
             // Verify the shape of the opening arguments matches the expected values.
-            Self::verify_opening_shape(chip, values)
-                .map_err(|e| VerificationError::OpeningShapeError(chip.name(), e))?;
+            let shape_result = Self::verify_opening_shape(chip, values)
+                .map_err(|e| VerificationError::OpeningShapeError(chip.name(), e));
+            record_check(
+                report.as_deref_mut(),
+                VerificationCheck::OpeningShape(chip.name()),
+                shape_result,
+            )?;
             // Verify the constraint evaluation.
-            Self::verify_constraints(
+            let constraints_result = Self::verify_constraints(
                 chip,
                 values,
                 trace_domain,
@@ -173,12 +245,113 @@ impl<SC: StarkGenericConfig, A: MachineAir<Val<SC>>> Verifier<SC, A> {
                 &permutation_challenges,
                 public_values,
             )
-            .map_err(|_| VerificationError::OodEvaluationMismatch(chip.name()))?;
+            .map_err(|_| VerificationError::OodEvaluationMismatch(chip.name()));
+            record_check(
+                report.as_deref_mut(),
+                VerificationCheck::Constraints(chip.name()),
+                constraints_result,
+            )?;
         }
 
         Ok(())
     }
 
+    /// Replays the transcript of a shard proof and extracts what is needed to rerun the
+    /// constraint check of the chip named `chip_name` in isolation.
+    ///
+    /// The `challenger` must be in the state it would be in before [`Self::verify_shard`].
+    pub fn extract_chip_reproduction(
+        vk: &StarkVerifyingKey<SC>,
+        challenger: &mut SC::Challenger,
+        proof: &ShardProof<SC>,
+        chip_name: &str,
+    ) -> Option<ChipReproduction<SC>> {
+        let index = *proof.chip_ordering.get(chip_name)?;
+
+        let (permutation_challenges, alpha, zeta) = Self::sample_challenges(
+            challenger,
+            &proof.commitment.permutation_commit,
+            &proof.commitment.quotient_commit,
+        );
+
+        Some(ChipReproduction {
+            chip_name: chip_name.to_string(),
+            chip_information: vk
+                .chip_information
+                .iter()
+                .find(|(name, _, _)| name == chip_name)
+                .cloned(),
+            opened_values: proof.opened_values.chips[index].clone(),
+            zeta,
+            alpha,
+            permutation_challenges,
+            public_values: proof.public_values.clone(),
+        })
+    }
+
+    /// Samples the permutation challenges, `alpha` and `zeta` of a shard, observing the
+    /// permutation and quotient commitments in between, as the prover does.
+    fn sample_challenges(
+        challenger: &mut SC::Challenger,
+        permutation_commit: &Com<SC>,
+        quotient_commit: &Com<SC>,
+    ) -> (Vec<SC::Challenge>, SC::Challenge, SC::Challenge) {
+        let permutation_challenges = (0..NUM_PERMUTATION_CHALLENGES)
+            .map(|_| challenger.sample_ext_element::<SC::Challenge>())
+            .collect::<Vec<_>>();
+
+        challenger.observe(permutation_commit.clone());
+
+        let alpha = challenger.sample_ext_element::<SC::Challenge>();
+
+        // Observe the quotient commitments.
+        challenger.observe(quotient_commit.clone());
+
+        let zeta = challenger.sample_ext_element::<SC::Challenge>();
+
+        (permutation_challenges, alpha, zeta)
+    }
+
+    /// Reruns the opening shape and constraint checks of a [`ChipReproduction`].
+    pub fn reproduce_chip(
+        config: &SC,
+        chip: &MachineChip<SC, A>,
+        reproduction: &ChipReproduction<SC>,
+    ) -> Result<(), VerificationError<SC>>
+    where
+        A: for<'a> Air<VerifierConstraintFolder<'a, SC>>,
+    {
+        let ChipReproduction {
+            opened_values,
+            zeta,
+            alpha,
+            permutation_challenges,
+            public_values,
+            ..
+        } = reproduction;
+
+        let log_degree = opened_values.log_degree;
+        let log_quotient_degree = chip.log_quotient_degree();
+        let trace_domain = config.pcs().natural_domain_for_degree(1 << log_degree);
+        let qc_domains = trace_domain
+            .create_disjoint_domain(1 << (log_degree + log_quotient_degree))
+            .split_domains(1 << log_quotient_degree);
+
+        Self::verify_opening_shape(chip, opened_values)
+            .map_err(|e| VerificationError::OpeningShapeError(chip.name(), e))?;
+        Self::verify_constraints(
+            chip,
+            opened_values,
+            trace_domain,
+            qc_domains,
+            *zeta,
+            *alpha,
+            permutation_challenges,
+            public_values,
+        )
+        .map_err(|_| VerificationError::OodEvaluationMismatch(chip.name()))
+    }
+
     fn verify_opening_shape(
         chip: &MachineChip<SC, A>,
         opening: &ChipOpenedValues<SC::Challenge>,
@@ -478,3 +651,87 @@ impl<SC: StarkGenericConfig> Display for VerificationError<SC> {
 }
 
 impl<SC: StarkGenericConfig> std::error::Error for VerificationError<SC> {}
+
+/// A check run while verifying a shard proof.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum VerificationCheck {
+    /// The proof opens as many chips as the shard has.
+    ChipCount,
+    /// The PCS opening proof is valid.
+    OpeningProof,
+    /// The openings of a chip have the expected shape.
+    OpeningShape(String),
+    /// The constraints of a chip hold at the out-of-domain point.
+    Constraints(String),
+}
+
+impl Display for VerificationCheck {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        match self {
+            VerificationCheck::ChipCount => write!(f, "chip count"),
+            VerificationCheck::OpeningProof => write!(f, "opening proof"),
+            VerificationCheck::OpeningShape(chip) => write!(f, "opening shape of chip {chip}"),
+            VerificationCheck::Constraints(chip) => write!(f, "constraints of chip {chip}"),
+        }
+    }
+}
+
+/// The outcome of [`Verifier::verify_shard_with_report`] together with its report.
+pub type ReportedVerification<SC> = (Result<(), VerificationError<SC>>, VerificationReport<SC>);
+
+/// The checks run by [`Verifier::verify_shard_with_report`], for debugging and monitoring.
+pub struct VerificationReport<SC: StarkGenericConfig> {
+    /// The checks that passed, in the order they ran.
+    pub passed_checks: Vec<VerificationCheck>,
+    /// The check that failed and why, if verification failed.
+    pub failed_check: Option<(VerificationCheck, String)>,
+    /// The number of chips opened by the shard proof.
+    pub num_chips: usize,
+    /// A hash of the permutation challenges, `alpha` and `zeta`, if verification reached the point
+    /// where they are sampled.
+    pub challenges_digest: Option<SC::Challenge>,
+}
+
+impl<SC: StarkGenericConfig> VerificationReport<SC> {
+    fn new(num_chips: usize) -> Self {
+        Self { passed_checks: Vec::new(), failed_check: None, num_chips, challenges_digest: None }
+    }
+
+    /// Whether every check that ran passed.
+    #[must_use]
+    pub fn is_success(&self) -> bool {
+        self.failed_check.is_none()
+    }
+}
+
+impl<SC: StarkGenericConfig> Display for VerificationReport<SC> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        let status = if self.is_success() { "passed" } else { "failed" };
+        writeln!(f, "shard verification {status} ({} chips)", self.num_chips)?;
+        if let Some(digest) = &self.challenges_digest {
+            writeln!(f, "  challenges digest: {digest:?}")?;
+        }
+        for check in &self.passed_checks {
+            writeln!(f, "  passed: {check}")?;
+        }
+        if let Some((check, reason)) = &self.failed_check {
+            writeln!(f, "  failed: {check}: {reason}")?;
+        }
+        Ok(())
+    }
+}
+
+/// Records the outcome of `check` in `report`, if there is one, and passes `result` through.
+fn record_check<SC: StarkGenericConfig>(
+    report: Option<&mut VerificationReport<SC>>,
+    check: VerificationCheck,
+    result: Result<(), VerificationError<SC>>,
+) -> Result<(), VerificationError<SC>> {
+    if let Some(report) = report {
+        match &result {
+            Ok(()) => report.passed_checks.push(check),
+            Err(error) => report.failed_check = Some((check, error.to_string())),
+        }
+    }
+    result
+}